@@ -2,6 +2,7 @@ use crate::msg::{Contracts, Fees, InstantiateMsg};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{ensure, Addr, Api, Decimal, StdResult, Storage, Uint128};
 use cw_storage_plus::Item;
+use wenruji_rs::DecayCurve;
 
 use crate::ContractError;
 
@@ -18,6 +19,32 @@ pub struct Config {
    pub fees: Vec<(Addr, Decimal)>,
    pub winner_share: Decimal,
    pub donation_addrs: Vec<Addr>,
+   pub decay_curve: DecayCurve,
+   pub winner_rule: WinnerRule,
+   /// Share of the pot paid to whoever settles a finished round
+   pub settlement_fee: Decimal,
+   /// Denoms accepted on `Join` besides the ticket denom, swapped to it through
+   /// `contracts.swap`
+   pub entry_denoms: Vec<String>,
+   /// Largest stake of an account, `ticket_amount` being the smallest. Stakes are exactly
+   /// `ticket_amount` when `None`.
+   pub max_ticket_amount: Option<Uint128>,
+}
+
+/// On-chain rule used to pick the vault winner once the decay has ended
+#[cw_serde]
+#[derive(Default)]
+pub enum WinnerRule {
+   /// The last account to join before `decay_ends_at` that has not exited
+   #[default]
+   LastJoin,
+   /// The first account to join that has not exited, the one that held the longest
+   LastStanding,
+   /// Stake-weighted draw among the accounts that have not exited. Players commit a hash on
+   /// `Join` and reveal it during the `reveal_seconds` following `decay_ends_at`. Accounts that
+   /// reveal in time get `reveal_bond` of their stake back at settlement, the others are left
+   /// out of the draw and forfeit it to the prize.
+   Lottery { reveal_seconds: u64, reveal_bond: Decimal },
 }
 
 impl Config {
@@ -42,6 +69,11 @@ impl Config {
          donation_addrs: msg.donation_addrs,
          winner_share,
          fees,
+         decay_curve: msg.decay_curve.unwrap_or_default(),
+         winner_rule: msg.winner_rule.unwrap_or_default(),
+         settlement_fee: msg.settlement_fee.unwrap_or_default(),
+         entry_denoms: msg.entry_denoms.unwrap_or_default(),
+         max_ticket_amount: msg.max_ticket_amount,
       })
    }
 
@@ -68,6 +100,26 @@ impl Config {
          self.ticket_amount.gt(&Uint128::zero()),
          ContractError::Invalid("ticket_amount".to_string())
       );
+      ensure!(
+         self.max_ticket_amount.is_none_or(|max| max >= self.ticket_amount),
+         ContractError::Invalid("max_ticket_amount".to_string())
+      );
+      self.decay_curve.validate()?;
+      if let WinnerRule::Lottery { reveal_seconds, reveal_bond } = self.winner_rule {
+         ensure!(reveal_seconds > 0, ContractError::Invalid("reveal_seconds".to_string()));
+         ensure!(
+            !reveal_bond.is_zero() && reveal_bond < Decimal::one(),
+            ContractError::Invalid("reveal_bond".to_string())
+         );
+      }
+      ensure!(
+         self.settlement_fee < Decimal::one(),
+         ContractError::Invalid("settlement_fee".to_string())
+      );
+      ensure!(
+         !self.entry_denoms.contains(&self.ticket_denom),
+         ContractError::Invalid("entry_denoms".to_string())
+      );
       Ok(())
    }
 
@@ -100,6 +152,26 @@ impl Config {
          self.game_delay = game_delay;
       }
 
+      if let Some(decay_curve) = msg.decay_curve {
+         self.decay_curve = decay_curve;
+      }
+
+      if let Some(winner_rule) = msg.winner_rule {
+         self.winner_rule = winner_rule;
+      }
+
+      if let Some(settlement_fee) = msg.settlement_fee {
+         self.settlement_fee = settlement_fee;
+      }
+
+      if let Some(entry_denoms) = msg.entry_denoms {
+         self.entry_denoms = entry_denoms;
+      }
+
+      if let Some(max_ticket_amount) = msg.max_ticket_amount {
+         self.max_ticket_amount = Some(max_ticket_amount).filter(|max| !max.is_zero());
+      }
+
       if let Some(fees) = msg.fees {
          self.fees = vec![
             (fees.fee_platform.address, fees.fee_platform.fee),
@@ -117,6 +189,18 @@ impl Config {
    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
       CONFIG.save(storage, self)
    }
+
+   /// Largest stake of an account
+   pub fn max_stake(&self) -> Uint128 {
+      self.max_ticket_amount.unwrap_or(self.ticket_amount)
+   }
+
+   /// Checks that `stake` is within the ticket range
+   pub fn check_stake(&self, stake: Uint128) -> Result<(), ContractError> {
+      ensure!(stake >= self.ticket_amount, ContractError::InsufficientFunds {});
+      ensure!(stake <= self.max_stake(), ContractError::Invalid("max_ticket_amount".to_string()));
+      Ok(())
+   }
 }
 
 #[cw_serde]
@@ -130,6 +214,12 @@ pub struct ConfigUpdate {
    pub donation_addrs: Option<Vec<Addr>>,
    pub admins: Option<Vec<Addr>>,
    pub fees: Option<Fees>,
+   pub decay_curve: Option<DecayCurve>,
+   pub winner_rule: Option<WinnerRule>,
+   pub settlement_fee: Option<Decimal>,
+   pub entry_denoms: Option<Vec<String>>,
+   /// Zero clears the cap back to the ticket amount
+   pub max_ticket_amount: Option<Uint128>,
 }
 
 #[cfg(test)]
@@ -161,6 +251,11 @@ mod tests {
          starts_at: Timestamp::from_seconds(0),
          game_delay: 60,
          admins: None,
+         decay_curve: None,
+         winner_rule: None,
+         settlement_fee: None,
+         entry_denoms: None,
+         max_ticket_amount: None,
       };
 
       let config = Config::new(msg).unwrap();
@@ -171,6 +266,8 @@ mod tests {
       assert_eq!(config.donation_addrs.len(), 2);
       assert_eq!(config.fees.len(), 3);
       assert_eq!(config.winner_share, Decimal::percent(82)); // 100% - 10% - 5% - 3%
+      assert_eq!(config.decay_curve, DecayCurve::Linear);
+      assert_eq!(config.winner_rule, WinnerRule::LastJoin);
    }
 
    #[test]
@@ -193,6 +290,11 @@ mod tests {
          starts_at: Timestamp::from_seconds(0),
          game_delay: 60,
          admins: None,
+         decay_curve: None,
+         winner_rule: None,
+         settlement_fee: None,
+         entry_denoms: None,
+         max_ticket_amount: None,
       };
 
       let config = Config::new(msg);
@@ -220,11 +322,44 @@ mod tests {
          starts_at: Timestamp::from_seconds(0),
          game_delay: 60,
          admins: None,
+         decay_curve: None,
+         winner_rule: None,
+         settlement_fee: None,
+         entry_denoms: None,
+         max_ticket_amount: None,
       };
 
-      let config = Config::new(msg).unwrap();
+      let mut config = Config::new(msg).unwrap();
       let validation_result = config.validate(&api);
-      assert!(validation_result.is_ok())
+      assert!(validation_result.is_ok());
+
+      let lottery = |reveal_seconds, reveal_bond| WinnerRule::Lottery {
+         reveal_seconds,
+         reveal_bond: Decimal::percent(reveal_bond),
+      };
+      config.winner_rule = lottery(0, 10);
+      config.validate(&api).unwrap_err();
+      config.winner_rule = lottery(60, 0);
+      config.validate(&api).unwrap_err();
+      config.winner_rule = lottery(60, 100);
+      config.validate(&api).unwrap_err();
+      config.winner_rule = lottery(60, 10);
+      config.validate(&api).unwrap();
+
+      config.settlement_fee = Decimal::one();
+      config.validate(&api).unwrap_err();
+      config.settlement_fee = Decimal::percent(1);
+      config.validate(&api).unwrap();
+
+      config.entry_denoms = vec![config.ticket_denom.clone()];
+      config.validate(&api).unwrap_err();
+      config.entry_denoms = vec!["uusk".to_string()];
+      config.validate(&api).unwrap();
+
+      config.max_ticket_amount = Some(config.ticket_amount - Uint128::one());
+      config.validate(&api).unwrap_err();
+      config.max_ticket_amount = Some(config.ticket_amount);
+      config.validate(&api).unwrap();
    }
 
    #[test]
@@ -247,6 +382,11 @@ mod tests {
          starts_at: Timestamp::from_seconds(0),
          game_delay: 60,
          admins: None,
+         decay_curve: None,
+         winner_rule: None,
+         settlement_fee: None,
+         entry_denoms: None,
+         max_ticket_amount: None,
       };
 
       let mut config = Config::new(msg).unwrap();
@@ -265,15 +405,29 @@ mod tests {
             fee_ref: Fee { address: Addr::unchecked("referral"), fee: Decimal::percent(2) },
          }),
          admins: None,
+         decay_curve: Some(DecayCurve::Step { steps: 5 }),
+         winner_rule: Some(WinnerRule::LastStanding),
+         settlement_fee: None,
+         entry_denoms: None,
+         max_ticket_amount: Some(Uint128::new(500)),
       };
 
-      config.apply_update(update).unwrap();
+      config.apply_update(update.clone()).unwrap();
       assert_eq!(config.owner, Addr::unchecked("new_owner"));
       assert_eq!(config.ticket_denom, "newtoken");
       assert_eq!(config.ticket_amount, Uint128::new(200));
       assert_eq!(config.duration_seconds, 3600); // Unchanged
       assert_eq!(config.fees.len(), 3);
       assert_eq!(config.winner_share, Decimal::percent(86)); // Updated to 100% - 8% - 4% - 2%
+      assert_eq!(config.decay_curve, DecayCurve::Step { steps: 5 });
+      assert_eq!(config.winner_rule, WinnerRule::LastStanding);
+      assert_eq!(config.max_ticket_amount, Some(Uint128::new(500)));
+
+      // zero clears the cap
+      config
+         .apply_update(ConfigUpdate { max_ticket_amount: Some(Uint128::zero()), ..update })
+         .unwrap();
+      assert_eq!(config.max_ticket_amount, None);
    }
 
    #[test]
@@ -297,6 +451,11 @@ mod tests {
          starts_at: Timestamp::from_seconds(0),
          game_delay: 60,
          admins: None,
+         decay_curve: None,
+         winner_rule: None,
+         settlement_fee: None,
+         entry_denoms: None,
+         max_ticket_amount: None,
       };
 
       let config = Config::new(msg).unwrap();
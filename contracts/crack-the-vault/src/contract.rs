@@ -1,20 +1,28 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-   ensure, ensure_eq, to_json_binary, wasm_execute, BankMsg, Binary, Coin, Decimal, Deps, DepsMut,
-   Empty, Env, Event, MessageInfo, Response,
+   ensure, ensure_eq, from_json, to_json_binary, wasm_execute, Addr, BankMsg, Binary, Coin, Deps,
+   DepsMut, Empty, Env, Event, MessageInfo, Reply, Response, StdResult, Timestamp, Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{ensure_from_older_version, set_contract_version};
+use cw_storage_plus::Bound;
 use cw_utils::{must_pay, nonpayable, one_coin, PaymentError};
-use kujira::CallbackData;
-use wenruji_rs::{DecayGame, DecayGameError};
+use wenruji_rs::{
+   keeper::{KeeperMsg, NextAction},
+   page_limit, DecayGame, DecayGameError,
+};
 
-use crate::config::Config;
+use crate::config::{Config, WinnerRule};
 use crate::error::ContractError;
-use crate::msg::{CallbackType, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::migrations::migrate_state;
+use crate::msg::{CallbackType, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, WinnerResponse};
 use crate::state::{
-   execute_donate, execute_endgame, execute_exit, execute_join, execute_post_swap, execute_ref,
-   execute_restart, ACCOUNTS, ADMIN, DECAY_GAME, REF_WEIGHTS, REWARDS,
+   execute_commit, execute_donate, execute_entry_refund, execute_entry_swap, execute_exit,
+   execute_exit_partial, execute_join, execute_post_swap, execute_prize_fallback, execute_ref,
+   execute_restart, execute_reveal, execute_rollover, execute_settle, execute_top_up,
+   select_winner, settles_at, PendingEntry, RoundSummary, ACCOUNTS, ADMIN, DECAY_GAME,
+   ENTRY_SWAP_REPLY, PENDING_ENTRY, PLAYER_STATS, PRIZE_SWAP_REPLY, RANDOMNESS, REF_WEIGHTS,
+   REWARDS, ROUNDS, ROUND_IDX,
 };
 
 // version info for migration info
@@ -35,8 +43,10 @@ pub fn instantiate(
    config.save(deps.storage)?;
 
    let decay_game: DecayGame =
-      DecayGame::new(msg.starts_at, msg.starts_at.plus_seconds(msg.duration_seconds));
+      DecayGame::new(msg.starts_at, msg.starts_at.plus_seconds(msg.duration_seconds))
+         .with_curve(config.decay_curve.clone());
    DECAY_GAME.save(deps.storage, &decay_game)?;
+   ROUND_IDX.save(deps.storage, &1)?;
 
    ADMIN.save(deps.storage, &msg.owner, &Empty {})?;
    if let Some(admins) = msg.admins {
@@ -59,32 +69,43 @@ pub fn execute(
    let mut config = Config::load(deps.storage)?;
    let time = env.block.time;
    match msg {
-      ExecuteMsg::Join { ref_code } => {
-         let amount = must_pay(&info, &config.ticket_denom)?;
-         ensure_eq!(amount, config.ticket_amount, ContractError::InsufficientFunds {});
-
-         ensure!(
-            !ACCOUNTS.has(deps.storage, info.sender.clone()),
-            ContractError::Invalid("already_joined".to_string())
-         );
-         let mut response = Response::new();
-
-         let (ambassador, ref_msg) =
-            execute_ref(deps.api, deps.storage, deps.querier, &config, &info.sender, ref_code)?;
-         if let Some(msg) = ref_msg {
-            response = response.add_message(msg);
+      ExecuteMsg::Join { ref_code, commitment } => {
+         let payment = one_coin(&info)?;
+         if payment.denom == config.ticket_denom {
+            config.check_stake(payment.amount)?;
          }
 
-         execute_join(deps.storage, time, &info.sender, amount)?;
+         // the first join after a finished round settles it and starts the next one
+         let response =
+            execute_rollover(deps.storage, time, &config, &info.sender)?.unwrap_or_default();
+
+         if payment.denom != config.ticket_denom {
+            ensure!(
+               !ACCOUNTS.has(deps.storage, info.sender.clone()),
+               ContractError::Invalid("already_joined".to_string())
+            );
+            let entry = PendingEntry {
+               account: info.sender.clone(),
+               offer: payment.clone(),
+               ref_code,
+               commitment,
+            };
+            let swap = execute_entry_swap(deps.storage, &config, entry)?;
+            return Ok(response.add_submessage(swap).add_event(
+               Event::new("crack-the-valut/entry_swap")
+                  .add_attribute("account", info.sender)
+                  .add_attribute("offer", payment.to_string()),
+            ));
+         }
 
-         Ok(response.add_event(
-            Event::new("crack-the-valut/join")
-               .add_attribute("account", info.sender)
-               .add_attribute("ambassador", ambassador.unwrap_or_default()),
-         ))
+         join(deps, time, &config, &info.sender, payment.amount, ref_code, commitment, response)
       }
       ExecuteMsg::Exit {} => {
          nonpayable(&info)?;
+         // the round is over, exiting settles it instead
+         if let Some(response) = execute_rollover(deps.storage, time, &config, &info.sender)? {
+            return Ok(response);
+         }
          //amount cannot be zero
          let (amount, decay_snap) = execute_exit(deps.storage, time, &info.sender)?;
 
@@ -99,50 +120,97 @@ pub fn execute(
                .add_attribute("decay_snap", decay_snap.to_string()),
          ))
       }
-      ExecuteMsg::EndGame { winner, restart } => {
+      ExecuteMsg::ExitPartial { amount } => {
+         nonpayable(&info)?;
+         // the round is over, exiting settles it instead
+         if let Some(response) = execute_rollover(deps.storage, time, &config, &info.sender)? {
+            return Ok(response);
+         }
+         let (paid, decay_snap) =
+            execute_exit_partial(deps.storage, time, &config, &info.sender, amount)?;
+
+         let msg = BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(paid, config.ticket_denom.clone())],
+         };
+
+         Ok(Response::new().add_message(msg).add_event(
+            Event::new("crack-the-valut/exit_partial")
+               .add_attribute("account", info.sender)
+               .add_attribute("amount", amount)
+               .add_attribute("paid", paid)
+               .add_attribute("decay_snap", decay_snap.to_string()),
+         ))
+      }
+      ExecuteMsg::TopUp {} => {
+         let amount = must_pay(&info, &config.ticket_denom)?;
+         let stake = execute_top_up(deps.storage, time, &config, &info.sender, amount)?;
+
+         Ok(Response::new().add_event(
+            Event::new("crack-the-valut/top_up")
+               .add_attribute("account", info.sender)
+               .add_attribute("amount", amount)
+               .add_attribute("stake", stake),
+         ))
+      }
+      ExecuteMsg::Reveal { secret } => {
+         nonpayable(&info)?;
+         execute_reveal(deps.storage, time, &config, &info.sender, secret)?;
+
+         Ok(Response::new()
+            .add_event(Event::new("crack-the-valut/reveal").add_attribute("account", info.sender)))
+      }
+      ExecuteMsg::EndGame { restart } => {
          nonpayable(&info)?;
          ensure!(ADMIN.has(deps.storage, &info.sender), ContractError::Unauthorized {});
 
-         let end_game = execute_endgame(deps.storage, time);
-         match end_game {
-            Ok(amount) => {
-               let cb = CallbackType::PostSwap { winner: winner.clone(), restart };
-               let cb_data = to_json_binary(&cb)?;
-
-               let swap_msg = kujira::fin::ExecuteMsg::Swap {
-                  offer_asset: Some(Coin { denom: config.ticket_denom.clone(), amount }),
-                  belief_price: None,
-                  max_spread: None,
-                  to: None,
-                  callback: Some(CallbackData(cb_data)),
-               };
-
-               let wasm_msg = wasm_execute(
-                  config.contracts.swap,
-                  &swap_msg,
-                  vec![Coin::new(amount, config.ticket_denom.clone())],
-               )?;
-               return Ok(Response::new().add_message(wasm_msg).add_event(
-                  Event::new("crack-the-valut/end_game")
-                     .add_attribute("winner", winner)
-                     .add_attribute("prize_amount_before", amount)
-                     .add_attribute("prize_denom_before", config.ticket_denom.clone()),
-               ));
+         if let WinnerRule::Lottery { reveal_seconds, .. } = config.winner_rule {
+            let decay_game = DECAY_GAME.load(deps.storage)?;
+            ensure!(
+               time.gt(&decay_game.decay_ends_at.plus_seconds(reveal_seconds)),
+               ContractError::Invalid("reveal_pending".to_string())
+            );
+         }
+
+         match execute_settle(deps.storage, time, &config, &info.sender, restart) {
+            Ok((mut response, swapped)) => {
+               // without a swap there is no callback to restart from
+               if restart && !swapped {
+                  response = response.add_message(wasm_execute(
+                     env.contract.address,
+                     &ExecuteMsg::Restart {},
+                     vec![],
+                  )?);
+               }
+               Ok(response)
             }
             Err(ContractError::DecayGameError(DecayGameError::NoRewards {})) => {
                let restart_msg =
                   wasm_execute(env.contract.address, &ExecuteMsg::Restart {}, vec![])?;
-               return Ok(Response::new().add_message(restart_msg).add_event(
+               Ok(Response::new().add_message(restart_msg).add_event(
                   Event::new("crack-the-valut/end_game")
                      .add_attribute("winner", "")
                      .add_attribute("prize_amount_before", "")
                      .add_attribute("prize_denom_before", ""),
-               ));
+               ))
             }
 
-            Err(err) => return Err(err),
+            Err(err) => Err(err),
          }
       }
+      ExecuteMsg::Settle {} => {
+         nonpayable(&info)?;
+         let (response, settled) =
+            match execute_rollover(deps.storage, time, &config, &info.sender)? {
+               Some(response) => (response, true),
+               None => (Response::new(), false),
+            };
+         Ok(response.add_event(
+            Event::new("crack-the-valut/settle")
+               .add_attribute("settler", info.sender)
+               .add_attribute("settled", settled.to_string()),
+         ))
+      }
       ExecuteMsg::Donate {} => {
          let decay_game = DECAY_GAME.load(deps.storage)?;
 
@@ -179,7 +247,7 @@ pub fn execute(
 
          let decay_game: DecayGame = DECAY_GAME.load(deps.storage)?;
 
-         if !(decay_game.rewards == decay_game.total - decay_game.exited) {
+         if decay_game.rewards != decay_game.total - decay_game.exited {
             return Err(ContractError::GameNotEnded {});
          }
 
@@ -199,13 +267,13 @@ pub fn execute(
       ExecuteMsg::Callback(cb) => {
          let msg: CallbackType = cb.deserialize_callback()?;
          match msg {
-            CallbackType::PostSwap { winner, restart } => {
+            CallbackType::PostSwap { restart } => {
                ensure!(
                   info.sender == config.contracts.swap,
                   ContractError::Invalid("sender".to_string())
                );
                let coin = one_coin(&info)?;
-               let mut response = execute_post_swap(deps.storage, &config, winner, coin.clone())?;
+               let mut response = execute_post_swap(deps.storage, &config, coin.clone())?;
 
                if restart {
                   response = response.add_message(wasm_execute(
@@ -221,11 +289,113 @@ pub fn execute(
                      .add_attribute("prize_denom_after", coin.denom.clone()),
                ))
             }
+            CallbackType::PostEntrySwap {} => {
+               ensure!(
+                  info.sender == config.contracts.swap,
+                  ContractError::Invalid("sender".to_string())
+               );
+               let coin = one_coin(&info)?;
+               let entry = PENDING_ENTRY.load(deps.storage)?;
+               PENDING_ENTRY.remove(deps.storage);
+               // failing here reverts the swap, the reply refunds the offer
+               ensure!(
+                  coin.denom == config.ticket_denom && coin.amount >= config.ticket_amount,
+                  ContractError::Invalid("entry_slippage".to_string())
+               );
+
+               let mut response = Response::new();
+               let amount = coin.amount.min(config.max_stake());
+               let change = coin.amount - amount;
+               if !change.is_zero() {
+                  response = response.add_message(BankMsg::Send {
+                     to_address: entry.account.to_string(),
+                     amount: vec![Coin::new(change, config.ticket_denom.clone())],
+                  });
+               }
+               join(
+                  deps,
+                  time,
+                  &config,
+                  &entry.account,
+                  amount,
+                  entry.ref_code,
+                  entry.commitment,
+                  response,
+               )
+            }
          }
       }
    }
 }
 
+#[allow(clippy::too_many_arguments)]
+fn join(
+   deps: DepsMut,
+   time: Timestamp,
+   config: &Config,
+   account: &Addr,
+   amount: Uint128,
+   ref_code: Option<String>,
+   commitment: Option<Binary>,
+   mut response: Response,
+) -> Result<Response, ContractError> {
+   ensure!(
+      !ACCOUNTS.has(deps.storage, account.clone()),
+      ContractError::Invalid("already_joined".to_string())
+   );
+
+   let (ambassador, ref_msg) =
+      execute_ref(deps.api, deps.storage, deps.querier, config, account, ref_code)?;
+   if let Some(msg) = ref_msg {
+      response = response.add_message(msg);
+   }
+
+   execute_join(deps.storage, time, account, amount)?;
+   execute_commit(deps.storage, config, account, commitment)?;
+
+   Ok(response.add_event(
+      Event::new("crack-the-valut/join")
+         .add_attribute("account", account)
+         .add_attribute("ambassador", ambassador.unwrap_or_default()),
+   ))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+   match msg.id {
+      ENTRY_SWAP_REPLY => {
+         execute_entry_refund(deps.storage, msg.result.into_result().err().unwrap_or_default())
+      }
+      PRIZE_SWAP_REPLY => {
+         let config = Config::load(deps.storage)?;
+         let callback: CallbackType = from_json(&msg.payload)?;
+         let error = msg.result.into_result().err().unwrap_or_default();
+         let mut response = execute_prize_fallback(deps.storage, &config, error)?;
+         if let CallbackType::PostSwap { restart: true } = callback {
+            response = response.add_message(wasm_execute(
+               env.contract.address,
+               &ExecuteMsg::Restart {},
+               vec![],
+            )?);
+         }
+         Ok(response)
+      }
+      id => Err(ContractError::Invalid(format!("reply_id: {id}"))),
+   }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+   let stored = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+   migrate_state(deps.storage, &stored, env.block.time)?;
+
+   Ok(Response::new().add_event(
+      Event::new("crack-the-valut/migrate")
+         .add_attribute("from_version", stored.to_string())
+         .add_attribute("to_version", CONTRACT_VERSION),
+   ))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
    match msg {
@@ -233,11 +403,18 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractErr
       QueryMsg::HasJoined { player } => Ok(to_json_binary(&ACCOUNTS.has(deps.storage, player))?),
       QueryMsg::HasExited { player } => {
          let account = ACCOUNTS.load(deps.storage, player)?;
-         Ok(to_json_binary(&(account.decay_snapshot != Decimal::zero()))?)
+         Ok(to_json_binary(&!account.is_active())?)
       }
-      QueryMsg::Donations {} => {
+      QueryMsg::Account { addr } => Ok(to_json_binary(&ACCOUNTS.may_load(deps.storage, addr)?)?),
+      QueryMsg::Donations { start_after, limit } => {
          let rewards: Vec<Coin> = REWARDS
-            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .range(
+               deps.storage,
+               start_after.map(Bound::exclusive),
+               None,
+               cosmwasm_std::Order::Ascending,
+            )
+            .take(page_limit(limit))
             .filter_map(|item| item.ok().map(|(_, coin)| coin))
             .collect();
          Ok(to_json_binary(&rewards)?)
@@ -246,6 +423,37 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractErr
          Ok(to_json_binary(&REF_WEIGHTS.may_load(deps.storage, player)?)?)
       }
       QueryMsg::Config {} => Ok(to_json_binary(&Config::load(deps.storage)?)?),
+      QueryMsg::Winner {} => {
+         let config = Config::load(deps.storage)?;
+         let winner = select_winner(deps.storage, &config.winner_rule)?;
+         Ok(to_json_binary(&WinnerResponse { rule: config.winner_rule, winner })?)
+      }
+      QueryMsg::Randomness {} => {
+         Ok(to_json_binary(&RANDOMNESS.may_load(deps.storage)?.unwrap_or_default())?)
+      }
+      QueryMsg::RoundHistory { start_after, limit } => {
+         let rounds: Vec<(u64, RoundSummary)> = ROUNDS
+            .range(
+               deps.storage,
+               None,
+               start_after.map(Bound::exclusive),
+               cosmwasm_std::Order::Descending,
+            )
+            .take(page_limit(limit))
+            .collect::<StdResult<_>>()?;
+         Ok(to_json_binary(&rounds)?)
+      }
+      QueryMsg::Round { idx } => Ok(to_json_binary(&ROUNDS.may_load(deps.storage, idx)?)?),
+      QueryMsg::PlayerStats { addr } => {
+         Ok(to_json_binary(&PLAYER_STATS.load(deps.storage, &addr)?)?)
+      }
+      QueryMsg::NextAction {} => {
+         let config = Config::load(deps.storage)?;
+         Ok(to_json_binary(&NextAction {
+            at: settles_at(deps.storage, &config)?.plus_seconds(1),
+            msg: KeeperMsg::Settle {},
+         })?)
+      }
    }
 }
 
@@ -1,7 +1,7 @@
 use cosmwasm_std::{ConversionOverflowError, OverflowError, StdError};
 use cw_utils::PaymentError;
 use thiserror::Error;
-use wenruji_rs::DecayGameError;
+use wenruji_rs::{randomness::RandomnessError, DecayGameError};
 
 #[derive(Error, Debug)]
 pub enum ContractError {
@@ -31,6 +31,9 @@ pub enum ContractError {
 
    #[error("{0}")]
    DecayGameError(#[from] DecayGameError),
+
+   #[error("{0}")]
+   RandomnessError(#[from] RandomnessError),
    // Add any other custom errors you like here.
    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -1,6 +1,7 @@
 pub mod config;
 pub mod contract;
 mod error;
+pub mod migrations;
 pub mod msg;
 pub mod state;
 
@@ -0,0 +1,77 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Order, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::Item;
+use semver::Version;
+use wenruji_rs::{AccountStatus, DecayCurve};
+
+use crate::{
+   config::{Config, WinnerRule},
+   msg::Contracts,
+   state::{record_join, ACCOUNTS, ROUND_IDX},
+   ContractError,
+};
+
+/// Config layout of the v0.1 releases, before the decay curve and winner rule were added
+#[cw_serde]
+pub struct ConfigV0_1 {
+   pub owner: Addr,
+   pub ticket_denom: String,
+   pub ticket_amount: Uint128,
+   pub duration_seconds: u64,
+   pub game_delay: u64,
+   pub contracts: Contracts,
+   pub fees: Vec<(Addr, Decimal)>,
+   pub winner_share: Decimal,
+   pub donation_addrs: Vec<Addr>,
+}
+
+pub static CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+
+/// Applies in order every state transform between the stored version and the current one
+pub fn migrate_state(
+   storage: &mut dyn Storage,
+   stored: &Version,
+   now: Timestamp,
+) -> Result<(), ContractError> {
+   if stored.major == 0 && stored.minor < 2 {
+      migrate_v0_1(storage, now)?;
+   }
+   Ok(())
+}
+
+fn migrate_v0_1(storage: &mut dyn Storage, now: Timestamp) -> Result<(), ContractError> {
+   let old = CONFIG_V0_1.load(storage)?;
+   let config = Config {
+      owner: old.owner,
+      ticket_denom: old.ticket_denom,
+      ticket_amount: old.ticket_amount,
+      duration_seconds: old.duration_seconds,
+      game_delay: old.game_delay,
+      contracts: old.contracts,
+      fees: old.fees,
+      winner_share: old.winner_share,
+      donation_addrs: old.donation_addrs,
+      decay_curve: DecayCurve::Linear,
+      winner_rule: WinnerRule::LastJoin,
+      settlement_fee: Decimal::zero(),
+      entry_denoms: vec![],
+      max_ticket_amount: None,
+   };
+   config.save(storage)?;
+   // rounds before the upgrade are not archived
+   ROUND_IDX.save(storage, &1)?;
+
+   // v0.1 did not record the join order, accounts of the live round are replayed by address
+   let accounts: Vec<Addr> =
+      ACCOUNTS.keys(storage, None, None, Order::Ascending).collect::<StdResult<_>>()?;
+   for account in accounts {
+      record_join(storage, &account)?;
+      // v0.1 claimed on exit without recording when, the migration time stands in for it
+      let mut account_data = ACCOUNTS.load(storage, account.clone())?;
+      if !account_data.decay_snapshot.is_zero() {
+         account_data.status = AccountStatus::Claimed { at: now };
+         ACCOUNTS.save(storage, account, &account_data)?;
+      }
+   }
+   Ok(())
+}
@@ -1,8 +1,14 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
-use wenruji_rs::DecayGame;
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Timestamp, Uint128};
+use wenruji_rs::{
+   keeper::NextAction, randomness::CommitReveal, DecayCurve, DecayGame, DecayGameAccount,
+   PlayerStats,
+};
 
-use crate::config::{Config, ConfigUpdate};
+use crate::{
+   config::{Config, ConfigUpdate, WinnerRule},
+   state::RoundSummary,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -16,19 +22,56 @@ pub struct InstantiateMsg {
    pub donation_addrs: Vec<Addr>,
    pub admins: Option<Vec<Addr>>,
    pub fees: Fees,
+   pub decay_curve: Option<DecayCurve>,
+   pub winner_rule: Option<WinnerRule>,
+   /// Share of the pot paid to whoever settles a finished round, defaults to zero
+   pub settlement_fee: Option<Decimal>,
+   /// Denoms accepted on `Join` besides the ticket denom, defaults to none
+   pub entry_denoms: Option<Vec<String>>,
+   /// Largest stake of an account, stakes are exactly `ticket_amount` when not set
+   pub max_ticket_amount: Option<Uint128>,
 }
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
-   Join { ref_code: Option<String> },
+   /// `commitment` is required by the lottery winner rule, see `wenruji_rs::randomness`.
+   /// Paid in an entry denom the funds are swapped to the ticket denom first: the stake is capped
+   /// to `max_ticket_amount`, the change is sent back and the funds are refunded when the swap
+   /// returns less than a ticket.
+   Join {
+      ref_code: Option<String>,
+      commitment: Option<Binary>,
+   },
    Donate {},
    Exit {},
-   EndGame { winner: Addr, restart: bool },
+   /// Takes `amount` of the stake out at the current decay and keeps the rest, at least a ticket,
+   /// in play
+   ExitPartial {
+      amount: Uint128,
+   },
+   /// Adds the funds to the stake of the sender before the decay starts, up to
+   /// `max_ticket_amount`
+   TopUp {},
+   Reveal {
+      secret: Binary,
+   },
+   EndGame {
+      restart: bool,
+   },
+   /// Settles the round once it is over and starts the next one, open to anyone and a no-op
+   /// while the round or its reveal window is live. The sender is paid the settlement fee.
+   Settle {},
    Restart {},
-   UpdateConfig { new_config: ConfigUpdate },
+   UpdateConfig {
+      new_config: ConfigUpdate,
+   },
    Callback(kujira::CallbackMsg),
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -41,19 +84,55 @@ pub enum QueryMsg {
    #[returns(bool)]
    HasExited { player: Addr },
 
+   #[returns(Option<DecayGameAccount>)]
+   Account { addr: Addr },
+
+   /// Donated coins ordered by denom
    #[returns(Vec<Coin>)]
-   Donations {},
+   Donations { start_after: Option<String>, limit: Option<u32> },
 
    #[returns(Decimal)]
    RefWeight { player: Addr },
 
    #[returns(Config)]
    Config {},
+
+   #[returns(WinnerResponse)]
+   Winner {},
+
+   #[returns(CommitReveal)]
+   Randomness {},
+
+   /// Settled rounds from the latest, `start_after` is the last index of the previous page
+   #[returns(Vec<(u64, RoundSummary)>)]
+   RoundHistory { start_after: Option<u64>, limit: Option<u32> },
+
+   #[returns(Option<RoundSummary>)]
+   Round { idx: u64 },
+
+   /// Lifetime statistics of `addr`, zeroed for unknown accounts
+   #[returns(PlayerStats)]
+   PlayerStats { addr: Addr },
+
+   /// When a keeper should send `Settle {}` next
+   #[returns(NextAction)]
+   NextAction {},
+}
+
+#[cw_serde]
+pub struct WinnerResponse {
+   pub rule: WinnerRule,
+   /// The account the rule picks from the current state, paid once the game ends
+   pub winner: Option<Addr>,
 }
 
 #[cw_serde]
 pub enum CallbackType {
-   PostSwap { winner: Addr, restart: bool },
+   PostSwap {
+      restart: bool,
+   },
+   /// Finishes the pending join paid in an entry denom
+   PostEntrySwap {},
 }
 
 #[cw_serde]
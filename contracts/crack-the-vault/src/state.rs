@@ -1,20 +1,104 @@
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-   ensure, wasm_execute, Addr, Api, BankMsg, Coin, Decimal, Empty, Order, QuerierWrapper, Response,
-   Storage, Timestamp, Uint128, WasmMsg,
+   coins, ensure, to_json_binary, wasm_execute, Addr, Api, BankMsg, Binary, Coin, Decimal, Empty,
+   Event, Order, QuerierWrapper, Response, StdResult, Storage, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use cw_storage_plus::{Item, Map};
+use kujira::CallbackData;
 use referral::{ExecuteMsg, QueryMsg};
 use wenruji_rs::{
-   calculate_fee_distribution, normalize, to_addr, DecayGame, DecayGameAccount, DecayGameError,
+   calculate_fee_distribution, normalize,
+   randomness::{validate_commitment, CommitReveal},
+   to_addr, DecayGame, DecayGameAccount, DecayGameError, PlayerStatsSM,
 };
 
-use crate::{config::Config, ContractError};
+use crate::{
+   config::{Config, WinnerRule},
+   msg::CallbackType,
+   ContractError,
+};
 
 pub static DECAY_GAME: Item<DecayGame> = Item::new("dg");
 pub static ACCOUNTS: Map<Addr, DecayGameAccount> = Map::new("dg/a");
+/// Accounts of the current round keyed by join sequence
+pub static JOINS: Map<u64, Addr> = Map::new("dg/j");
+/// Accounts that joined the current round, exits do not lower it. Also the next join sequence.
+pub static PARTICIPANTS: Item<u64> = Item::new("dg/n");
+/// Winner picked at the end of the game, paid out by the swap callback
+pub static WINNER: Item<Addr> = Item::new("w");
+/// Referral weights of the round the pending `WINNER` was picked in
+pub static WINNER_REF_WEIGHTS: Item<Vec<(Addr, Decimal)>> = Item::new("w/rw");
+/// Lottery commitments not yet revealed
+pub static COMMITMENTS: Map<Addr, Binary> = Map::new("cr/c");
+/// Accounts that revealed their commitment in time
+pub static REVEALED: Map<Addr, Empty> = Map::new("cr/r");
+pub static RANDOMNESS: Item<CommitReveal> = Item::new("cr");
 pub static REWARDS: Map<String, Coin> = Map::new("r");
 pub static REF_WEIGHTS: Map<Addr, Decimal> = Map::new("rw");
 pub static ADMIN: Map<&Addr, Empty> = Map::new("admin");
+/// Index of the live round, starting at 1
+pub static ROUND_IDX: Item<u64> = Item::new("round_idx");
+/// Summaries of the settled rounds keyed by round index
+pub static ROUNDS: Map<u64, RoundSummary> = Map::new("rounds");
+/// Lifetime statistics of every account
+pub static PLAYER_STATS: PlayerStatsSM = PlayerStatsSM::new();
+/// Join paid in an entry denom, finished by the swap callback or refunded by the reply
+pub static PENDING_ENTRY: Item<PendingEntry> = Item::new("pe");
+
+/// Reply id of the swap sent for a join paid in an entry denom
+pub const ENTRY_SWAP_REPLY: u64 = 1;
+/// Reply id of the swap sent for the prize of a settled round, its payload is the `PostSwap`
+/// callback
+pub const PRIZE_SWAP_REPLY: u64 = 2;
+
+#[cw_serde]
+pub struct PendingEntry {
+   pub account: Addr,
+   pub offer: Coin,
+   pub ref_code: Option<String>,
+   pub commitment: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct RoundSummary {
+   pub starts_at: Timestamp,
+   pub ends_at: Timestamp,
+   pub winner: Option<Addr>,
+   /// Pot left after the settlement fee, in the ticket denom
+   pub prize: Coin,
+   /// Pot returned by the swap, or the unswapped pot when the swap failed. `None` without a
+   /// winner.
+   pub swapped_prize: Option<Coin>,
+   pub total: Uint128,
+   pub exited: Uint128,
+   /// Donations paid with the prize, or kept in the vault without a winner
+   pub donations: Vec<Coin>,
+   pub participants: u64,
+}
+
+impl RoundSummary {
+   fn new(
+      storage: &dyn Storage,
+      decay_game: &DecayGame,
+      winner: Option<Addr>,
+      prize: Coin,
+   ) -> StdResult<Self> {
+      Ok(Self {
+         starts_at: decay_game.decay_starts_at,
+         ends_at: decay_game.decay_ends_at,
+         winner,
+         prize,
+         swapped_prize: None,
+         total: decay_game.total,
+         exited: decay_game.exited,
+         donations: REWARDS
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, coin)| coin))
+            .collect::<StdResult<_>>()?,
+         participants: PARTICIPANTS.may_load(storage)?.unwrap_or_default(),
+      })
+   }
+}
 
 pub fn execute_ref(
    api: &dyn Api,
@@ -34,7 +118,7 @@ pub fn execute_ref(
          REF_WEIGHTS.may_load(storage, ambassador_addr.clone())?.unwrap_or(Decimal::zero());
       REF_WEIGHTS.save(storage, ambassador_addr, &(ref_weight + Decimal::one()))?;
 
-      return Ok((Some(ambassador), None));
+      Ok((Some(ambassador), None))
    } else {
       if let Some(ref_code) = ref_code {
          // Query the referral contract to get the referrer address
@@ -54,7 +138,7 @@ pub fn execute_ref(
          )?;
          return Ok((Some(ambassador.clone()), Some(msg)));
       }
-      return Ok((None, None));
+      Ok((None, None))
    }
 }
 
@@ -68,9 +152,161 @@ pub fn execute_join(
    let account_data = decay_game.join(amount, &now)?;
    DECAY_GAME.save(storage, &decay_game)?;
    ACCOUNTS.save(storage, account.clone(), &account_data)?;
+   record_join(storage, account)?;
+   PLAYER_STATS.record_join(storage, account, amount)?;
+   Ok(())
+}
+
+/// Adds `amount` to the stake of `account` within the ticket range, returns the new stake
+pub fn execute_top_up(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   account: &Addr,
+   amount: Uint128,
+) -> Result<Uint128, ContractError> {
+   let mut decay_game = DECAY_GAME.load(storage)?;
+   let mut account_data = ACCOUNTS
+      .may_load(storage, account.clone())?
+      .ok_or(ContractError::Invalid("not_joined".to_string()))?;
+   config.check_stake(account_data.amount + amount)?;
+   decay_game.top_up(amount, &now, &mut account_data)?;
+   DECAY_GAME.save(storage, &decay_game)?;
+   ACCOUNTS.save(storage, account.clone(), &account_data)?;
+   PLAYER_STATS.record_top_up(storage, account, amount)?;
+   Ok(account_data.amount)
+}
+
+pub fn record_join(storage: &mut dyn Storage, account: &Addr) -> Result<(), ContractError> {
+   let next = PARTICIPANTS.may_load(storage)?.unwrap_or_default();
+   JOINS.save(storage, next, account)?;
+   PARTICIPANTS.save(storage, &(next + 1))?;
+   Ok(())
+}
+
+/// Swaps `offer` to the ticket denom, the join is finished by the `PostEntrySwap` callback
+pub fn execute_entry_swap(
+   storage: &mut dyn Storage,
+   config: &Config,
+   entry: PendingEntry,
+) -> Result<SubMsg, ContractError> {
+   ensure!(
+      config.entry_denoms.contains(&entry.offer.denom),
+      ContractError::Invalid("entry_denom".to_string())
+   );
+   let cb_data = to_json_binary(&CallbackType::PostEntrySwap {})?;
+   let swap_msg = kujira::fin::ExecuteMsg::Swap {
+      offer_asset: Some(entry.offer.clone()),
+      belief_price: None,
+      max_spread: None,
+      to: None,
+      callback: Some(CallbackData(cb_data)),
+   };
+   let wasm_msg =
+      wasm_execute(config.contracts.swap.clone(), &swap_msg, vec![entry.offer.clone()])?;
+   PENDING_ENTRY.save(storage, &entry)?;
+   Ok(SubMsg::reply_on_error(wasm_msg, ENTRY_SWAP_REPLY))
+}
+
+/// Sends the offer of the pending join back after its swap or callback failed
+pub fn execute_entry_refund(
+   storage: &mut dyn Storage,
+   error: String,
+) -> Result<Response, ContractError> {
+   let entry = PENDING_ENTRY.load(storage)?;
+   PENDING_ENTRY.remove(storage);
+   Ok(Response::new()
+      .add_message(BankMsg::Send {
+         to_address: entry.account.to_string(),
+         amount: vec![entry.offer.clone()],
+      })
+      .add_event(
+         Event::new("crack-the-valut/entry_refund")
+            .add_attribute("account", entry.account)
+            .add_attribute("offer", entry.offer.to_string())
+            .add_attribute("error", error),
+      ))
+}
+
+pub fn execute_commit(
+   storage: &mut dyn Storage,
+   config: &Config,
+   account: &Addr,
+   commitment: Option<Binary>,
+) -> Result<(), ContractError> {
+   if let WinnerRule::Lottery { .. } = config.winner_rule {
+      let commitment = commitment.ok_or(ContractError::Invalid("commitment".to_string()))?;
+      validate_commitment(&commitment)?;
+      COMMITMENTS.save(storage, account.clone(), &commitment)?;
+   }
    Ok(())
 }
 
+pub fn execute_reveal(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   account: &Addr,
+   secret: Binary,
+) -> Result<(), ContractError> {
+   let WinnerRule::Lottery { reveal_seconds, .. } = config.winner_rule else {
+      return Err(ContractError::Invalid("winner_rule".to_string()));
+   };
+   let decay_game = DECAY_GAME.load(storage)?;
+   ensure!(
+      now.gt(&decay_game.decay_ends_at),
+      ContractError::DecayGameError(DecayGameError::DecayNotEnded {})
+   );
+   ensure!(
+      now.le(&decay_game.decay_ends_at.plus_seconds(reveal_seconds)),
+      ContractError::Invalid("reveal_ended".to_string())
+   );
+
+   let commitment = COMMITMENTS
+      .may_load(storage, account.clone())?
+      .ok_or(ContractError::Invalid("commitment".to_string()))?;
+
+   let mut randomness = RANDOMNESS.may_load(storage)?.unwrap_or_default();
+   randomness.reveal(account, &commitment, &secret)?;
+   RANDOMNESS.save(storage, &randomness)?;
+   COMMITMENTS.remove(storage, account.clone());
+   REVEALED.save(storage, account.clone(), &Empty {})?;
+   Ok(())
+}
+
+/// Picks the winner following `rule`, exited accounts are never eligible
+pub fn select_winner(
+   storage: &dyn Storage,
+   rule: &WinnerRule,
+) -> Result<Option<Addr>, ContractError> {
+   let order = match rule {
+      WinnerRule::LastJoin => Order::Descending,
+      WinnerRule::LastStanding => Order::Ascending,
+      WinnerRule::Lottery { .. } => return select_lottery_winner(storage),
+   };
+   for item in JOINS.range(storage, None, None, order) {
+      let (_, account) = item?;
+      let account_data = ACCOUNTS.load(storage, account.clone())?;
+      if account_data.is_active() {
+         return Ok(Some(account));
+      }
+   }
+   Ok(None)
+}
+
+fn select_lottery_winner(storage: &dyn Storage) -> Result<Option<Addr>, ContractError> {
+   let mut candidates = vec![];
+   for item in JOINS.range(storage, None, None, Order::Ascending) {
+      let (_, account) = item?;
+      let account_data = ACCOUNTS.load(storage, account.clone())?;
+      if account_data.is_active() && REVEALED.has(storage, account.clone()) {
+         candidates.push((account, account_data.amount));
+      }
+   }
+   let randomness = RANDOMNESS.may_load(storage)?.unwrap_or_default();
+   Ok(randomness.pick_weighted(&candidates).cloned())
+}
+
 pub fn execute_exit(
    storage: &mut dyn Storage,
    now: Timestamp,
@@ -82,20 +318,49 @@ pub fn execute_exit(
 
    let mut account_data = ACCOUNTS.load(storage, account.clone())?;
 
-   if !account_data.decay_snapshot.is_zero() {
+   if !account_data.is_active() {
       return Err(ContractError::Invalid("already_exited".to_string()));
    }
 
-   decay_game.exit(&now, &mut account_data);
+   decay_game.exit(&now, &mut account_data)?;
 
    let amount = decay_game.claim(&mut account_data);
 
    DECAY_GAME.save(storage, &decay_game)?;
    ACCOUNTS.save(storage, account.clone(), &account_data)?;
+   PLAYER_STATS.record_exit(storage, account, amount, account_data.decay_snapshot)?;
 
    Ok((amount, account_data.decay_snapshot))
 }
 
+/// Takes `amount` of the stake of `account` out, returns the amount paid and the decay factor
+pub fn execute_exit_partial(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   account: &Addr,
+   amount: Uint128,
+) -> Result<(Uint128, Decimal), ContractError> {
+   let mut decay_game = DECAY_GAME.load(storage)?;
+
+   ensure!(now.lt(&decay_game.decay_ends_at), ContractError::Invalid("game_ended".to_string()));
+
+   let mut account_data = ACCOUNTS.load(storage, account.clone())?;
+   decay_game.exit_partial(&now, &mut account_data, amount)?;
+   // the stake left in play is never below a ticket
+   ensure!(
+      account_data.amount >= config.ticket_amount,
+      ContractError::Invalid("exit_amount".to_string())
+   );
+   let paid = decay_game.claim(&mut account_data);
+
+   DECAY_GAME.save(storage, &decay_game)?;
+   ACCOUNTS.save(storage, account.clone(), &account_data)?;
+   PLAYER_STATS.record_partial_exit(storage, account, paid)?;
+
+   Ok((paid, decay_game.decay_factor(&now)))
+}
+
 pub fn execute_endgame(
    storage: &mut dyn Storage,
    now: Timestamp,
@@ -106,6 +371,180 @@ pub fn execute_endgame(
    Ok(amount)
 }
 
+/// Pays `reveal_bond` of their stake back to the lottery accounts still in play that revealed,
+/// the others are forfeited and leave it to the prize
+fn settle_reveals(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+) -> Result<Response, ContractError> {
+   let WinnerRule::Lottery { reveal_bond, .. } = config.winner_rule else {
+      return Ok(Response::new());
+   };
+   let mut decay_game = DECAY_GAME.load(storage)?;
+   let mut response = Response::new();
+   let (mut paid, mut forfeited) = (Uint128::zero(), Uint128::zero());
+
+   let accounts: Vec<Addr> = JOINS
+      .range(storage, None, None, Order::Ascending)
+      .map(|item| item.map(|(_, account)| account))
+      .collect::<StdResult<_>>()?;
+   for account in accounts {
+      let mut account_data = ACCOUNTS.load(storage, account.clone())?;
+      if !account_data.is_active() {
+         continue;
+      }
+      if REVEALED.has(storage, account.clone()) {
+         decay_game.exit_with_bonus(&now, &mut account_data, reveal_bond)?;
+         let amount = decay_game.claim(&mut account_data);
+         if !amount.is_zero() {
+            response = response.add_message(BankMsg::Send {
+               to_address: account.to_string(),
+               amount: coins(amount.u128(), config.ticket_denom.clone()),
+            });
+         }
+         paid += amount;
+      } else {
+         forfeited += account_data.amount.mul_floor(reveal_bond);
+         decay_game.forfeit(&now, &mut account_data)?;
+      }
+      ACCOUNTS.save(storage, account, &account_data)?;
+   }
+   DECAY_GAME.save(storage, &decay_game)?;
+
+   Ok(response.add_event(
+      Event::new("crack-the-valut/reveal_bonds")
+         .add_attribute("paid", paid)
+         .add_attribute("forfeited", forfeited),
+   ))
+}
+
+/// Settles a finished round: lottery reveal bonds are paid back, the settlement fee goes to
+/// `settler` and the rest of the pot is swapped for the winner, or kept in the vault when nobody
+/// held until the end.
+/// Returns whether a swap was sent, `restart` is forwarded to its callback.
+pub fn execute_settle(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   settler: &Addr,
+   restart: bool,
+) -> Result<(Response, bool), ContractError> {
+   // the winner is drawn before the reveal bonds take the lottery accounts out of play
+   let winner = select_winner(storage, &config.winner_rule)?;
+   let mut response = settle_reveals(storage, now, config)?;
+   let pot = execute_endgame(storage, now)?;
+
+   let settlement = pot.mul_floor(config.settlement_fee);
+   if !settlement.is_zero() {
+      response = response.add_message(BankMsg::Send {
+         to_address: settler.to_string(),
+         amount: coins(settlement.u128(), config.ticket_denom.clone()),
+      });
+   }
+   let amount = pot - settlement;
+
+   let summary = RoundSummary::new(
+      storage,
+      &DECAY_GAME.load(storage)?,
+      winner.clone(),
+      Coin::new(amount, config.ticket_denom.clone()),
+   )?;
+   let idx = ROUND_IDX.load(storage)?;
+   ROUNDS.save(storage, idx, &summary)?;
+
+   let Some(winner) = winner else {
+      // Nobody held until the end, the prize stays in the vault for the next round
+      execute_donate(storage, Coin::new(amount, config.ticket_denom.clone()))?;
+      return Ok((
+         response.add_event(
+            Event::new("crack-the-valut/end_game")
+               .add_attribute("winner", "")
+               .add_attribute("prize_amount_before", amount)
+               .add_attribute("prize_denom_before", config.ticket_denom.clone())
+               .add_attribute("settler", settler)
+               .add_attribute("settlement_fee", settlement),
+         ),
+         false,
+      ));
+   };
+
+   WINNER.save(storage, &winner)?;
+   PLAYER_STATS.record_win(storage, &winner)?;
+   let ref_weights =
+      REF_WEIGHTS.range(storage, None, None, Order::Ascending).collect::<StdResult<_>>()?;
+   WINNER_REF_WEIGHTS.save(storage, &ref_weights)?;
+
+   let cb_data = to_json_binary(&CallbackType::PostSwap { restart })?;
+   let swap_msg = kujira::fin::ExecuteMsg::Swap {
+      offer_asset: Some(Coin { denom: config.ticket_denom.clone(), amount }),
+      belief_price: None,
+      max_spread: None,
+      to: None,
+      callback: Some(CallbackData(cb_data.clone())),
+   };
+   let wasm_msg = wasm_execute(
+      config.contracts.swap.clone(),
+      &swap_msg,
+      vec![Coin::new(amount, config.ticket_denom.clone())],
+   )?;
+   // a failed swap pays the prize in the ticket denom instead of stalling the settlement
+   let swap = SubMsg::reply_on_error(wasm_msg, PRIZE_SWAP_REPLY).with_payload(cb_data);
+   Ok((
+      response.add_submessage(swap).add_event(
+         Event::new("crack-the-valut/end_game")
+            .add_attribute("winner", winner)
+            .add_attribute("prize_amount_before", amount)
+            .add_attribute("prize_denom_before", config.ticket_denom.clone())
+            .add_attribute("settler", settler)
+            .add_attribute("settlement_fee", settlement),
+      ),
+      true,
+   ))
+}
+
+/// Last block time of the round, including the lottery reveal window
+pub fn settles_at(storage: &dyn Storage, config: &Config) -> StdResult<Timestamp> {
+   let decay_game = DECAY_GAME.load(storage)?;
+   Ok(match config.winner_rule {
+      WinnerRule::Lottery { reveal_seconds, .. } => {
+         decay_game.decay_ends_at.plus_seconds(reveal_seconds)
+      }
+      _ => decay_game.decay_ends_at,
+   })
+}
+
+/// Settles a round that is over and starts the next one, `None` while the round or its reveal
+/// window is live. Rounds nobody joined or already ended are only restarted.
+pub fn execute_rollover(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   settler: &Addr,
+) -> Result<Option<Response>, ContractError> {
+   if now.le(&settles_at(storage, config)?) {
+      return Ok(None);
+   }
+   let response = match execute_settle(storage, now, config, settler, false) {
+      Ok((response, _)) => response,
+      Err(ContractError::DecayGameError(DecayGameError::NoRewards {})) => Response::new(),
+      Err(err) => return Err(err),
+   };
+   execute_restart(storage, now, config)?;
+   let starts_at = now.plus_seconds(config.game_delay);
+   Ok(Some(
+      response.add_event(
+         Event::new("crack-the-valut/rollover")
+            .add_attribute("settler", settler)
+            .add_attribute("game_starts_at", starts_at.to_string())
+            .add_attribute(
+               "game_ends_at",
+               starts_at.plus_seconds(config.duration_seconds).to_string(),
+            ),
+      ),
+   ))
+}
+
 pub fn execute_donate(storage: &mut dyn Storage, coin: Coin) -> Result<(), ContractError> {
    let old_coin = REWARDS
       .load(storage, coin.denom.clone())
@@ -128,28 +567,72 @@ pub fn execute_restart(
    if now.le(&decay_game.decay_ends_at) {
       return Err(ContractError::DecayGameError(DecayGameError::DecayNotEnded {}));
    }
-   if !(decay_game.rewards == decay_game.total - decay_game.exited) {
+   if decay_game.rewards != decay_game.total - decay_game.exited {
       return Err(ContractError::GameNotEnded {});
    }
+   // rounds left without rewards are archived here, the others when settled
+   let idx = ROUND_IDX.load(storage)?;
+   if !ROUNDS.has(storage, idx) {
+      let prize = Coin::new(Uint128::zero(), config.ticket_denom.clone());
+      let summary = RoundSummary::new(storage, &decay_game, None, prize)?;
+      ROUNDS.save(storage, idx, &summary)?;
+   }
+   ROUND_IDX.save(storage, &(idx + 1))?;
    ACCOUNTS.clear(storage);
+   JOINS.clear(storage);
+   PARTICIPANTS.remove(storage);
+   COMMITMENTS.clear(storage);
+   REVEALED.clear(storage);
+   RANDOMNESS.remove(storage);
    REF_WEIGHTS.clear(storage);
    DECAY_GAME.remove(storage);
    let start_time = now.plus_seconds(config.game_delay);
-   let decay_game = DecayGame::new(start_time, start_time.plus_seconds(config.duration_seconds));
+   let decay_game = DecayGame::new(start_time, start_time.plus_seconds(config.duration_seconds))
+      .with_curve(config.decay_curve.clone());
    DECAY_GAME.save(storage, &decay_game)?;
    Ok(())
 }
 
+/// Pays the prize of the last settled round in the ticket denom after its swap or callback failed
+pub fn execute_prize_fallback(
+   storage: &mut dyn Storage,
+   config: &Config,
+   error: String,
+) -> Result<Response, ContractError> {
+   let (_, summary) = ROUNDS
+      .range(storage, None, None, Order::Descending)
+      .next()
+      .transpose()?
+      .ok_or(ContractError::Invalid("round".to_string()))?;
+   let response = execute_post_swap(storage, config, summary.prize.clone())?;
+   Ok(response.add_event(
+      Event::new("crack-the-valut/prize_fallback")
+         .add_attribute("prize", summary.prize.to_string())
+         .add_attribute("error", error),
+   ))
+}
+
 pub fn execute_post_swap(
    storage: &mut dyn Storage,
    config: &Config,
-   winner: Addr,
    coin: Coin,
 ) -> Result<Response, ContractError> {
    let mut response = Response::new();
 
+   let winner = WINNER.load(storage)?;
+   WINNER.remove(storage);
+   let referrals = WINNER_REF_WEIGHTS.load(storage)?;
+   WINNER_REF_WEIGHTS.remove(storage);
+
+   // the round of the pending winner is the last one archived
+   let last = ROUNDS.range(storage, None, None, Order::Descending).next().transpose()?;
+   if let Some((idx, mut summary)) = last {
+      summary.swapped_prize = Some(coin.clone());
+      ROUNDS.save(storage, idx, &summary)?;
+   }
+
    let mut fees = config.fees.clone();
-   fees.insert(0, (winner, config.winner_share));
+   fees.insert(0, (winner.clone(), config.winner_share));
    let fee_split = calculate_fee_distribution(vec![coin], &fees);
 
    // Collect all existing rewards, merge with winner rewards, and normalize
@@ -159,20 +642,17 @@ pub fn execute_post_swap(
       .map(|(_, coin)| coin)
       .chain(fee_split[0].1.clone()) // Winner’s rewards
       .collect();
+   let rewards = normalize(rewards);
+   PLAYER_STATS.record_prize(storage, &winner, rewards.clone())?;
 
    // Dispatch messages to send coins and distribute referral rewards
    response = response.add_messages(vec![
-      BankMsg::Send { to_address: fee_split[0].0.to_string(), amount: normalize(rewards) },
+      BankMsg::Send { to_address: fee_split[0].0.to_string(), amount: rewards },
       BankMsg::Send { to_address: fee_split[1].0.to_string(), amount: fee_split[1].1.clone() },
       BankMsg::Send { to_address: fee_split[2].0.to_string(), amount: fee_split[2].1.clone() },
    ]);
 
-   // Collect referral weights and trigger referral reward distribution
-   let referrals = REF_WEIGHTS
-      .range(storage, None, None, Order::Ascending)
-      .filter_map(|item| item.ok())
-      .collect();
-
+   // Trigger referral reward distribution with the weights of the settled round
    response = response.add_message(wasm_execute(
       config.contracts.referral.clone(),
       &ExecuteMsg::DistributeRewards { referers: referrals },
@@ -191,6 +671,7 @@ mod tests {
    use cosmwasm_std::{
       testing::mock_dependencies, Addr, BankMsg, Coin, Decimal, Timestamp, Uint128,
    };
+   use wenruji_rs::{randomness::commitment, AccountStatus, DecayCurve};
 
    fn setup_config() -> Config {
       Config {
@@ -210,6 +691,11 @@ mod tests {
          ],
          winner_share: Decimal::percent(90),
          game_delay: 60u64,
+         decay_curve: DecayCurve::Linear,
+         winner_rule: WinnerRule::LastJoin,
+         settlement_fee: Decimal::zero(),
+         entry_denoms: vec![],
+         max_ticket_amount: None,
       }
    }
 
@@ -245,7 +731,7 @@ mod tests {
          .save(
             &mut deps.storage,
             account.clone(),
-            &DecayGameAccount { amount, decay_snapshot: Decimal::zero(), pending: Uint128::zero() },
+            &DecayGameAccount { amount, ..Default::default() },
          )
          .unwrap();
 
@@ -306,6 +792,7 @@ mod tests {
       decay_game.exited = Uint128::new(500); // Simulate players exited
       decay_game.rewards = Uint128::new(500);
       DECAY_GAME.save(&mut deps.storage, &decay_game).unwrap();
+      ROUND_IDX.save(&mut deps.storage, &1).unwrap();
 
       // Execute restart error not ended
       execute_restart(&mut deps.storage, now, &config).unwrap_err();
@@ -313,10 +800,19 @@ mod tests {
       // Execute restart
       execute_restart(&mut deps.storage, now.plus_seconds(4000), &config).unwrap();
 
+      // The round never settled is archived without a prize
+      assert_eq!(ROUND_IDX.load(&deps.storage).unwrap(), 2);
+      let summary = ROUNDS.load(&deps.storage, 1).unwrap();
+      assert_eq!(summary.total, Uint128::new(1000));
+      assert_eq!(summary.exited, Uint128::new(500));
+      assert_eq!(summary.winner, None);
+      assert!(summary.prize.amount.is_zero());
+
       // Verify that the new game has been created with reset state
       let new_game = DECAY_GAME.load(&deps.storage).unwrap();
       assert!(new_game.total.is_zero());
       assert!(new_game.exited.is_zero());
+      assert_eq!(new_game.curve, config.decay_curve);
    }
 
    #[test]
@@ -331,9 +827,15 @@ mod tests {
       // Add mock reward to the REWARDS map
       REWARDS.save(&mut deps.storage, "utoken".to_string(), &coin).unwrap();
 
+      // Execute post_swap error no winner picked
+      execute_post_swap(&mut deps.storage, &config, win_coin.clone()).unwrap_err();
+
       // Execute post_swap
-      let response =
-         execute_post_swap(&mut deps.storage, &config, winner.clone(), win_coin.clone()).unwrap();
+      WINNER.save(&mut deps.storage, &winner).unwrap();
+      WINNER_REF_WEIGHTS.save(&mut deps.storage, &vec![]).unwrap();
+      let response = execute_post_swap(&mut deps.storage, &config, win_coin.clone()).unwrap();
+      assert!(!WINNER.exists(&deps.storage));
+      assert!(!WINNER_REF_WEIGHTS.exists(&deps.storage));
 
       // Verify that response contains BankMsg::Send messages
       let bank_msgs: Vec<&BankMsg> = response
@@ -349,5 +851,138 @@ mod tests {
          .collect();
 
       assert!(bank_msgs.len() >= 2); // Should contain at least 2 send messages
+      assert!(
+         matches!(bank_msgs[0], BankMsg::Send { to_address, .. } if to_address == winner.as_str())
+      );
+   }
+
+   #[test]
+   fn test_select_winner() {
+      let mut deps = mock_dependencies();
+      let now = Timestamp::from_seconds(1_000_000);
+      DECAY_GAME.save(&mut deps.storage, &DecayGame::new(now, now.plus_seconds(3600))).unwrap();
+
+      // no players => no winner
+      assert_eq!(select_winner(&deps.storage, &WinnerRule::LastJoin).unwrap(), None);
+      assert_eq!(select_winner(&deps.storage, &WinnerRule::LastStanding).unwrap(), None);
+
+      let players: Vec<Addr> =
+         ["player1", "player2", "player3", "player4"].into_iter().map(Addr::unchecked).collect();
+      for player in &players {
+         execute_join(&mut deps.storage, now, player, Uint128::new(100)).unwrap();
+      }
+      assert_eq!(
+         select_winner(&deps.storage, &WinnerRule::LastJoin).unwrap(),
+         Some(players[3].clone())
+      );
+      assert_eq!(
+         select_winner(&deps.storage, &WinnerRule::LastStanding).unwrap(),
+         Some(players[0].clone())
+      );
+
+      // exited players are skipped
+      execute_exit(&mut deps.storage, now.plus_seconds(10), &players[0]).unwrap();
+      execute_exit(&mut deps.storage, now.plus_seconds(10), &players[3]).unwrap();
+      assert_eq!(
+         select_winner(&deps.storage, &WinnerRule::LastJoin).unwrap(),
+         Some(players[2].clone())
+      );
+      assert_eq!(
+         select_winner(&deps.storage, &WinnerRule::LastStanding).unwrap(),
+         Some(players[1].clone())
+      );
+
+      // everyone exited => no winner
+      execute_exit(&mut deps.storage, now.plus_seconds(10), &players[1]).unwrap();
+      execute_exit(&mut deps.storage, now.plus_seconds(10), &players[2]).unwrap();
+      assert_eq!(select_winner(&deps.storage, &WinnerRule::LastJoin).unwrap(), None);
+      assert_eq!(select_winner(&deps.storage, &WinnerRule::LastStanding).unwrap(), None);
+   }
+
+   #[test]
+   fn test_execute_reveal() {
+      let mut deps = mock_dependencies();
+      let now = Timestamp::from_seconds(1_000_000);
+      let rule = WinnerRule::Lottery { reveal_seconds: 60, reveal_bond: Decimal::percent(10) };
+      let config = Config { winner_rule: rule.clone(), ..setup_config() };
+      DECAY_GAME.save(&mut deps.storage, &DecayGame::new(now, now.plus_seconds(3600))).unwrap();
+
+      let players: Vec<Addr> =
+         ["player1", "player2", "player3"].into_iter().map(Addr::unchecked).collect();
+      let secret = |player: &Addr| Binary::from(format!("{player} secret").as_bytes());
+
+      // the lottery needs a valid commitment
+      execute_commit(&mut deps.storage, &config, &players[0], None).unwrap_err();
+      execute_commit(&mut deps.storage, &config, &players[0], Some(Binary::from(b"hash")))
+         .unwrap_err();
+      execute_commit(&mut deps.storage, &setup_config(), &players[0], None).unwrap();
+
+      for player in &players {
+         execute_join(&mut deps.storage, now, player, Uint128::new(100)).unwrap();
+         let commitment = commitment(player, &secret(player));
+         execute_commit(&mut deps.storage, &config, player, Some(commitment)).unwrap();
+      }
+      execute_exit(&mut deps.storage, now.plus_seconds(10), &players[1]).unwrap();
+
+      let ended = now.plus_seconds(3601);
+      // only during the reveal window
+      execute_reveal(&mut deps.storage, now, &config, &players[0], secret(&players[0]))
+         .unwrap_err();
+      execute_reveal(
+         &mut deps.storage,
+         ended.plus_seconds(60),
+         &config,
+         &players[0],
+         secret(&players[0]),
+      )
+      .unwrap_err();
+      // only with the lottery rule
+      execute_reveal(&mut deps.storage, ended, &setup_config(), &players[0], secret(&players[0]))
+         .unwrap_err();
+      // only with the committed secret
+      execute_reveal(&mut deps.storage, ended, &config, &players[0], secret(&players[1]))
+         .unwrap_err();
+      execute_reveal(
+         &mut deps.storage,
+         ended,
+         &config,
+         &Addr::unchecked("other"),
+         secret(&players[0]),
+      )
+      .unwrap_err();
+      assert_eq!(select_winner(&deps.storage, &rule).unwrap(), None);
+
+      execute_reveal(&mut deps.storage, ended, &config, &players[0], secret(&players[0])).unwrap();
+      execute_reveal(&mut deps.storage, ended, &config, &players[1], secret(&players[1])).unwrap();
+      // only once
+      execute_reveal(&mut deps.storage, ended, &config, &players[0], secret(&players[0]))
+         .unwrap_err();
+      assert_eq!(RANDOMNESS.load(&deps.storage).unwrap().reveals, 2);
+
+      // player2 exited and player3 did not reveal
+      assert_eq!(select_winner(&deps.storage, &rule).unwrap(), Some(players[0].clone()));
+
+      // player1 gets its bond back, player3 forfeits its stake
+      let res = settle_reveals(&mut deps.storage, ended.plus_seconds(61), &config).unwrap();
+      assert_eq!(
+         res.messages,
+         vec![SubMsg::new(BankMsg::Send {
+            to_address: players[0].to_string(),
+            amount: coins(10, "utoken"),
+         })]
+      );
+      let status = |player: &Addr| ACCOUNTS.load(&deps.storage, player.clone()).unwrap().status;
+      assert!(matches!(status(&players[0]), AccountStatus::Claimed { .. }));
+      assert!(matches!(status(&players[2]), AccountStatus::Forfeited { .. }));
+
+      // a new round starts without commitments
+      let mut decay_game = DECAY_GAME.load(&deps.storage).unwrap();
+      decay_game.distribute_rewards(&ended).unwrap();
+      DECAY_GAME.save(&mut deps.storage, &decay_game).unwrap();
+      ROUND_IDX.save(&mut deps.storage, &1).unwrap();
+      execute_restart(&mut deps.storage, ended, &config).unwrap();
+      assert!(COMMITMENTS.is_empty(&deps.storage));
+      assert!(REVEALED.is_empty(&deps.storage));
+      assert!(!RANDOMNESS.exists(&deps.storage));
    }
 }
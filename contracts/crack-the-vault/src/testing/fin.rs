@@ -76,5 +76,5 @@ pub fn fin_execute(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn fin_query(_deps: Deps, _env: Env, _msg: kujira::fin::QueryMsg) -> StdResult<Binary> {
-   Ok(to_json_binary("")?)
+   to_json_binary("")
 }
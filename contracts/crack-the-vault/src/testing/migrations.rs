@@ -0,0 +1,201 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{coin, coins, Addr, Decimal, Order, StdResult, Timestamp, Uint128};
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::{Item, Map};
+use wenruji_rs::{AccountStatus, DecayCurve, DecayGame, DecayGameAccount};
+
+use crate::{
+   config::{Config, WinnerRule},
+   migrations::{ConfigV0_1, CONFIG_V0_1},
+   msg::QueryMsg,
+   state::{ACCOUNTS, JOINS, PARTICIPANTS, ROUND_IDX},
+};
+
+use super::{test_helpers::TestEnv, test_macros::define_test};
+
+const DENOM: &str = "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9";
+
+/// DecayGame layout of the v0.1 releases, before the decay curve was stored on the game
+#[cw_serde]
+struct DecayGameV0_1 {
+   decay_starts_at: Timestamp,
+   decay_ends_at: Timestamp,
+   total: Uint128,
+   exited: Uint128,
+   rewards: Uint128,
+}
+
+/// DecayGameAccount layout of the v0.1 releases, before partial exits and the account status
+#[cw_serde]
+struct DecayGameAccountV0_1 {
+   amount: Uint128,
+   decay_snapshot: Decimal,
+   pending: Uint128,
+}
+
+/// Rewrites the live contract state with the v0.1.1-rc1 layouts listed in RELEASES.md
+fn downgrade_to_v0_1(env: &mut TestEnv) {
+   let mut storage = env.app.contract_storage_mut(&env.contracts.game);
+
+   let config = Config::load(storage.as_ref()).unwrap();
+   CONFIG_V0_1
+      .save(
+         storage.as_mut(),
+         &ConfigV0_1 {
+            owner: config.owner,
+            ticket_denom: config.ticket_denom,
+            ticket_amount: config.ticket_amount,
+            duration_seconds: config.duration_seconds,
+            game_delay: config.game_delay,
+            contracts: config.contracts,
+            fees: config.fees,
+            winner_share: config.winner_share,
+            donation_addrs: config.donation_addrs,
+         },
+      )
+      .unwrap();
+
+   let decay_game: DecayGame = Item::new("dg").load(storage.as_ref()).unwrap();
+   Item::new("dg")
+      .save(
+         storage.as_mut(),
+         &DecayGameV0_1 {
+            decay_starts_at: decay_game.decay_starts_at,
+            decay_ends_at: decay_game.decay_ends_at,
+            total: decay_game.total,
+            exited: decay_game.exited,
+            rewards: decay_game.rewards,
+         },
+      )
+      .unwrap();
+
+   let accounts: Vec<(Addr, DecayGameAccount)> = ACCOUNTS
+      .range(storage.as_ref(), None, None, Order::Ascending)
+      .collect::<StdResult<_>>()
+      .unwrap();
+   for (addr, account) in accounts {
+      Map::new("dg/a")
+         .save(
+            storage.as_mut(),
+            addr,
+            &DecayGameAccountV0_1 {
+               amount: account.amount,
+               decay_snapshot: account.decay_snapshot,
+               pending: account.pending,
+            },
+         )
+         .unwrap();
+   }
+
+   JOINS.clear(storage.as_mut());
+   PARTICIPANTS.remove(storage.as_mut());
+   ROUND_IDX.remove(storage.as_mut());
+
+   set_contract_version(storage.as_mut(), "crates.io:crack-the-vault", "0.1.1-rc1").unwrap();
+}
+
+fn set_version(env: &mut TestEnv, name: &str, version: &str) {
+   let mut storage = env.app.contract_storage_mut(&env.contracts.game);
+   set_contract_version(storage.as_mut(), name, version).unwrap();
+}
+
+define_test! {
+    name: test_migrate_from_v0_1,
+    game: {
+        owner: "owner",
+        ticket_denom: DENOM,
+        ticket_amount: Uint128::new(100),
+        start_at: Timestamp::from_seconds(1000),
+        duration_seconds: 1000u64,
+        donation_addrs: vec![],
+        fees: vec![
+            Decimal::percent(10),
+            Decimal::percent(10),
+            Decimal::percent(10)
+        ],
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+        carol: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(100, DENOM)).unwrap();
+        env.join("carol", None, coins(100, DENOM)).unwrap();
+        env.exit("carol").unwrap();
+
+        downgrade_to_v0_1(env);
+
+        // the old layout cannot be read by the new code
+        env.exit("alice").unwrap_err();
+
+        // only the admin can migrate
+        env.migrate("alice").unwrap_err();
+        env.migrate("owner").unwrap();
+
+        let version = get_contract_version(env.app.contract_storage(&env.contracts.game).as_ref()).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        let config: Config = env.app.wrap().query_wasm_smart(env.contracts.game.clone(), &QueryMsg::Config {}).unwrap();
+        assert_eq!(config.decay_curve, DecayCurve::Linear);
+        assert_eq!(config.ticket_amount, Uint128::new(100));
+        assert_eq!(config.winner_rule, WinnerRule::LastJoin);
+
+        // exits of v0.1 were claimed on the spot and are stamped with the migration time
+        let carol: DecayGameAccount = env.app.wrap().query_wasm_smart(env.contracts.game.clone(), &QueryMsg::Account { addr: env.addr("carol") }).unwrap();
+        assert_eq!(carol.status, AccountStatus::Claimed { at: Timestamp::from_seconds(1000) });
+        let alice: DecayGameAccount = env.app.wrap().query_wasm_smart(env.contracts.game.clone(), &QueryMsg::Account { addr: env.addr("alice") }).unwrap();
+        assert_eq!(alice.status, AccountStatus::Joined);
+
+        // the join order is rebuilt from the live accounts
+        let winner = env.winner().winner.unwrap();
+        assert!(winner == env.addr("alice") || winner == env.addr("bob"));
+
+        // the live round keeps going after the migration
+        let game: DecayGame = env.app.wrap().query_wasm_smart(env.contracts.game.clone(), &QueryMsg::GameStatus {}).unwrap();
+        assert_eq!(game.total, Uint128::new(300));
+        assert_eq!(game.curve, DecayCurve::Linear);
+
+        env.move_block(500);
+        env.exit("alice").unwrap();
+        env.assert_balance("alice", coin(150u128, DENOM));
+
+        // the live round is the first one archived
+        assert!(env.round_history(None, None).is_empty());
+        env.move_block(501);
+        env.settle("alice").unwrap();
+        assert_eq!(env.round(1).unwrap().participants, 3);
+
+        // migrating again from the same version is a no-op
+        env.migrate("owner").unwrap();
+    }
+}
+
+define_test! {
+    name: test_migrate_refuses_downgrade,
+    game: {
+        owner: "owner",
+        ticket_denom: DENOM,
+        ticket_amount: Uint128::new(100),
+        start_at: Timestamp::from_seconds(1000),
+        duration_seconds: 1000u64,
+        donation_addrs: vec![],
+        fees: vec![
+            Decimal::percent(10),
+            Decimal::percent(10),
+            Decimal::percent(10)
+        ],
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        set_version(env, "crates.io:crack-the-vault", "99.0.0");
+        env.migrate("owner").unwrap_err();
+
+        set_version(env, "hitnrug", "0.1.0-rc1");
+        env.migrate("owner").unwrap_err();
+    }
+}
@@ -4,6 +4,7 @@ mod referral;
 #[cfg(test)]
 mod fin;
 
+mod migrations;
 mod test_cases;
 mod test_helpers;
 mod test_macros;
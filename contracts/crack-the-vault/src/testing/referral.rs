@@ -31,6 +31,9 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
    match msg {
       ExecuteMsg::GenCode { .. } => todo!(),
+      ExecuteMsg::ChangeCode { .. } => todo!(),
+      ExecuteMsg::RevokeCode { .. } => todo!(),
+      ExecuteMsg::TransferCode { .. } => todo!(),
       ExecuteMsg::AddReferee { code: _, referee: _ } => {
          Ok(Response::default().add_event(Event::new("referral/add_referee")))
       }
@@ -49,25 +52,28 @@ pub fn execute(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
-   Ok(match msg {
+   match msg {
       QueryMsg::Config {} => todo!(),
       QueryMsg::GetCode { .. } => todo!(),
       QueryMsg::GetAddr { code } => get_addr(code, deps.api),
       QueryMsg::GetReferrer { user } => get_referrer(user, deps.api),
       QueryMsg::ReferralStructure { .. } => todo!(),
+      QueryMsg::ReferralCount { .. } => todo!(),
+      QueryMsg::Upline { .. } => todo!(),
+      QueryMsg::TierEarnings { .. } => todo!(),
       QueryMsg::PendingRewards { .. } => todo!(),
-   }?)
+   }
 }
 
 fn get_addr(code: String, api: &dyn Api) -> Result<Binary, ContractError> {
    let valid_code = "VALID_CODE".to_string();
    if code == valid_code {
-      return Ok(to_json_binary(&to_addr(
+      Ok(to_json_binary(&to_addr(
          "cosmwasm1se09wrdugr8m62wwd6xgrukuvqjntf9e73p9lmexwkry35sh3v5s52j5fn".to_string(), //VALID_AMBASSADOR => To Addr
          api,
-      )?)?);
+      )?)?)
    } else {
-      return Err(ContractError::Std(StdError::not_found("code")));
+      Err(ContractError::Std(StdError::not_found("code")))
    }
 }
 
@@ -77,11 +83,11 @@ fn get_referrer(user: Addr, api: &dyn Api) -> Result<Binary, ContractError> {
       api,
    )?; //VALID_USER => to Addr
    if user == valid_user {
-      return Ok(to_json_binary(&to_addr(
+      Ok(to_json_binary(&to_addr(
          "cosmwasm1se09wrdugr8m62wwd6xgrukuvqjntf9e73p9lmexwkry35sh3v5s52j5fn".to_string(), //VALID_AMBASSADOR => To Addr
          api,
-      )?)?);
+      )?)?)
    } else {
-      return Ok(to_json_binary("")?);
+      Ok(to_json_binary("")?)
    }
 }
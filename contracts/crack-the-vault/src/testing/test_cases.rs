@@ -1,6 +1,18 @@
 use cosmwasm_std::{coin, coins, Decimal, Timestamp, Uint128};
+use wenruji_rs::{
+   keeper::{KeeperMsg, NextAction},
+   AccountStatus, DecayCurve,
+};
 
-use super::test_macros::define_test;
+use crate::{
+   config::WinnerRule,
+   state::{DECAY_GAME, PENDING_ENTRY},
+};
+
+use super::{
+   test_helpers::{TestEnv, DENOM, PRIZE_DENOM},
+   test_macros::define_test,
+};
 
 define_test! {
     name: test_lifecycle,
@@ -50,9 +62,16 @@ define_test! {
         env.donate("donald", vec![coin(100u128, "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9"), coin(100u128, "donate")]).unwrap(); //success he's whitelisted
         env.donate("alice", vec![coin(10u128, "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9")]).unwrap_err(); //error alice is not whitelisted
 
+        // Test Donations pages, ordered by denom
+        assert_eq!(env.donations(None, None), vec![coin(100u128, "donate"), coin(100u128, "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9")]);
+        assert_eq!(env.donations(None, Some(1)), vec![coin(100u128, "donate")]);
+        assert_eq!(env.donations(Some("donate"), Some(1)), vec![coin(100u128, "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9")]);
+
         // Test Exit
         env.exit("donald").unwrap_err(); // he didnt join
+        assert!(env.account("donald").is_none());
         env.exit("alice").unwrap();
+        assert!(!env.account("alice").unwrap().decay_snapshot.is_zero());
         env.assert_balance("alice", coin(200u128, "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9")); // she gets everything back because the time is not moved
 
         env.move_block(500);
@@ -70,16 +89,528 @@ define_test! {
 
         //TEST END
         env.set_block(Timestamp::from_seconds(1500)); //go back to test error if endgame before the time
-        env.endgame("owner", "alice", false).unwrap_err(); //not ended
+        env.endgame("owner", false).unwrap_err(); //not ended
 
         env.set_block(Timestamp::from_seconds(2001)); //end competition
-        env.endgame("not_owner", "alice", false).unwrap_err(); //error only owner can set the winner
+        env.endgame("not_owner", false).unwrap_err(); //error only owner can end the game
 
-        env.endgame("owner", "alice", true).unwrap();
+        // VALID_USER is the only one who never exited
+        assert_eq!(env.winner().winner, Some(env.addr("VALID_USER")));
+        env.endgame("owner", true).unwrap();
 
         env.set_block(Timestamp::from_seconds(4001)); //go back to test error if endgame before the time
-        env.endgame("owner", "alice", true).unwrap();
+        env.endgame("owner", true).unwrap();
 
         env.join("alice", None, coins(100, "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9")).unwrap();
     }
 }
+
+define_test! {
+    name: test_step_decay_curve,
+    game: {
+        decay_curve: Some(DecayCurve::Step { steps: 4 }),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(100, DENOM)).unwrap();
+
+        // still in the first tier => full refund
+        env.set_block(Timestamp::from_seconds(1249));
+        env.exit("alice").unwrap();
+        env.assert_balance("alice", coin(200u128, DENOM));
+
+        // third tier => half refund
+        env.set_block(Timestamp::from_seconds(1500));
+        env.exit("bob").unwrap();
+        env.assert_balance("bob", coin(150u128, DENOM));
+    }
+}
+
+define_test! {
+    name: test_winner_last_join,
+    game: {
+        winner_rule: Some(WinnerRule::LastJoin),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+        charlie: coins(200u128, DENOM),
+        dave: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        assert_winner_rule(env, WinnerRule::LastJoin, "charlie");
+    }
+}
+
+define_test! {
+    name: test_winner_last_standing,
+    game: {
+        winner_rule: Some(WinnerRule::LastStanding),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+        charlie: coins(200u128, DENOM),
+        dave: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        assert_winner_rule(env, WinnerRule::LastStanding, "bob");
+    }
+}
+
+define_test! {
+    name: test_no_winner_keeps_prize,
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // everybody exits halfway => nobody to pay
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.set_block(Timestamp::from_seconds(1500));
+        env.exit("alice").unwrap();
+        assert_eq!(env.winner().winner, None);
+
+        env.set_block(Timestamp::from_seconds(2001));
+        env.endgame("owner", true).unwrap();
+        env.assert_balance("alice", coin(150u128, DENOM));
+
+        // the 50 left behind are paid, unswapped, to the next winner with the new prize
+        env.join("bob", None, coins(100, DENOM)).unwrap();
+        env.set_block(Timestamp::from_seconds(3002));
+        env.endgame("owner", true).unwrap();
+        env.assert_balance("bob", coin(150u128, DENOM));
+        env.assert_balance("bob", coin(140u128, PRIZE_DENOM));
+    }
+}
+
+define_test! {
+    name: test_lottery_winner,
+    game: {
+        winner_rule: Some(WinnerRule::Lottery {
+            reveal_seconds: 100,
+            reveal_bond: Decimal::percent(10),
+        }),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+        charlie: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(1000));
+        // a commitment is required to enter the draw
+        env.join("alice", None, coins(100, DENOM)).unwrap_err();
+        env.join_with_secret("alice", "alice secret", coins(100, DENOM)).unwrap();
+        env.join_with_secret("bob", "bob secret", coins(100, DENOM)).unwrap();
+        env.join_with_secret("charlie", "charlie secret", coins(100, DENOM)).unwrap();
+
+        env.set_block(Timestamp::from_seconds(1500));
+        env.exit("bob").unwrap();
+        env.reveal("alice", "alice secret").unwrap_err(); // game not ended
+
+        env.set_block(Timestamp::from_seconds(2001));
+        env.reveal("alice", "wrong secret").unwrap_err();
+        env.reveal("alice", "alice secret").unwrap();
+        env.reveal("bob", "bob secret").unwrap(); // exited players only add entropy
+        env.endgame("owner", false).unwrap_err(); // reveal still open
+
+        // charlie missed the reveal window and is left out of the draw
+        env.set_block(Timestamp::from_seconds(2101));
+        env.reveal("charlie", "charlie secret").unwrap_err();
+        assert_eq!(env.winner().winner, Some(env.addr("alice")));
+
+        // alice gets her 10% reveal bond back, charlie forfeits his to the prize
+        env.set_block(Timestamp::from_seconds(2102));
+        env.endgame("owner", false).unwrap();
+        env.assert_balance("alice", coin(110u128, DENOM));
+        env.assert_balance("charlie", coin(100u128, DENOM));
+        assert!(matches!(env.account("charlie").unwrap().status, AccountStatus::Forfeited { .. }));
+
+        // 240 left in the vault, swapped at 2 => 480, the winner takes 70%
+        env.assert_balance("alice", coin(336u128, PRIZE_DENOM));
+        env.assert_balance("charlie", coin(0u128, PRIZE_DENOM));
+    }
+}
+
+define_test! {
+    name: test_lazy_rollover,
+    game: {
+        settlement_fee: Some(Decimal::percent(1)),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+        charlie: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(100, DENOM)).unwrap();
+
+        // nobody ends the round, charlie's join settles it and enters the next one
+        env.set_block(Timestamp::from_seconds(2001));
+        env.join("charlie", None, coins(100, DENOM)).unwrap();
+        // 2 of the 200 pot settle the round, 198 swapped at 2 => 396, the winner takes 70%
+        env.assert_balance("charlie", coin(102u128, DENOM));
+        env.assert_balance("bob", coin(277u128, PRIZE_DENOM));
+        assert!(env.account("alice").is_none());
+        assert!(env.account("charlie").is_some());
+
+        // exiting a finished round settles it instead, alice gets the fee
+        env.set_block(Timestamp::from_seconds(3002));
+        env.exit("alice").unwrap();
+        env.assert_balance("alice", coin(101u128, DENOM));
+        env.assert_balance("charlie", coin(138u128, PRIZE_DENOM));
+        assert!(env.account("charlie").is_none());
+
+        // a round nobody joined only restarts
+        env.set_block(Timestamp::from_seconds(4003));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.assert_balance("alice", coin(1u128, DENOM));
+    }
+}
+
+define_test! {
+    name: test_prize_swap_fallback,
+    game: {
+        ticket_denom: "uatom",
+    },
+    accounts: {
+        alice: coins(200u128, "uatom"),
+        bob: coins(200u128, "uatom"),
+        charlie: coins(200u128, "uatom"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, "uatom")).unwrap();
+        env.join("bob", None, coins(100, "uatom")).unwrap();
+
+        // the mock FIN has no uatom market, the winner takes 70% of the pot unswapped
+        env.set_block(Timestamp::from_seconds(2001));
+        let res = env.join("charlie", None, coins(100, "uatom")).unwrap();
+        assert!(res.events.iter().any(|event| event.ty == "wasm-crack-the-valut/prize_fallback"));
+        env.assert_balance("bob", coin(240u128, "uatom"));
+        assert_eq!(env.round(1).unwrap().swapped_prize, Some(coin(200u128, "uatom")));
+        assert_eq!(env.player_stats("bob").prizes, coins(140u128, "uatom"));
+
+        // the join went through with the rollover
+        assert!(env.account("charlie").is_some());
+        env.assert_balance("charlie", coin(100u128, "uatom"));
+    }
+}
+
+define_test! {
+    name: test_settle,
+    game: {
+        winner_rule: Some(WinnerRule::Lottery {
+            reveal_seconds: 100,
+            reveal_bond: Decimal::percent(10),
+        }),
+        settlement_fee: Some(Decimal::percent(2)),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // the reveal window is part of the round
+        assert_eq!(
+            env.next_action(),
+            NextAction { at: Timestamp::from_seconds(2101), msg: KeeperMsg::Settle {} }
+        );
+
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join_with_secret("alice", "alice secret", coins(100, DENOM)).unwrap();
+        env.set_block(Timestamp::from_seconds(2001));
+        env.reveal("alice", "alice secret").unwrap();
+        env.settle("bob").unwrap();
+        assert!(env.account("alice").is_some()); // reveal still open
+
+        // anyone settles once the round is over and takes the fee
+        env.set_block(Timestamp::from_seconds(2101));
+        env.settle("bob").unwrap();
+        env.assert_balance("bob", coin(201u128, DENOM));
+        // 10 of the 100 pot back to alice, 89 swapped at 2 => 178, the winner takes 70%
+        env.assert_balance("alice", coin(110u128, DENOM));
+        env.assert_balance("alice", coin(124u128, PRIZE_DENOM));
+        assert!(env.account("alice").is_none());
+        assert_eq!(env.next_action().at, Timestamp::from_seconds(3202));
+
+        // settling again is a no-op
+        env.settle("bob").unwrap();
+        env.assert_balance("bob", coin(201u128, DENOM));
+    }
+}
+
+define_test! {
+    name: test_round_history,
+    game: {
+        donation_addrs: vec![MockApi::default().addr_make("donald")],
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(200u128, DENOM),
+        donald: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // round 1: bob holds until the end and takes the donations
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(100, DENOM)).unwrap();
+        env.donate("donald", coins(50, DENOM)).unwrap();
+        env.set_block(Timestamp::from_seconds(1500));
+        env.exit("alice").unwrap();
+        assert!(env.round_history(None, None).is_empty());
+
+        env.set_block(Timestamp::from_seconds(2001));
+        env.settle("donald").unwrap();
+        let round = env.round(1).unwrap();
+        assert_eq!(round.winner, Some(env.addr("bob")));
+        assert_eq!(round.prize, coin(150u128, DENOM));
+        assert_eq!(round.swapped_prize, Some(coin(300u128, PRIZE_DENOM)));
+        assert_eq!(round.total, Uint128::new(200));
+        assert_eq!(round.exited, Uint128::new(50));
+        assert_eq!(round.donations, coins(50, DENOM));
+        // alice exited but still counts as a participant
+        assert_eq!(round.participants, 2);
+        assert_eq!(round.starts_at, Timestamp::from_seconds(1000));
+        assert_eq!(round.ends_at, Timestamp::from_seconds(2000));
+
+        // round 2: nobody joins
+        env.set_block(Timestamp::from_seconds(3002));
+        env.settle("donald").unwrap();
+        let round = env.round(2).unwrap();
+        assert_eq!(round.winner, None);
+        assert_eq!(round.participants, 0);
+        assert!(round.donations.is_empty());
+        assert_eq!(env.round(3), None);
+
+        // latest first
+        let history = env.round_history(None, None);
+        assert_eq!(history.iter().map(|(idx, _)| *idx).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(env.round_history(Some(2), Some(1))[0].0, 1);
+    }
+}
+
+define_test! {
+    name: test_player_stats,
+    game: {
+        donation_addrs: vec![MockApi::default().addr_make("donald")],
+    },
+    accounts: {
+        alice: coins(300u128, DENOM),
+        bob: coins(300u128, DENOM),
+        donald: coins(300u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // round 1: alice exits half way, bob wins the swapped pot and the donations
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(100, DENOM)).unwrap();
+        env.donate("donald", coins(50, DENOM)).unwrap();
+        env.set_block(Timestamp::from_seconds(1500));
+        env.exit("alice").unwrap();
+        env.set_block(Timestamp::from_seconds(2001));
+        env.settle("donald").unwrap();
+
+        // round 2: alice exits with 70% left
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.set_block(Timestamp::from_seconds(2301));
+        env.exit("alice").unwrap();
+
+        let alice = env.player_stats("alice");
+        assert_eq!((alice.rounds_joined, alice.rounds_exited, alice.wins), (2, 2, 0));
+        assert_eq!(alice.avg_exit_decay, Decimal::percent(60));
+        assert_eq!(alice.paid_in, Uint128::new(200));
+        assert_eq!(alice.paid_out, Uint128::new(120));
+        assert!(alice.prizes.is_empty());
+
+        let bob = env.player_stats("bob");
+        assert_eq!((bob.rounds_joined, bob.rounds_exited, bob.wins), (1, 0, 1));
+        assert_eq!(bob.paid_in, Uint128::new(100));
+        assert_eq!(bob.prizes, vec![coin(50, DENOM), coin(210, PRIZE_DENOM)]);
+        assert_eq!(bob.hits_given, 0);
+
+        // unknown accounts have empty stats
+        assert_eq!(env.player_stats("donald").rounds_joined, 0);
+    }
+}
+
+define_test! {
+    name: test_entry_swap,
+    game: {
+        entry_denoms: Some(vec![PRIZE_DENOM.to_string()]),
+    },
+    accounts: {
+        alice: vec![coin(200u128, PRIZE_DENOM), coin(200, "uatom")],
+        bob: vec![coin(200u128, PRIZE_DENOM), coin(200, "uatom")],
+        charlie: vec![coin(200u128, PRIZE_DENOM), coin(200, "uatom")],
+    },
+    test_fn: |env: &mut TestEnv| {
+        let entry_denom = PRIZE_DENOM;
+        env.set_block(Timestamp::from_seconds(1000));
+
+        // the mock FIN pays 2 ticket denom per entry denom, the change is sent back
+        env.join("alice", None, coins(60, entry_denom)).unwrap();
+        assert_eq!(env.account("alice").unwrap().amount, Uint128::new(100));
+        env.assert_balance("alice", coin(140u128, entry_denom));
+        env.assert_balance("alice", coin(20u128, DENOM));
+        assert_eq!(env.player_stats("alice").paid_in, Uint128::new(100));
+
+        // a swap returning less than a ticket is refunded
+        let res = env.join("bob", None, coins(40, entry_denom)).unwrap();
+        assert!(res.events.iter().any(|event| event.ty == "wasm-crack-the-valut/entry_refund"));
+        assert_eq!(env.account("bob"), None);
+        env.assert_balance("bob", coin(200u128, entry_denom));
+        env.assert_balance("bob", coin(0u128, DENOM));
+
+        // denoms off the whitelist are rejected
+        env.join("charlie", None, coins(100, "uatom")).unwrap_err();
+        env.join("alice", None, coins(60, entry_denom)).unwrap_err();
+
+        assert!(!PENDING_ENTRY.exists(env.app.contract_storage(&env.contracts.game).as_ref()));
+        let game = DECAY_GAME.load(env.app.contract_storage(&env.contracts.game).as_ref()).unwrap();
+        assert_eq!(game.total, Uint128::new(100));
+    }
+}
+
+define_test! {
+    name: test_variable_stakes,
+    game: {
+        max_ticket_amount: Some(Uint128::new(300)),
+    },
+    accounts: {
+        alice: coins(500u128, DENOM),
+        bob: coins(500u128, DENOM),
+        charlie: coins(500u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // the game holds exactly the pool left to the winner
+        let assert_pot = |env: &TestEnv| {
+            let game = DECAY_GAME.load(env.app.contract_storage(&env.contracts.game).as_ref()).unwrap();
+            assert_eq!(env.game_balance(DENOM), game.total - game.exited);
+        };
+
+        // stakes within the ticket range
+        env.set_block(Timestamp::from_seconds(900));
+        env.join("charlie", None, coins(50, DENOM)).unwrap_err();
+        env.join("charlie", None, coins(301, DENOM)).unwrap_err();
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(250, DENOM)).unwrap();
+
+        // top-ups before the decay starts, up to the largest stake
+        env.top_up("alice", coins(150, DENOM)).unwrap();
+        env.top_up("bob", coins(100, DENOM)).unwrap_err();
+        env.top_up("charlie", coins(100, DENOM)).unwrap_err();
+        assert_eq!(env.account("alice").unwrap().amount, Uint128::new(250));
+        assert_pot(env);
+
+        env.set_block(Timestamp::from_seconds(1000));
+        env.top_up("bob", coins(50, DENOM)).unwrap_err();
+
+        // exits are pro-rata to the stake
+        env.set_block(Timestamp::from_seconds(1500));
+        env.exit("alice").unwrap();
+        env.assert_balance("alice", coin(375u128, DENOM));
+        assert_pot(env);
+
+        // the 375 left is swapped for the winner
+        env.set_block(Timestamp::from_seconds(2001));
+        env.settle("charlie").unwrap();
+        let round = env.round(1).unwrap();
+        assert_eq!(round.prize, coin(375u128, DENOM));
+        assert_eq!(round.total - round.exited, Uint128::new(375));
+        assert_eq!(round.swapped_prize, Some(coin(750u128, PRIZE_DENOM)));
+        env.assert_balance("bob", coin(525u128, PRIZE_DENOM));
+        assert_eq!(env.game_balance(DENOM), Uint128::zero());
+    }
+}
+
+define_test! {
+    name: test_exit_partial,
+    game: {
+        max_ticket_amount: Some(Uint128::new(200)),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(300u128, DENOM),
+        charlie: coins(200u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(200, DENOM)).unwrap();
+
+        // half of the decay, the stake left can't drop below a ticket
+        env.set_block(Timestamp::from_seconds(1500));
+        env.exit_partial("bob", 200).unwrap_err();
+        env.exit_partial("bob", 150).unwrap_err();
+        env.exit_partial("alice", 50).unwrap_err();
+        env.exit_partial("bob", 100).unwrap();
+        env.assert_balance("bob", coin(150u128, DENOM));
+        let account = env.account("bob").unwrap();
+        assert_eq!(account.amount, Uint128::new(100));
+        assert_eq!(account.principal(), Uint128::new(200));
+
+        // a partial exit keeps the account in the game
+        assert_eq!(env.winner().winner, Some(env.addr("bob")));
+        env.set_block(Timestamp::from_seconds(2001));
+        env.settle("charlie").unwrap();
+        let round = env.round(1).unwrap();
+        assert_eq!(round.total - round.exited, Uint128::new(250));
+        assert_eq!(round.swapped_prize, Some(coin(500u128, PRIZE_DENOM)));
+        env.assert_balance("bob", coin(350u128, PRIZE_DENOM));
+    }
+}
+
+define_test! {
+    name: test_exit_partial_after_decay_end,
+    game: {
+        max_ticket_amount: Some(Uint128::new(200)),
+    },
+    accounts: {
+        alice: coins(200u128, DENOM),
+        bob: coins(300u128, DENOM),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(1000));
+        env.join("alice", None, coins(100, DENOM)).unwrap();
+        env.join("bob", None, coins(200, DENOM)).unwrap();
+
+        // a partial exit from a finished round settles it instead, nothing is paid back
+        env.set_block(Timestamp::from_seconds(2001));
+        env.exit_partial("bob", 100).unwrap();
+        env.assert_balance("bob", coin(100u128, DENOM));
+        assert!(env.account("bob").is_none());
+        let round = env.round(1).unwrap();
+        assert_eq!(round.total - round.exited, Uint128::new(300));
+        env.assert_balance("bob", coin(420u128, PRIZE_DENOM));
+    }
+}
+
+/// Four players join, the first and the last to join leave halfway
+fn assert_winner_rule(env: &mut TestEnv, rule: WinnerRule, winner: &str) {
+   env.set_block(Timestamp::from_seconds(1000));
+   env.join("alice", None, coins(100, DENOM)).unwrap();
+   env.join("bob", None, coins(100, DENOM)).unwrap();
+   env.join("charlie", None, coins(100, DENOM)).unwrap();
+   env.join("dave", None, coins(100, DENOM)).unwrap();
+   assert_eq!(env.winner().rule, rule);
+
+   env.set_block(Timestamp::from_seconds(1500));
+   env.exit("alice").unwrap();
+   env.exit("dave").unwrap();
+   assert_eq!(env.winner().winner, Some(env.addr(winner)));
+
+   // 300 left in the vault, swapped at 2 => 600, the winner takes 70%
+   env.set_block(Timestamp::from_seconds(2001));
+   env.endgame("owner", false).unwrap();
+   env.assert_balance(winner, coin(420u128, PRIZE_DENOM));
+}
@@ -1,27 +1,38 @@
 use cosmwasm_std::{
-   coin, testing::MockApi, to_json_string, Addr, Coin, Decimal, Timestamp, Uint128,
+   coin, testing::MockApi, to_json_string, Addr, Binary, Coin, Decimal, Timestamp, Uint128,
 };
 use cw_multi_test::{App, AppResponse, BasicAppBuilder, ContractWrapper, Executor};
 use referral::{self};
+use wenruji_rs::{
+   keeper::NextAction, randomness::commitment, DecayCurve, DecayGameAccount, PlayerStats,
+};
 
 use crate::{
    //config::ConfigUpdate,
-   contract::{execute, instantiate, query},
-   msg::{Contracts, ExecuteMsg, Fee, Fees, InstantiateMsg},
+   config::WinnerRule,
+   contract::{execute, instantiate, migrate, query, reply},
+   msg::{Contracts, ExecuteMsg, Fee, Fees, InstantiateMsg, MigrateMsg, QueryMsg, WinnerResponse},
+   state::RoundSummary,
 };
 
+#[allow(dead_code)]
 pub struct TestEnv {
    pub app: App,
    pub owner: Addr,
    pub contracts: MockContracts,
 }
 
+#[allow(dead_code)]
 pub struct MockContracts {
    pub game: Addr,
    pub fin: Addr,
    pub referral: Addr,
 }
 
+/// Ticket denom, the mock FIN swaps it for `PRIZE_DENOM` at 2
+pub const DENOM: &str = "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9";
+pub const PRIZE_DENOM: &str = "kujira1v4h0t7dfguwg927y6zz496wxc88lc96ekl6fnc3r5yqty9snlrnsm0ee6t";
+
 pub struct PartialInstantiate {
    pub owner: Addr,
    pub ticket_denom: String,
@@ -31,6 +42,11 @@ pub struct PartialInstantiate {
    pub game_delay: u64,
    pub donation_addrs: Vec<Addr>,
    pub fees: Vec<Decimal>,
+   pub decay_curve: Option<DecayCurve>,
+   pub winner_rule: Option<WinnerRule>,
+   pub settlement_fee: Option<Decimal>,
+   pub entry_denoms: Option<Vec<String>>,
+   pub max_ticket_amount: Option<Uint128>,
 }
 
 pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantiate) -> TestEnv {
@@ -51,6 +67,8 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
             owner: app.api().addr_make("owner"),
             whitelisted_denoms: referral::msg::Whitelist::All,
             whitelisted_contracts: referral::msg::Whitelist::All,
+            tiers: None,
+            max_depth: None,
          },
          &[],
          "fin",
@@ -73,6 +91,8 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
             owner: app.api().addr_make("owner"),
             whitelisted_denoms: referral::msg::Whitelist::All,
             whitelisted_contracts: referral::msg::Whitelist::All,
+            tiers: None,
+            max_depth: None,
          },
          &[],
          "referral",
@@ -80,7 +100,9 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
       )
       .unwrap();
 
-   let game_code_id = app.store_code(Box::new(ContractWrapper::new(execute, instantiate, query)));
+   let game_code_id = app.store_code(Box::new(
+      ContractWrapper::new(execute, instantiate, query).with_reply(reply).with_migrate(migrate),
+   ));
 
    let game_addr = app
       .instantiate_contract(
@@ -101,10 +123,15 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
                fee_ref: Fee { address: ref_addr.clone(), fee: config.fees[2] },
             },
             admins: None,
+            decay_curve: config.decay_curve,
+            winner_rule: config.winner_rule,
+            settlement_fee: config.settlement_fee,
+            entry_denoms: config.entry_denoms,
+            max_ticket_amount: config.max_ticket_amount,
          },
          &[],
          "game",
-         None,
+         Some(app.api().addr_make("owner").to_string()),
       )
       .unwrap();
 
@@ -119,11 +146,11 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
             &fin_addr.clone(),
             vec![
                coin(
-                  1_000_000_000_00u128,
+                  100_000_000_000u128,
                   "kujira1v4h0t7dfguwg927y6zz496wxc88lc96ekl6fnc3r5yqty9snlrnsm0ee6t",
                ),
                coin(
-                  1_000_000_000_00u128,
+                  100_000_000_000u128,
                   "kujira15drytn4ntvg7f292ncul6wcxle4xe404q280hcw9878zjqa2h9nqulj2e9",
                ),
             ],
@@ -148,7 +175,7 @@ pub fn create_partial_instantiate(
    fees: Vec<Decimal>,
 ) -> PartialInstantiate {
    PartialInstantiate {
-      owner: MockApi::default().addr_make(&owner),
+      owner: MockApi::default().addr_make(owner),
       ticket_denom: ticket_denom.to_string(),
       ticket_amount,
       starts_at,
@@ -156,6 +183,26 @@ pub fn create_partial_instantiate(
       donation_addrs,
       fees,
       game_delay: 0u64,
+      decay_curve: None,
+      winner_rule: None,
+      settlement_fee: None,
+      entry_denoms: None,
+      max_ticket_amount: None,
+   }
+}
+
+/// A 100 ticket game of 1000 seconds starting at 1000, 10% for each fee and no donation addresses
+impl Default for PartialInstantiate {
+   fn default() -> Self {
+      create_partial_instantiate(
+         "owner",
+         DENOM,
+         Uint128::new(100),
+         Timestamp::from_seconds(1000),
+         1000u64,
+         vec![],
+         vec![Decimal::percent(10), Decimal::percent(10), Decimal::percent(10)],
+      )
    }
 }
 
@@ -182,11 +229,58 @@ impl TestEnv {
       self.app.execute_contract(
          self.addr(account),
          self.contracts.game.clone(),
-         &ExecuteMsg::Join { ref_code },
+         &ExecuteMsg::Join { ref_code, commitment: None },
          &funds,
       )
    }
 
+   pub fn exit_partial(&mut self, account: &str, amount: u128) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::ExitPartial { amount: Uint128::new(amount) },
+         &[],
+      )
+   }
+
+   pub fn top_up(&mut self, account: &str, funds: Vec<Coin>) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::TopUp {},
+         &funds,
+      )
+   }
+
+   /// Funds held by the game contract
+   pub fn game_balance(&self, denom: &str) -> Uint128 {
+      self.app.wrap().query_balance(self.contracts.game.clone(), denom).unwrap().amount
+   }
+
+   pub fn join_with_secret(
+      &mut self,
+      account: &str,
+      secret: &str,
+      funds: Vec<Coin>,
+   ) -> anyhow::Result<AppResponse> {
+      let commitment = commitment(&self.addr(account), secret.as_bytes());
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::Join { ref_code: None, commitment: Some(commitment) },
+         &funds,
+      )
+   }
+
+   pub fn reveal(&mut self, account: &str, secret: &str) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::Reveal { secret: Binary::from(secret.as_bytes()) },
+         &[],
+      )
+   }
+
    pub fn exit(&mut self, account: &str) -> anyhow::Result<AppResponse> {
       self.app.execute_contract(
          self.addr(account),
@@ -205,20 +299,102 @@ impl TestEnv {
       )
    }
 
-   pub fn endgame(
-      &mut self,
-      account: &str,
-      winner: &str,
-      restart: bool,
-   ) -> anyhow::Result<AppResponse> {
+   pub fn endgame(&mut self, account: &str, restart: bool) -> anyhow::Result<AppResponse> {
       self.app.execute_contract(
          self.addr(account),
          self.contracts.game.clone(),
-         &ExecuteMsg::EndGame { winner: self.addr(winner), restart },
+         &ExecuteMsg::EndGame { restart },
          &[],
       )
    }
 
+   pub fn settle(&mut self, account: &str) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::Settle {},
+         &[],
+      )
+   }
+
+   pub fn next_action(&self) -> NextAction {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::NextAction {})
+         .unwrap()
+   }
+
+   pub fn account(&self, account: &str) -> Option<DecayGameAccount> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::Account { addr: self.addr(account) },
+         )
+         .unwrap()
+   }
+
+   pub fn donations(&self, start_after: Option<&str>, limit: Option<u32>) -> Vec<Coin> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::Donations { start_after: start_after.map(str::to_string), limit },
+         )
+         .unwrap()
+   }
+
+   pub fn winner(&self) -> WinnerResponse {
+      self.app.wrap().query_wasm_smart(self.contracts.game.clone(), &QueryMsg::Winner {}).unwrap()
+   }
+
+   pub fn round_history(
+      &self,
+      start_after: Option<u64>,
+      limit: Option<u32>,
+   ) -> Vec<(u64, RoundSummary)> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::RoundHistory { start_after, limit },
+         )
+         .unwrap()
+   }
+
+   pub fn round(&self, idx: u64) -> Option<RoundSummary> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::Round { idx })
+         .unwrap()
+   }
+
+   pub fn player_stats(&self, account: &str) -> PlayerStats {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::PlayerStats { addr: self.addr(account) },
+         )
+         .unwrap()
+   }
+
+   pub fn migrate(&mut self, account: &str) -> anyhow::Result<AppResponse> {
+      let code_id = self.app.contract_data(&self.contracts.game)?.code_id;
+      self.app.migrate_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &MigrateMsg {},
+         code_id,
+      )
+   }
+
    // pub fn update_config(
    //    &mut self,
    //    account: &str,
@@ -286,6 +462,11 @@ fn test_serialize_instantiate_msg() {
       },
       game_delay: 300u64,
       admins: Some(vec![Addr::unchecked("kujira1y3ztnmghrmsa8d8h5ny7h2lvq4w3lre9hvwhcw")]),
+      decay_curve: None,
+      winner_rule: None,
+      settlement_fee: None,
+      entry_denoms: None,
+      max_ticket_amount: None,
    };
 
    // Serialize the InstantiateMsg instance to JSON
@@ -2,15 +2,9 @@ mod macros {
    macro_rules! define_test {
         (
             name: $name:ident,
-            game: {
-                owner: $owner:expr,
-                ticket_denom: $ticket_denom:expr,
-                ticket_amount: $ticket_amount:expr,
-                start_at: $start_at:expr,
-                duration_seconds: $duration_seconds:expr,
-                donation_addrs: $donation_addrs:expr,
-                fees: $fees:expr,
-            },
+            $(game: {
+                $($game:tt)*
+            },)?
             accounts: {
                 $($account:ident: $balance:expr),* $(,)?
             },
@@ -18,19 +12,13 @@ mod macros {
         ) => {
             #[test]
             fn $name() {
-                use crate::testing::test_helpers::{setup_test_env, TestEnv, create_partial_instantiate};
+                use crate::testing::test_helpers::{setup_test_env, TestEnv, PartialInstantiate};
                 use cosmwasm_std::testing::MockApi;
 
-                // Set up the contract competition configuration
-                let config = create_partial_instantiate (
-                    $owner,
-                    $ticket_denom,
-                    $ticket_amount,
-                    $start_at,
-                    $duration_seconds,
-                    $donation_addrs,
-                    $fees,
-                );
+                // Set up the contract competition configuration, `game` overrides the defaults
+                #[allow(unused_mut)]
+                let mut config = PartialInstantiate::default();
+                $(define_test!(@game config; $($game)*);)?
 
                 // Set up the accounts
                 let accounts = vec![
@@ -46,6 +34,23 @@ mod macros {
                 $test_fn(&mut env);
             }
         };
+        (@game $config:ident;) => {};
+        (@game $config:ident; owner: $owner:expr $(, $($rest:tt)*)?) => {
+            $config.owner = MockApi::default().addr_make($owner);
+            define_test!(@game $config; $($($rest)*)?);
+        };
+        (@game $config:ident; ticket_denom: $ticket_denom:expr $(, $($rest:tt)*)?) => {
+            $config.ticket_denom = $ticket_denom.to_string();
+            define_test!(@game $config; $($($rest)*)?);
+        };
+        (@game $config:ident; start_at: $start_at:expr $(, $($rest:tt)*)?) => {
+            $config.starts_at = $start_at;
+            define_test!(@game $config; $($($rest)*)?);
+        };
+        (@game $config:ident; $field:ident: $value:expr $(, $($rest:tt)*)?) => {
+            $config.$field = $value;
+            define_test!(@game $config; $($($rest)*)?);
+        };
     }
 
    pub(crate) use define_test;
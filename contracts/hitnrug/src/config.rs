@@ -1,7 +1,8 @@
-use crate::msg::{Fees, InstantiateMsg, Points};
+use crate::msg::{ActionRules, Fees, HelpGate, InstantiateMsg, Points, TeamRules, TeammateHelp};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{ensure, Addr, Api, Decimal, StdResult, Storage, Uint128};
 use cw_storage_plus::Item;
+use wenruji_rs::DecayCurve;
 
 use crate::ContractError;
 
@@ -18,6 +19,98 @@ pub struct Config {
    pub winner_share: Decimal,
    pub fees: Fees,
    pub points: Points,
+   pub decay_curve: DecayCurve,
+   pub help_gate: HelpGate,
+   pub prize_table: PrizeTable,
+   /// Share of the pot paid to the account settling a finished round
+   pub settlement_fee: Decimal,
+   /// Archived rounds kept in storage
+   pub round_retention: u64,
+   /// Largest stake of an account, `ticket_amount` being the smallest. Stakes are exactly
+   /// `ticket_amount` when `None`.
+   pub max_ticket_amount: Option<Uint128>,
+   /// Scales the points of every play by the stake of the player over `ticket_amount`
+   pub stake_weighted_points: bool,
+   /// Share of the stake forfeited by the accounts that neither exited nor won, paid back to
+   /// them by points at settlement
+   pub consolation_share: Decimal,
+   /// Costs and cooldowns of each play
+   pub action_rules: ActionRules,
+   /// Team play, players play alone when `None`
+   pub teams: Option<TeamRules>,
+}
+
+/// Rounds archived when `round_retention` is not set
+pub const DEFAULT_ROUND_RETENTION: u64 = 10;
+/// Longest `round_retention`
+pub const MAX_ROUND_RETENTION: u64 = 1000;
+
+/// Most ranks a `PrizeTable::Top` can pay
+pub const MAX_PRIZE_RANKS: usize = 20;
+
+/// Split of the winner share between the ranked players
+#[cw_serde]
+#[derive(Default)]
+pub enum PrizeTable {
+   /// Everything to the first place
+   #[default]
+   Winner,
+   /// Share of each place from the first one, summing to one. The shares of places without a
+   /// player go to the first place.
+   Top { shares: Vec<Decimal> },
+   /// Split by the points above `threshold` between the players over it, everything to the first
+   /// place when nobody is over it
+   Proportional { threshold: i64 },
+}
+
+impl PrizeTable {
+   pub fn validate(&self) -> Result<(), ContractError> {
+      if let PrizeTable::Top { shares } = self {
+         ensure!(
+            !shares.is_empty()
+               && shares.len() <= MAX_PRIZE_RANKS
+               && shares.iter().all(|share| !share.is_zero())
+               && shares.iter().sum::<Decimal>() == Decimal::one(),
+            ContractError::Invalid("prize_table".to_string())
+         );
+      }
+      Ok(())
+   }
+
+   /// Prize weights of the players of `ranking`, ordered from the first place
+   pub fn split(&self, ranking: &[(Addr, i64)]) -> Vec<(Addr, Decimal)> {
+      let Some((first, _)) = ranking.first() else {
+         return vec![];
+      };
+      match self {
+         PrizeTable::Winner => vec![(first.clone(), Decimal::one())],
+         PrizeTable::Top { shares } => {
+            let mut split: Vec<(Addr, Decimal)> = ranking
+               .iter()
+               .zip(shares)
+               .map(|((addr, _), share)| (addr.clone(), *share))
+               .collect();
+            let paid: Decimal = split.iter().skip(1).map(|(_, share)| share).sum();
+            split[0].1 = Decimal::one() - paid;
+            split
+         }
+         PrizeTable::Proportional { threshold } => {
+            let above: Vec<(Addr, u128)> = ranking
+               .iter()
+               .filter(|(_, points)| points > threshold)
+               .map(|(addr, points)| (addr.clone(), points.abs_diff(*threshold) as u128))
+               .collect();
+            let total: u128 = above.iter().map(|(_, points)| points).sum();
+            if total == 0 {
+               return vec![(first.clone(), Decimal::one())];
+            }
+            above
+               .into_iter()
+               .map(|(addr, points)| (addr, Decimal::from_ratio(points, total)))
+               .collect()
+         }
+      }
+   }
 }
 
 impl Config {
@@ -36,6 +129,16 @@ impl Config {
          delay_play_seconds: msg.delay_play_seconds,
          fees: msg.fees,
          points: msg.points,
+         decay_curve: msg.decay_curve.unwrap_or_default(),
+         help_gate: msg.help_gate.unwrap_or_default(),
+         prize_table: msg.prize_table.unwrap_or_default(),
+         settlement_fee: msg.settlement_fee.unwrap_or_default(),
+         round_retention: msg.round_retention.unwrap_or(DEFAULT_ROUND_RETENTION),
+         max_ticket_amount: msg.max_ticket_amount,
+         stake_weighted_points: msg.stake_weighted_points.unwrap_or_default(),
+         consolation_share: msg.consolation_share.unwrap_or_default(),
+         action_rules: msg.action_rules.unwrap_or_default(),
+         teams: msg.teams,
       })
    }
 
@@ -60,6 +163,40 @@ impl Config {
          self.ticket_amount.gt(&Uint128::zero()),
          ContractError::Invalid("ticket_amount".to_string())
       );
+      ensure!(
+         self.max_ticket_amount.is_none_or(|max| max >= self.ticket_amount),
+         ContractError::Invalid("max_ticket_amount".to_string())
+      );
+      self.decay_curve.validate()?;
+      self.prize_table.validate()?;
+      ensure!(
+         self.settlement_fee < Decimal::one(),
+         ContractError::Invalid("settlement_fee".to_string())
+      );
+      ensure!(
+         self.consolation_share <= Decimal::one(),
+         ContractError::Invalid("consolation_share".to_string())
+      );
+      ensure!(
+         self.round_retention > 0 && self.round_retention <= MAX_ROUND_RETENTION,
+         ContractError::Invalid("round_retention".to_string())
+      );
+
+      let gate = &self.help_gate;
+      ensure!(gate.max_pair_helps != Some(0), ContractError::Invalid("max_pair_helps".to_string()));
+      if let Some(limit) = &gate.help_rate_limit {
+         ensure!(
+            limit.max_helps > 0 && limit.window_seconds > 0,
+            ContractError::Invalid("help_rate_limit".to_string())
+         );
+      }
+
+      if let Some(teams) = &self.teams {
+         ensure!(teams.max_members > 0, ContractError::Invalid("max_members".to_string()));
+         if let TeammateHelp::Discounted { share } = teams.teammate_help {
+            ensure!(share <= Decimal::one(), ContractError::Invalid("teammate_help".to_string()));
+         }
+      }
       Ok(())
    }
 
@@ -92,6 +229,46 @@ impl Config {
          self.points = points;
       }
 
+      if let Some(decay_curve) = msg.decay_curve {
+         self.decay_curve = decay_curve;
+      }
+
+      if let Some(help_gate) = msg.help_gate {
+         self.help_gate = help_gate;
+      }
+
+      if let Some(prize_table) = msg.prize_table {
+         self.prize_table = prize_table;
+      }
+
+      if let Some(settlement_fee) = msg.settlement_fee {
+         self.settlement_fee = settlement_fee;
+      }
+
+      if let Some(round_retention) = msg.round_retention {
+         self.round_retention = round_retention;
+      }
+
+      if let Some(max_ticket_amount) = msg.max_ticket_amount {
+         self.max_ticket_amount = Some(max_ticket_amount).filter(|max| !max.is_zero());
+      }
+
+      if let Some(stake_weighted_points) = msg.stake_weighted_points {
+         self.stake_weighted_points = stake_weighted_points;
+      }
+
+      if let Some(consolation_share) = msg.consolation_share {
+         self.consolation_share = consolation_share;
+      }
+
+      if let Some(action_rules) = msg.action_rules {
+         self.action_rules = action_rules;
+      }
+
+      if let Some(teams) = msg.teams {
+         self.teams = Some(teams).filter(|teams| teams.max_members > 0);
+      }
+
       if let Some(fees) = msg.fees {
          let total_fee = fees.fee_platform.bp + fees.fee_ref.bp;
          ensure!(total_fee.lt(&Decimal::one()), ContractError::Invalid("fees_amounts".to_string()));
@@ -106,6 +283,25 @@ impl Config {
    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
       CONFIG.save(storage, self)
    }
+
+   /// Checks that `stake` is within the ticket range
+   pub fn check_stake(&self, stake: Uint128) -> Result<(), ContractError> {
+      ensure!(stake >= self.ticket_amount, ContractError::InsufficientFunds {});
+      ensure!(
+         stake <= self.max_ticket_amount.unwrap_or(self.ticket_amount),
+         ContractError::Invalid("max_ticket_amount".to_string())
+      );
+      Ok(())
+   }
+
+   /// `points` scaled by `stake` over `ticket_amount` when `stake_weighted_points` is on
+   pub fn weigh_points(&self, points: i64, stake: Uint128) -> i64 {
+      if !self.stake_weighted_points {
+         return points;
+      }
+      let weighted = points as i128 * stake.u128() as i128 / self.ticket_amount.u128() as i128;
+      weighted.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+   }
 }
 
 #[cw_serde]
@@ -118,15 +314,31 @@ pub struct ConfigUpdate {
    pub game_delay_sec: Option<u64>,
    pub fees: Option<Fees>,
    pub points: Option<Points>,
+   pub decay_curve: Option<DecayCurve>,
+   pub help_gate: Option<HelpGate>,
+   pub prize_table: Option<PrizeTable>,
+   pub settlement_fee: Option<Decimal>,
+   pub round_retention: Option<u64>,
+   /// Zero clears the cap back to the ticket amount
+   pub max_ticket_amount: Option<Uint128>,
+   pub stake_weighted_points: Option<bool>,
+   pub consolation_share: Option<Decimal>,
+   pub action_rules: Option<ActionRules>,
+   /// A `max_members` of zero turns team play off
+   pub teams: Option<TeamRules>,
 }
 
 #[cfg(test)]
 mod tests {
-   use crate::config::{Config, ConfigUpdate};
-   use crate::msg::{Fee, Fees, InstantiateMsg, Point, Points};
+   use crate::config::{Config, ConfigUpdate, PrizeTable, MAX_PRIZE_RANKS, MAX_ROUND_RETENTION};
+   use crate::msg::{
+      ActionRules, Fee, Fees, HelpGate, HelpRateLimit, InstantiateMsg, Point, Points, TeamRules,
+      TeammateHelp,
+   };
    use crate::ContractError;
    use cosmwasm_std::testing::{mock_dependencies, MockStorage};
    use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
+   use wenruji_rs::DecayCurve;
 
    // Utility function to create a config from InstantiateMsg
    fn create_config(msg: InstantiateMsg) -> Result<Config, ContractError> {
@@ -147,8 +359,25 @@ mod tests {
             fee_platform: Fee { bp: Decimal::percent(1), address: Addr::unchecked("platform_fee") },
             fee_ref: Fee { bp: Decimal::percent(2), address: Addr::unchecked("ref_fee") },
          },
-         points: Points { keep: 6i64, hit: -4i64, help: Point { myself: 6i64, other: 4i64 } },
+         points: Points {
+            keep: 6i64,
+            hit: -4i64,
+            help: Point { myself: 6i64, other: 4i64 },
+            shield_seconds: 0,
+            steal: 0,
+            rug_bonus: Decimal::zero(),
+         },
          starts_at: Timestamp::from_seconds(10000),
+         decay_curve: None,
+         help_gate: None,
+         prize_table: None,
+         settlement_fee: None,
+         round_retention: None,
+         max_ticket_amount: None,
+         stake_weighted_points: None,
+         consolation_share: None,
+         action_rules: None,
+         teams: None,
       };
 
       // Create the config using the given InstantiateMsg
@@ -186,8 +415,25 @@ mod tests {
             },
             fee_ref: Fee { bp: Decimal::percent(15), address: Addr::unchecked("ref_fee") },
          },
-         points: Points { keep: 6i64, hit: -4i64, help: Point { myself: 6i64, other: 4i64 } },
+         points: Points {
+            keep: 6i64,
+            hit: -4i64,
+            help: Point { myself: 6i64, other: 4i64 },
+            shield_seconds: 0,
+            steal: 0,
+            rug_bonus: Decimal::zero(),
+         },
          starts_at: Timestamp::from_seconds(10000),
+         decay_curve: None,
+         help_gate: None,
+         prize_table: None,
+         settlement_fee: None,
+         round_retention: None,
+         max_ticket_amount: None,
+         stake_weighted_points: None,
+         consolation_share: None,
+         action_rules: None,
+         teams: None,
       };
 
       // Ensure the total fee is invalid and throws an error
@@ -211,8 +457,25 @@ mod tests {
             fee_platform: Fee { bp: Decimal::percent(1), address: Addr::unchecked("platform_fee") },
             fee_ref: Fee { bp: Decimal::percent(2), address: Addr::unchecked("ref_fee") },
          },
-         points: Points { keep: 6i64, hit: -4i64, help: Point { myself: 6i64, other: 4i64 } },
+         points: Points {
+            keep: 6i64,
+            hit: -4i64,
+            help: Point { myself: 6i64, other: 4i64 },
+            shield_seconds: 0,
+            steal: 0,
+            rug_bonus: Decimal::zero(),
+         },
          starts_at: Timestamp::from_seconds(10000),
+         decay_curve: None,
+         help_gate: None,
+         prize_table: None,
+         settlement_fee: None,
+         round_retention: None,
+         max_ticket_amount: None,
+         stake_weighted_points: None,
+         consolation_share: None,
+         action_rules: None,
+         teams: None,
       };
 
       // Try creating config and expect validation failure for invalid address
@@ -234,8 +497,25 @@ mod tests {
             fee_platform: Fee { bp: Decimal::percent(1), address: Addr::unchecked("platform_fee") },
             fee_ref: Fee { bp: Decimal::percent(2), address: Addr::unchecked("ref_fee") },
          },
-         points: Points { keep: 6i64, hit: -4i64, help: Point { myself: 6i64, other: 4i64 } },
+         points: Points {
+            keep: 6i64,
+            hit: -4i64,
+            help: Point { myself: 6i64, other: 4i64 },
+            shield_seconds: 0,
+            steal: 0,
+            rug_bonus: Decimal::zero(),
+         },
          winner_share: Decimal::percent(97),
+         decay_curve: DecayCurve::Linear,
+         help_gate: HelpGate::default(),
+         prize_table: PrizeTable::Winner,
+         settlement_fee: Decimal::zero(),
+         round_retention: 10,
+         max_ticket_amount: None,
+         stake_weighted_points: false,
+         consolation_share: Decimal::zero(),
+         action_rules: ActionRules::default(),
+         teams: None,
       };
 
       let update_msg = ConfigUpdate {
@@ -249,6 +529,9 @@ mod tests {
             keep: 10i64,
             hit: -5i64,
             help: Point { myself: 10i64, other: 5i64 },
+            shield_seconds: 0,
+            steal: 0,
+            rug_bonus: Decimal::zero(),
          }),
          fees: Some(Fees {
             fee_platform: Fee {
@@ -257,9 +540,22 @@ mod tests {
             },
             fee_ref: Fee { bp: Decimal::percent(3), address: Addr::unchecked("new_ref_fee") },
          }),
+         decay_curve: Some(DecayCurve::Sigmoid { steepness: 2 }),
+         help_gate: Some(HelpGate { max_pair_helps: Some(2), ..HelpGate::default() }),
+         prize_table: Some(PrizeTable::Proportional { threshold: 10 }),
+         settlement_fee: Some(Decimal::percent(1)),
+         round_retention: Some(20),
+         max_ticket_amount: Some(Uint128::new(500)),
+         stake_weighted_points: Some(true),
+         consolation_share: Some(Decimal::percent(50)),
+         action_rules: Some(ActionRules {
+            hit_cost_step: Uint128::new(5),
+            ..ActionRules::default()
+         }),
+         teams: Some(TeamRules { max_members: 3, teammate_help: TeammateHelp::Allowed }),
       };
 
-      config.apply_update(update_msg).expect("Failed to apply update");
+      config.apply_update(update_msg.clone()).expect("Failed to apply update");
 
       // Validate updated values
       assert_eq!(config.owner, Addr::unchecked("new_owner"));
@@ -273,6 +569,177 @@ mod tests {
       assert_eq!(config.points.hit, -5i64);
       assert_eq!(config.points.help.myself, 10i64);
       assert_eq!(config.points.help.other, 5i64);
+      assert_eq!(config.decay_curve, DecayCurve::Sigmoid { steepness: 2 });
+      assert_eq!(config.help_gate.max_pair_helps, Some(2));
+      assert_eq!(config.prize_table, PrizeTable::Proportional { threshold: 10 });
+      assert_eq!(config.settlement_fee, Decimal::percent(1));
+      assert_eq!(config.round_retention, 20);
+      assert_eq!(config.max_ticket_amount, Some(Uint128::new(500)));
+      assert!(config.stake_weighted_points);
+      assert_eq!(config.consolation_share, Decimal::percent(50));
+      assert_eq!(config.action_rules.hit_cost_step, Uint128::new(5));
+      assert_eq!(config.teams.as_ref().map(|teams| teams.max_members), Some(3));
+
+      // zero clears the cap and turns team play off
+      config
+         .apply_update(ConfigUpdate {
+            max_ticket_amount: Some(Uint128::zero()),
+            teams: Some(TeamRules { max_members: 0, teammate_help: TeammateHelp::Allowed }),
+            ..update_msg
+         })
+         .expect("Failed to clear the cap and the teams");
+      assert_eq!(config.max_ticket_amount, None);
+      assert_eq!(config.teams, None);
+   }
+
+   #[test]
+   fn test_validate_help_gate() {
+      let deps = mock_dependencies();
+      let mut config = Config::new(InstantiateMsg {
+         owner: deps.api.addr_make("owner"),
+         ticket_denom: "token".to_string(),
+         ticket_amount: Uint128::new(100),
+         duration_seconds: 3600,
+         game_delay_sec: 10,
+         delay_play_seconds: 5,
+         fees: Fees {
+            fee_platform: Fee { bp: Decimal::percent(1), address: deps.api.addr_make("platform") },
+            fee_ref: Fee { bp: Decimal::percent(2), address: deps.api.addr_make("ref") },
+         },
+         points: Points {
+            keep: 6i64,
+            hit: -4i64,
+            help: Point { myself: 6i64, other: 4i64 },
+            shield_seconds: 0,
+            steal: 0,
+            rug_bonus: Decimal::zero(),
+         },
+         starts_at: Timestamp::from_seconds(10000),
+         decay_curve: None,
+         help_gate: Some(HelpGate {
+            max_pair_helps: Some(1),
+            help_rate_limit: Some(HelpRateLimit { max_helps: 2, window_seconds: 60 }),
+            min_account_age: Some(3600),
+            min_prior_rounds: Some(10),
+         }),
+         prize_table: None,
+         settlement_fee: None,
+         round_retention: None,
+         max_ticket_amount: None,
+         stake_weighted_points: None,
+         consolation_share: None,
+         action_rules: None,
+         teams: None,
+      })
+      .unwrap();
+      config.validate(&deps.api).unwrap();
+
+      let valid = config.help_gate.clone();
+      for gate in [
+         HelpGate { max_pair_helps: Some(0), ..valid.clone() },
+         HelpGate {
+            help_rate_limit: Some(HelpRateLimit { max_helps: 0, window_seconds: 60 }),
+            ..valid.clone()
+         },
+         HelpGate {
+            help_rate_limit: Some(HelpRateLimit { max_helps: 2, window_seconds: 0 }),
+            ..valid.clone()
+         },
+      ] {
+         config.help_gate = gate;
+         config.validate(&deps.api).unwrap_err();
+      }
+
+      config.help_gate = valid;
+      config.settlement_fee = Decimal::one();
+      config.validate(&deps.api).unwrap_err();
+      config.settlement_fee = Decimal::zero();
+      config.consolation_share = Decimal::percent(101);
+      config.validate(&deps.api).unwrap_err();
+      config.consolation_share = Decimal::one();
+      config.validate(&deps.api).unwrap();
+      config.teams = Some(TeamRules { max_members: 0, teammate_help: TeammateHelp::Forbidden });
+      config.validate(&deps.api).unwrap_err();
+      config.teams = Some(TeamRules {
+         max_members: 3,
+         teammate_help: TeammateHelp::Discounted { share: Decimal::percent(101) },
+      });
+      config.validate(&deps.api).unwrap_err();
+      config.teams = None;
+
+      config.round_retention = 0;
+      config.validate(&deps.api).unwrap_err();
+      config.round_retention = MAX_ROUND_RETENTION + 1;
+      config.validate(&deps.api).unwrap_err();
+      // prior rounds are not bound to the retained ones
+      config.round_retention = 1;
+      config.help_gate.min_prior_rounds = Some(11);
+      config.validate(&deps.api).unwrap();
+
+      config.max_ticket_amount = Some(config.ticket_amount - Uint128::one());
+      config.validate(&deps.api).unwrap_err();
+      config.max_ticket_amount = Some(config.ticket_amount);
+      config.validate(&deps.api).unwrap();
+   }
+
+   #[test]
+   fn test_validate_prize_table() {
+      PrizeTable::Winner.validate().unwrap();
+      PrizeTable::Proportional { threshold: 0 }.validate().unwrap();
+      PrizeTable::Top { shares: vec![Decimal::one()] }.validate().unwrap();
+      PrizeTable::Top {
+         shares: vec![Decimal::percent(60), Decimal::percent(25), Decimal::percent(15)],
+      }
+      .validate()
+      .unwrap();
+
+      PrizeTable::Top { shares: vec![] }.validate().unwrap_err();
+      PrizeTable::Top { shares: vec![Decimal::percent(60), Decimal::percent(25)] }
+         .validate()
+         .unwrap_err();
+      PrizeTable::Top { shares: vec![Decimal::one(), Decimal::zero()] }.validate().unwrap_err();
+      PrizeTable::Top { shares: vec![Decimal::percent(5); MAX_PRIZE_RANKS + 1] }
+         .validate()
+         .unwrap_err();
+   }
+
+   #[test]
+   fn test_prize_split() {
+      let ranking: Vec<(Addr, i64)> =
+         [("a", 30), ("b", 20), ("c", 20), ("d", 5)].map(|(a, p)| (Addr::unchecked(a), p)).into();
+      let addr = Addr::unchecked;
+
+      assert_eq!(PrizeTable::Winner.split(&ranking), vec![(addr("a"), Decimal::one())]);
+      assert_eq!(PrizeTable::Winner.split(&[]), vec![]);
+
+      let top = PrizeTable::Top {
+         shares: vec![Decimal::percent(60), Decimal::percent(25), Decimal::percent(15)],
+      };
+      assert_eq!(
+         top.split(&ranking),
+         vec![
+            (addr("a"), Decimal::percent(60)),
+            (addr("b"), Decimal::percent(25)),
+            (addr("c"), Decimal::percent(15))
+         ]
+      );
+      // missing places go to the first one
+      assert_eq!(
+         top.split(&ranking[..2]),
+         vec![(addr("a"), Decimal::percent(75)), (addr("b"), Decimal::percent(25))]
+      );
+
+      let proportional = PrizeTable::Proportional { threshold: 10 };
+      assert_eq!(
+         proportional.split(&ranking),
+         vec![
+            (addr("a"), Decimal::percent(50)),
+            (addr("b"), Decimal::percent(25)),
+            (addr("c"), Decimal::percent(25))
+         ]
+      );
+      let proportional = PrizeTable::Proportional { threshold: 30 };
+      assert_eq!(proportional.split(&ranking), vec![(addr("a"), Decimal::one())]);
    }
 
    // Test 5: Save and load config from storage
@@ -289,8 +756,25 @@ mod tests {
             fee_platform: Fee { bp: Decimal::percent(1), address: Addr::unchecked("platform_fee") },
             fee_ref: Fee { bp: Decimal::percent(2), address: Addr::unchecked("ref_fee") },
          },
-         points: Points { keep: 6i64, hit: -4i64, help: Point { myself: 6i64, other: 4i64 } },
+         points: Points {
+            keep: 6i64,
+            hit: -4i64,
+            help: Point { myself: 6i64, other: 4i64 },
+            shield_seconds: 0,
+            steal: 0,
+            rug_bonus: Decimal::zero(),
+         },
          starts_at: Timestamp::from_seconds(10000),
+         decay_curve: None,
+         help_gate: None,
+         prize_table: None,
+         settlement_fee: None,
+         round_retention: None,
+         max_ticket_amount: None,
+         stake_weighted_points: None,
+         consolation_share: None,
+         action_rules: None,
+         teams: None,
       };
 
       let config = create_config(msg).unwrap();
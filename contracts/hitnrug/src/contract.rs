@@ -1,16 +1,26 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-   ensure, ensure_eq, to_json_binary, to_json_string, BankMsg, Binary, Coin, Deps, DepsMut, Env,
-   Event, MessageInfo, Response,
+   ensure, to_json_binary, to_json_string, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Empty, Env,
+   Event, MessageInfo, Response, StdResult,
+};
+use cw2::{ensure_from_older_version, set_contract_version};
+use cw_storage_plus::{Bound, Map};
+use cw_utils::{may_pay, must_pay, nonpayable};
+use wenruji_rs::{
+   keeper::{KeeperMsg, NextAction},
+   page_limit,
 };
-use cw2::set_contract_version;
-use cw_utils::{must_pay, nonpayable};
 
 use crate::config::Config;
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{execute_endgame, execute_ref, execute_restart, GAME_SM, IDX, SNAPSHOT};
+use crate::game::RoundSummary;
+use crate::migrations::migrate_state;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{
+   execute_endgame, execute_play, execute_ref, execute_restart, execute_rollover, execute_team,
+   GAME_SM, IDX, PLAYER_STATS, ROUNDS, ROUNDS_PLAYED, ROUNDS_WON,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "hitnrug";
@@ -31,6 +41,7 @@ pub fn instantiate(
       deps.storage,
       msg.starts_at,
       msg.starts_at.plus_seconds(msg.duration_seconds),
+      config.decay_curve.clone(),
    )?;
    IDX.save(deps.storage, &1u64)?;
    Ok(Response::default())
@@ -48,7 +59,10 @@ pub fn execute(
    match msg {
       ExecuteMsg::Join { ref_code } => {
          let amount = must_pay(&info, &config.ticket_denom)?;
-         ensure_eq!(amount, config.ticket_amount, ContractError::InsufficientFunds {});
+         config.check_stake(amount)?;
+
+         let mut response =
+            execute_rollover(deps.storage, time, &config, &info.sender)?.unwrap_or_default();
 
          ensure!(
             !GAME_SM.is_started(deps.storage, time)?,
@@ -59,7 +73,6 @@ pub fn execute(
             !GAME_SM.has_joined(deps.storage, &info.sender)?,
             ContractError::Invalid("already_joined".to_string())
          );
-         let mut response = Response::new();
 
          let (ambassador, ref_msg) =
             execute_ref(deps.api, deps.storage, deps.querier, &config, &info.sender, ref_code)?;
@@ -68,6 +81,7 @@ pub fn execute(
          }
 
          GAME_SM.join(deps.storage, time, &info.sender, amount)?;
+         PLAYER_STATS.record_join(deps.storage, &info.sender, amount)?;
 
          Ok(response.add_event(
             Event::new("hitnrug/join")
@@ -77,12 +91,16 @@ pub fn execute(
       }
       ExecuteMsg::Exit {} => {
          nonpayable(&info)?;
+         if let Some(response) = execute_rollover(deps.storage, time, &config, &info.sender)? {
+            return Ok(response);
+         }
          ensure!(
             GAME_SM.is_started(deps.storage, time)?,
             ContractError::Invalid("game already started".to_string())
          );
 
          let (amount, decay_snap) = GAME_SM.exit(deps.storage, time, &info.sender)?;
+         PLAYER_STATS.record_exit(deps.storage, &info.sender, amount, decay_snap)?;
 
          let msg = BankMsg::Send {
             to_address: info.sender.to_string(),
@@ -95,8 +113,70 @@ pub fn execute(
                .add_attribute("decay_snap", decay_snap.to_string()),
          ))
       }
-      ExecuteMsg::Play(play_msg) => {
+      ExecuteMsg::ExitPartial { amount } => {
          nonpayable(&info)?;
+         if let Some(response) = execute_rollover(deps.storage, time, &config, &info.sender)? {
+            return Ok(response);
+         }
+         ensure!(
+            GAME_SM.is_started(deps.storage, time)?,
+            ContractError::Invalid("game already started".to_string())
+         );
+
+         let (paid, decay_snap) =
+            GAME_SM.exit_partial(deps.storage, time, &info.sender, amount, &config)?;
+         PLAYER_STATS.record_partial_exit(deps.storage, &info.sender, paid)?;
+
+         let msg = BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(paid, config.ticket_denom.clone())],
+         };
+
+         Ok(Response::new().add_message(msg).add_event(
+            Event::new("hitnrug/exit_partial")
+               .add_attribute("account", info.sender)
+               .add_attribute("amount", amount)
+               .add_attribute("paid", paid)
+               .add_attribute("decay_snap", decay_snap.to_string()),
+         ))
+      }
+      ExecuteMsg::TopUp {} => {
+         let amount = must_pay(&info, &config.ticket_denom)?;
+         ensure!(
+            GAME_SM.has_joined(deps.storage, &info.sender)?,
+            ContractError::Invalid("not_joined".to_string())
+         );
+         let stake = GAME_SM.top_up(deps.storage, time, &info.sender, amount, &config)?;
+         PLAYER_STATS.record_top_up(deps.storage, &info.sender, amount)?;
+
+         Ok(Response::new().add_event(
+            Event::new("hitnrug/top_up")
+               .add_attribute("account", info.sender)
+               .add_attribute("amount", amount)
+               .add_attribute("stake", stake),
+         ))
+      }
+      ExecuteMsg::CreateTeam { name } => {
+         nonpayable(&info)?;
+         execute_team(deps.storage, time, &config, &info.sender, &name, true)?;
+         Ok(Response::new().add_event(
+            Event::new("hitnrug/create_team")
+               .add_attribute("account", info.sender)
+               .add_attribute("team", name),
+         ))
+      }
+      ExecuteMsg::JoinTeam { name } => {
+         nonpayable(&info)?;
+         let team = execute_team(deps.storage, time, &config, &info.sender, &name, false)?;
+         Ok(Response::new().add_event(
+            Event::new("hitnrug/join_team")
+               .add_attribute("account", info.sender)
+               .add_attribute("team", name)
+               .add_attribute("members", team.members.len().to_string()),
+         ))
+      }
+      ExecuteMsg::Play(play_msg) => {
+         let paid = may_pay(&info, &config.ticket_denom)?;
          ensure!(
             GAME_SM.is_started(deps.storage, time)?,
             ContractError::Invalid("game not started".to_string())
@@ -113,17 +193,43 @@ pub fn execute(
             !GAME_SM.has_exited(deps.storage, &info.sender)?,
             ContractError::Invalid("exited_cannot_play".to_string())
          );
-         GAME_SM.play(deps.storage, play_msg.clone(), &info.sender, &config, time)?;
+         let rug = execute_play(deps.storage, time, &config, &info.sender, play_msg.clone(), paid)?;
          let action = to_json_string(&play_msg)?;
-         Ok(Response::new().add_event(Event::new("hitnrug/play").add_attribute("action", action)))
+         let response =
+            Response::new().add_event(Event::new("hitnrug/play").add_attribute("action", action));
+         let Some((amount, decay_snap)) = rug else {
+            return Ok(response);
+         };
+         Ok(response
+            .add_message(BankMsg::Send {
+               to_address: info.sender.to_string(),
+               amount: vec![Coin::new(amount, config.ticket_denom.clone())],
+            })
+            .add_event(
+               Event::new("hitnrug/exit")
+                  .add_attribute("account", info.sender)
+                  .add_attribute("decay_snap", decay_snap.to_string()),
+            ))
       }
       ExecuteMsg::EndGame {} => {
          nonpayable(&info)?;
-         execute_endgame(deps.storage, time, &config)
+         execute_endgame(deps.storage, time, &config, &info.sender)
+      }
+      ExecuteMsg::Settle {} => {
+         nonpayable(&info)?;
+         let (response, settled) =
+            match execute_rollover(deps.storage, time, &config, &info.sender)? {
+               Some(response) => (response, true),
+               None => (Response::new(), false),
+            };
+         Ok(response.add_event(
+            Event::new("hitnrug/settle")
+               .add_attribute("settler", info.sender)
+               .add_attribute("settled", settled.to_string()),
+         ))
       }
       ExecuteMsg::Restart {} => {
-         let idx =
-            execute_restart(deps.storage, time, config.duration_seconds, config.game_delay_sec)?;
+         let idx = execute_restart(deps.storage, time, &config)?;
 
          Ok(Response::new().add_event(
             Event::new("hitnrug/restart")
@@ -149,14 +255,82 @@ pub fn execute(
    }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+   let stored = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+   migrate_state(deps.storage, &stored, env.block.time)?;
+
+   Ok(Response::new().add_event(
+      Event::new("hitnrug/migrate")
+         .add_attribute("from_version", stored.to_string())
+         .add_attribute("to_version", CONTRACT_VERSION),
+   ))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
    match msg {
-      QueryMsg::GameStatus { idx } => match idx {
-         Some(idx) => Ok(SNAPSHOT.load(deps.storage, idx)?),
-         None => GAME_SM.get_snap(deps.storage),
-      },
+      QueryMsg::GameStatus { start_after, limit } => Ok(to_json_binary(&GAME_SM.get_snap(
+         deps.storage,
+         start_after.as_ref(),
+         Some(page_limit(limit)),
+      )?)?),
+      QueryMsg::Round { idx } => Ok(to_json_binary(&ROUNDS.may_load(deps.storage, idx)?)?),
+      QueryMsg::RoundHistory { start_after, limit } => {
+         let rounds: Vec<(u64, RoundSummary)> = ROUNDS
+            .range(
+               deps.storage,
+               None,
+               start_after.map(Bound::exclusive),
+               cosmwasm_std::Order::Descending,
+            )
+            .take(page_limit(limit))
+            .collect::<StdResult<_>>()?;
+         Ok(to_json_binary(&rounds)?)
+      }
+      QueryMsg::RoundsWon { addr, start_after, limit } => {
+         Ok(to_json_binary(&round_keys(deps, &ROUNDS_WON, &addr, start_after, limit)?)?)
+      }
+      QueryMsg::RoundsPlayed { addr, start_after, limit } => {
+         Ok(to_json_binary(&round_keys(deps, &ROUNDS_PLAYED, &addr, start_after, limit)?)?)
+      }
       QueryMsg::Config {} => Ok(to_json_binary(&Config::load(deps.storage)?)?),
       QueryMsg::GameIndex {} => Ok(to_json_binary(&IDX.load(deps.storage)?)?),
+      QueryMsg::Player { addr } => Ok(to_json_binary(&GAME_SM.get_player(deps.storage, &addr)?)?),
+      QueryMsg::PlayerStats { addr } => {
+         Ok(to_json_binary(&PLAYER_STATS.load(deps.storage, &addr)?)?)
+      }
+      QueryMsg::PlayerHistory { addr, start_after, limit } => Ok(to_json_binary(
+         &GAME_SM.get_history(deps.storage, &addr, start_after, page_limit(limit))?,
+      )?),
+      QueryMsg::Team { name } => Ok(to_json_binary(&GAME_SM.teams.may_load(deps.storage, &name)?)?),
+      QueryMsg::Teams { start_after, limit } => {
+         Ok(to_json_binary(&GAME_SM.get_teams(deps.storage, start_after, page_limit(limit))?)?)
+      }
+      QueryMsg::Consolation {} => {
+         Ok(to_json_binary(&GAME_SM.consolation.may_load(deps.storage)?.unwrap_or_default())?)
+      }
+      QueryMsg::Leaderboard { start_after, limit } => Ok(to_json_binary(
+         &GAME_SM.get_leaderboard(deps.storage, start_after, page_limit(limit))?,
+      )?),
+      QueryMsg::NextAction {} => Ok(to_json_binary(&NextAction {
+         at: GAME_SM.ends_at(deps.storage)?.plus_seconds(1),
+         msg: KeeperMsg::Settle {},
+      })?),
    }
 }
+
+/// Rounds of `addr` in an index of archived rounds, from the latest
+fn round_keys(
+   deps: Deps,
+   index: &Map<(&Addr, u64), Empty>,
+   addr: &Addr,
+   start_after: Option<u64>,
+   limit: Option<u32>,
+) -> StdResult<Vec<u64>> {
+   index
+      .prefix(addr)
+      .keys(deps.storage, None, start_after.map(Bound::exclusive), cosmwasm_std::Order::Descending)
+      .take(page_limit(limit))
+      .collect()
+}
@@ -1,23 +1,35 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{
-   ensure, to_json_binary, Addr, Binary, Decimal, StdResult, Storage, Timestamp, Uint128,
+use cosmwasm_std::{ensure, Addr, Decimal, Empty, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Bound, Item, Map};
+use wenruji_rs::{DecayCurve, DecayGame, DecayGameAccount};
+
+use crate::{
+   config::Config,
+   msg::{HelpGate, PlayMsg, PlayerResponse, TeamRules},
+   ContractError,
 };
-use cw_storage_plus::{Item, Map};
-use wenruji_rs::{DecayGame, DecayGameAccount};
-
-use crate::{config::Config, msg::PlayMsg, ContractError};
 #[cw_serde]
 pub struct GameBase {
    pub decay_game: DecayGame,
    pub current_winner: Option<(Addr, i64)>,
+   /// Team with the most points in team mode
+   #[serde(default)]
+   pub winning_team: Option<(String, i64)>,
 }
 
 impl GameBase {
-   pub fn new(decay_starts_at: Timestamp, decay_ends_at: Timestamp) -> Self {
-      Self { decay_game: DecayGame::new(decay_starts_at, decay_ends_at), current_winner: None }
+   pub fn new(decay_starts_at: Timestamp, decay_ends_at: Timestamp, curve: DecayCurve) -> Self {
+      Self {
+         decay_game: DecayGame::new(decay_starts_at, decay_ends_at).with_curve(curve),
+         current_winner: None,
+         winning_team: None,
+      }
    }
 }
 
+/// Longest team name
+pub const MAX_TEAM_NAME_LEN: usize = 32;
+
 #[cw_serde]
 pub struct GameSmSnapshot {
    pub decay_game: DecayGame,
@@ -25,28 +37,118 @@ pub struct GameSmSnapshot {
    pub current_winner: Option<(Addr, i64)>,
    pub players: Vec<PlayerStatus>,
    pub referrals: Vec<(Addr, Decimal)>,
+   /// Final standings, empty until the game is ended
+   #[serde(default)]
+   pub ranking: Vec<Rank>,
+}
+
+/// Archived outcome of a round
+#[cw_serde]
+pub struct RoundSummary {
+   pub starts_at: Timestamp,
+   pub ends_at: Timestamp,
+   pub winner: Option<(Addr, i64)>,
+   /// Final standings with the prize of each place, empty when the round paid no rewards
+   pub ranking: Vec<Rank>,
+   pub total: Uint128,
+   pub exited: Uint128,
+   pub players: Vec<PlayerStatus>,
+   pub referrals: Vec<(Addr, Decimal)>,
+   #[serde(default)]
+   pub consolation: Consolation,
+   #[serde(default)]
+   pub winning_team: Option<(String, i64)>,
+}
+
+#[cw_serde]
+pub struct Rank {
+   pub address: Addr,
+   pub points: i64,
+   pub prize: Uint128,
+}
+
+/// Stake forfeited at settlement and its share paid back by points
+#[cw_serde]
+#[derive(Default)]
+pub struct Consolation {
+   /// Stakes of the accounts that neither exited nor won
+   pub forfeited: Uint128,
+   /// Sum of the payouts
+   pub pool: Uint128,
+   pub payouts: Vec<(Addr, Uint128)>,
 }
 
 #[cw_serde]
 
 pub struct PlayerStatus {
-   address: Addr,
-   points: i64,
-   history: Vec<PlayMsg>,
-   last_play: Timestamp,
+   pub address: Addr,
+   pub points: i64,
+   /// Plays this round, the sequence of the next entry of `GameSM::history`
+   pub plays: u64,
+   pub last_play: Timestamp,
 }
 
 impl PlayerStatus {
    pub fn new(account: Addr) -> Self {
-      Self { address: account, points: 0i64, history: vec![], last_play: Timestamp::default() }
+      Self { address: account, points: 0i64, plays: 0, last_play: Timestamp::default() }
    }
 }
 
+#[cw_serde]
+pub struct Team {
+   pub name: String,
+   pub members: Vec<Addr>,
+   /// Sum of the points of the members
+   pub points: i64,
+}
+
+/// Play of an account, kept until the round restarts
+#[cw_serde]
+pub struct PlayRecord {
+   pub msg: PlayMsg,
+   pub at: Timestamp,
+   pub target: Option<Addr>,
+   /// Change of the points of the player
+   pub points: i64,
+   /// Change of the points of the target
+   pub target_points: i64,
+}
+
 pub struct GameSM<'a> {
    pub game_base: Item<GameBase>,
    pub accounts: Map<&'a Addr, DecayGameAccount>,
    pub ref_weight: Map<&'a Addr, Decimal>,
    pub players: Map<&'a Addr, PlayerStatus>,
+   /// Helps given between two accounts this round, keyed by the sorted pair
+   pub help_pairs: Map<(&'a Addr, &'a Addr), u32>,
+   /// Start of the current window and helps received in it
+   pub help_received: Map<&'a Addr, (Timestamp, u32)>,
+   /// Last play of each kind by an account this round
+   pub last_actions: Map<(&'a Addr, &'a str), Timestamp>,
+   /// Hits given by an account to a target this round
+   pub pair_hits: Map<(&'a Addr, &'a Addr), u32>,
+   /// End of the shield of an account
+   pub shields: Map<&'a Addr, Timestamp>,
+   /// Plays of this round by account and sequence
+   pub history: Map<(&'a Addr, u64), PlayRecord>,
+   pub teams: Map<&'a str, Team>,
+   /// Team of each member
+   pub team_of: Map<&'a Addr, String>,
+   /// Teams ordered by points
+   pub team_board: Map<(i64, &'a str), Empty>,
+   /// First join of every account, kept across rounds
+   pub first_joins: Map<&'a Addr, Timestamp>,
+   pub ranking: Item<Vec<Rank>>,
+   pub consolation: Item<Consolation>,
+   /// Players by negated points, ascending keys walk down from the most points with ties by
+   /// address
+   pub leaderboard: Map<(i64, &'a Addr), Empty>,
+}
+
+impl<'a> Default for GameSM<'a> {
+   fn default() -> Self {
+      Self::new()
+   }
 }
 
 impl<'a> GameSM<'a> {
@@ -56,6 +158,19 @@ impl<'a> GameSM<'a> {
          accounts: Map::new("gm/a"),
          ref_weight: Map::new("gm/rf"),
          players: Map::new("gm/p"),
+         help_pairs: Map::new("gm/hp"),
+         help_received: Map::new("gm/hr"),
+         last_actions: Map::new("gm/la"),
+         pair_hits: Map::new("gm/ph"),
+         shields: Map::new("gm/sh"),
+         history: Map::new("gm/h"),
+         teams: Map::new("gm/t"),
+         team_of: Map::new("gm/tm"),
+         team_board: Map::new("gm/tb"),
+         first_joins: Map::new("gm/fj"),
+         ranking: Item::new("gm/rk"),
+         consolation: Item::new("gm/cs"),
+         leaderboard: Map::new("gm/lb"),
       }
    }
 
@@ -64,8 +179,9 @@ impl<'a> GameSM<'a> {
       storage: &mut dyn Storage,
       decay_starts_at: Timestamp,
       decay_ends_at: Timestamp,
+      curve: DecayCurve,
    ) -> StdResult<()> {
-      let game_base = GameBase::new(decay_starts_at, decay_ends_at);
+      let game_base = GameBase::new(decay_starts_at, decay_ends_at, curve);
       self.game_base.save(storage, &game_base)?;
       Ok(())
    }
@@ -86,13 +202,35 @@ impl<'a> GameSM<'a> {
    ) -> Result<(), ContractError> {
       let mut game_base = self.game_base.load(storage)?;
       let account_data = game_base.decay_game.join(amount, &now)?;
-      self.check_winner(&mut game_base, account, 0i64);
-      self.game_base.save(storage, &game_base)?;
       self.accounts.save(storage, account, &account_data)?;
-      self.players.save(storage, &account, &PlayerStatus::new(account.clone()))?;
+      self.players.save(storage, account, &PlayerStatus::new(account.clone()))?;
+      self.leaderboard.save(storage, (0, account), &Empty {})?;
+      self.update_winner(storage, &mut game_base)?;
+      self.game_base.save(storage, &game_base)?;
+      if !self.first_joins.has(storage, account) {
+         self.first_joins.save(storage, account, &now)?;
+      }
       Ok(())
    }
 
+   /// Adds `amount` to the stake of `account` within the ticket range, returns the new stake
+   pub fn top_up(
+      &self,
+      storage: &mut dyn Storage,
+      now: Timestamp,
+      account: &Addr,
+      amount: Uint128,
+      config: &Config,
+   ) -> Result<Uint128, ContractError> {
+      let mut game_base = self.game_base.load(storage)?;
+      let mut account_data = self.accounts.load(storage, account)?;
+      config.check_stake(account_data.amount + amount)?;
+      game_base.decay_game.top_up(amount, &now, &mut account_data)?;
+      self.game_base.save(storage, &game_base)?;
+      self.accounts.save(storage, account, &account_data)?;
+      Ok(account_data.amount)
+   }
+
    pub fn exit(
       &self,
       storage: &mut dyn Storage,
@@ -106,17 +244,45 @@ impl<'a> GameSM<'a> {
       );
 
       let mut account_data = self.accounts.load(storage, account)?;
-      if !account_data.decay_snapshot.is_zero() {
+      if !account_data.is_active() {
          return Err(ContractError::Invalid("already_exited".to_string()));
       }
 
-      game_base.decay_game.exit(&now, &mut account_data);
+      game_base.decay_game.exit(&now, &mut account_data)?;
       let amount = game_base.decay_game.claim(&mut account_data);
       self.game_base.save(storage, &game_base)?;
       self.accounts.save(storage, account, &account_data)?;
       Ok((amount, account_data.decay_snapshot))
    }
 
+   /// Takes `amount` of the stake of `account` out, leaving at least a ticket in play. Returns
+   /// the amount paid.
+   pub fn exit_partial(
+      &self,
+      storage: &mut dyn Storage,
+      now: Timestamp,
+      account: &Addr,
+      amount: Uint128,
+      config: &Config,
+   ) -> Result<(Uint128, Decimal), ContractError> {
+      let mut game_base = self.game_base.load(storage)?;
+      ensure!(
+         now.lt(&game_base.decay_game.decay_ends_at),
+         ContractError::Invalid("game_ended".to_string())
+      );
+
+      let mut account_data = self.accounts.load(storage, account)?;
+      game_base.decay_game.exit_partial(&now, &mut account_data, amount)?;
+      ensure!(
+         account_data.amount >= config.ticket_amount,
+         ContractError::Invalid("exit_amount".to_string())
+      );
+      let paid = game_base.decay_game.claim(&mut account_data);
+      self.game_base.save(storage, &game_base)?;
+      self.accounts.save(storage, account, &account_data)?;
+      Ok((paid, game_base.decay_game.decay_factor(&now)))
+   }
+
    pub fn endgame(
       self,
       storage: &mut dyn Storage,
@@ -134,62 +300,339 @@ impl<'a> GameSM<'a> {
       storage: &mut dyn Storage,
       decay_starts_at: Timestamp,
       decay_ends_at: Timestamp,
+      curve: DecayCurve,
    ) -> Result<(), ContractError> {
       self.accounts.clear(storage);
       self.ref_weight.clear(storage);
       self.players.clear(storage);
-      self.initialize(storage, decay_starts_at, decay_ends_at)?;
+      self.help_pairs.clear(storage);
+      self.help_received.clear(storage);
+      self.last_actions.clear(storage);
+      self.pair_hits.clear(storage);
+      self.shields.clear(storage);
+      self.history.clear(storage);
+      self.teams.clear(storage);
+      self.team_of.clear(storage);
+      self.team_board.clear(storage);
+      self.ranking.remove(storage);
+      self.consolation.remove(storage);
+      self.leaderboard.clear(storage);
+      self.initialize(storage, decay_starts_at, decay_ends_at, curve)?;
 
       Ok(())
    }
 
-   pub fn check_winner(&self, game_base: &mut GameBase, account: &Addr, points: i64) {
-      if game_base.current_winner.is_none() || points > game_base.current_winner.clone().unwrap().1
-      {
-         game_base.current_winner = Some((account.clone(), points));
+   /// Players by points in leaderboard order
+   pub fn get_ranking(&self, storage: &dyn Storage) -> StdResult<Vec<(Addr, i64)>> {
+      self
+         .leaderboard
+         .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+         .map(|item| item.map(|(points, addr)| (addr, -points)))
+         .collect()
+   }
+
+   /// Stores the final standings and the winner of the round
+   pub fn save_ranking(
+      &self,
+      storage: &mut dyn Storage,
+      ranking: &Vec<Rank>,
+      winner: &Rank,
+   ) -> StdResult<()> {
+      let mut game_base = self.game_base.load(storage)?;
+      game_base.current_winner = Some((winner.address.clone(), winner.points));
+      self.game_base.save(storage, &game_base)?;
+      self.ranking.save(storage, ranking)
+   }
+
+   /// Marks every account still in play but `winners` as forfeited, returns their stakes by
+   /// account
+   pub fn forfeit(
+      &self,
+      storage: &mut dyn Storage,
+      now: Timestamp,
+      winners: &[Addr],
+   ) -> Result<Vec<(Addr, Uint128)>, ContractError> {
+      let mut game_base = self.game_base.load(storage)?;
+      let accounts = self
+         .accounts
+         .range(storage, None, None, cosmwasm_std::Order::Ascending)
+         .collect::<StdResult<Vec<_>>>()?;
+      let mut forfeited = vec![];
+      for (addr, mut account) in accounts {
+         if !account.is_active() || winners.contains(&addr) {
+            continue;
+         }
+         game_base.decay_game.forfeit(&now, &mut account)?;
+         self.accounts.save(storage, &addr, &account)?;
+         forfeited.push((addr, account.amount));
       }
+      self.game_base.save(storage, &game_base)?;
+      Ok(forfeited)
    }
 
+   /// Sets the winner to the head of the leaderboard and the winning team to the first name with
+   /// the most team points
    pub fn update_winner(
       &self,
       storage: &dyn Storage,
       game_base: &mut GameBase,
    ) -> Result<(), ContractError> {
-      let mut max_points = i64::MIN;
-      let mut winner: Option<Addr> = None;
-      for item in self.players.range(storage, None, None, cosmwasm_std::Order::Ascending) {
-         let (addr, player) = item?;
-         if player.points > max_points {
-            max_points = player.points;
-            winner = Some(addr.clone());
-         }
-      }
-      game_base.current_winner = winner.map(|addr| (addr, max_points));
+      let top = self
+         .team_board
+         .keys(storage, None, None, cosmwasm_std::Order::Descending)
+         .next()
+         .transpose()?;
+      game_base.winning_team = match top {
+         Some((points, _)) => self
+            .team_board
+            .prefix(points)
+            .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+            .next()
+            .transpose()?
+            .map(|name| (name, points)),
+         None => None,
+      };
+
+      game_base.current_winner = self
+         .leaderboard
+         .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+         .next()
+         .transpose()?
+         .map(|(points, addr)| (addr, -points));
       Ok(())
    }
 
-   fn apply_points(&self, player: &mut PlayerStatus, points: i64) {
+   fn apply_points(
+      &self,
+      storage: &mut dyn Storage,
+      player: &mut PlayerStatus,
+      points: i64,
+   ) -> StdResult<()> {
+      let before = player.points;
+      self.leaderboard.remove(storage, (-player.points, &player.address));
       player.points = (player.points.saturating_add(points)).max(0);
+      self.leaderboard.save(storage, (-player.points, &player.address), &Empty {})?;
+
+      if let Some(name) = self.team_of.may_load(storage, &player.address)? {
+         let mut team = self.teams.load(storage, &name)?;
+         self.team_board.remove(storage, (team.points, &name));
+         team.points = team.points.saturating_add(player.points - before);
+         self.team_board.save(storage, (team.points, &name), &Empty {})?;
+         self.teams.save(storage, &name, &team)?;
+      }
+      Ok(())
    }
 
+   /// Adds `account` to team `name`, creating the team first when `create` is set, and refreshes
+   /// the winning team
+   pub fn add_to_team(
+      &self,
+      storage: &mut dyn Storage,
+      account: &Addr,
+      name: &str,
+      rules: &TeamRules,
+      create: bool,
+   ) -> Result<Team, ContractError> {
+      ensure!(!self.team_of.has(storage, account), ContractError::Invalid("in_team".to_string()));
+      let mut team = match (create, self.teams.may_load(storage, name)?) {
+         (true, None) => {
+            ensure!(
+               !name.is_empty() && name.len() <= MAX_TEAM_NAME_LEN,
+               ContractError::Invalid("team_name".to_string())
+            );
+            Team { name: name.to_string(), members: vec![], points: 0 }
+         }
+         (false, Some(team)) => team,
+         (true, Some(_)) => return Err(ContractError::Invalid("team_exists".to_string())),
+         (false, None) => return Err(ContractError::Invalid("team_not_found".to_string())),
+      };
+      ensure!(
+         team.members.len() < rules.max_members as usize,
+         ContractError::Invalid("team_full".to_string())
+      );
+
+      let points = self.players.load(storage, account)?.points;
+      self.team_board.remove(storage, (team.points, name));
+      team.points = team.points.saturating_add(points);
+      team.members.push(account.clone());
+      self.teams.save(storage, name, &team)?;
+      self.team_board.save(storage, (team.points, name), &Empty {})?;
+      self.team_of.save(storage, account, &name.to_string())?;
+      let mut game_base = self.game_base.load(storage)?;
+      self.update_winner(storage, &mut game_base)?;
+      self.game_base.save(storage, &game_base)?;
+      Ok(team)
+   }
+
+   /// Members of team `name` with their share of its points. Members without points get no
+   /// share unless nobody scored.
+   pub fn team_shares(&self, storage: &dyn Storage, name: &str) -> StdResult<Vec<(Addr, Decimal)>> {
+      let team = self.teams.load(storage, name)?;
+      let mut members = vec![];
+      for member in team.members {
+         let points = self.players.load(storage, &member)?.points;
+         members.push((member, points.max(0) as u64));
+      }
+      let total: u64 = members.iter().map(|(_, points)| points).sum();
+      if total == 0 {
+         let count = members.len() as u64;
+         return Ok(members
+            .into_iter()
+            .map(|(addr, _)| (addr, Decimal::from_ratio(1u64, count)))
+            .collect());
+      }
+      Ok(members
+         .into_iter()
+         .filter(|(_, points)| *points > 0)
+         .map(|(addr, points)| (addr, Decimal::from_ratio(points, total)))
+         .collect())
+   }
+
+   pub fn get_teams(
+      &self,
+      storage: &dyn Storage,
+      start_after: Option<String>,
+      limit: usize,
+   ) -> StdResult<Vec<Team>> {
+      self
+         .teams
+         .range(
+            storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            cosmwasm_std::Order::Ascending,
+         )
+         .take(limit)
+         .map(|item| item.map(|(_, team)| team))
+         .collect()
+   }
+
+   /// Players by points from the highest, ties ordered by address. `start_after` is the last
+   /// entry of the previous page.
+   pub fn get_leaderboard(
+      &self,
+      storage: &dyn Storage,
+      start_after: Option<(Addr, i64)>,
+      limit: usize,
+   ) -> StdResult<Vec<(Addr, i64)>> {
+      let start = start_after.as_ref().map(|(addr, points)| Bound::exclusive((-*points, addr)));
+      self
+         .leaderboard
+         .keys(storage, start, None, cosmwasm_std::Order::Ascending)
+         .take(limit)
+         .map(|item| item.map(|(points, addr)| (addr, -points)))
+         .collect()
+   }
+
+   /// Enforces the play delay, the cooldown and the cost of `msg`, `paid` being the amount sent
+   /// with it
+   #[allow(clippy::too_many_arguments)]
    pub fn update_play(
       &self,
+      storage: &mut dyn Storage,
       player: &mut PlayerStatus,
       points: i64,
       msg: PlayMsg,
       now: Timestamp,
-      delay: u64,
+      config: &Config,
+      paid: Uint128,
    ) -> Result<(), ContractError> {
       ensure!(
-         now.ge(&player.last_play.plus_seconds(delay)),
+         now.ge(&player.last_play.plus_seconds(config.delay_play_seconds)),
          ContractError::Invalid("play_timestamp".to_string())
       );
-      self.apply_points(player, points);
-      player.history.push(msg);
+
+      let rule = config.action_rules.rule(&msg);
+      let kind = (&player.address, msg.kind());
+      if let Some(last) = self.last_actions.may_load(storage, kind)? {
+         ensure!(
+            now.ge(&last.plus_seconds(rule.cooldown_seconds)),
+            ContractError::Invalid("action_cooldown".to_string())
+         );
+      }
+      self.last_actions.save(storage, kind, &now)?;
+
+      let mut cost = rule.cost;
+      if let PlayMsg::Hit { target } = &msg {
+         let hits =
+            self.pair_hits.may_load(storage, (&player.address, target))?.unwrap_or_default();
+         cost += config.action_rules.hit_cost_step * Uint128::from(hits);
+         self.pair_hits.save(storage, (&player.address, target), &(hits + 1))?;
+      }
+      ensure!(paid == cost, ContractError::Invalid("action_cost".to_string()));
+
+      self.apply_points(storage, player, points)?;
       player.last_play = now;
       Ok(())
    }
 
+   fn record_play(
+      &self,
+      storage: &mut dyn Storage,
+      player: &mut PlayerStatus,
+      record: &PlayRecord,
+   ) -> StdResult<()> {
+      self.history.save(storage, (&player.address, player.plays), record)?;
+      player.plays += 1;
+      Ok(())
+   }
+
+   /// Plays of `addr` this round from the latest, `start_after` is the last sequence of the
+   /// previous page
+   pub fn get_history(
+      &self,
+      storage: &dyn Storage,
+      addr: &Addr,
+      start_after: Option<u64>,
+      limit: usize,
+   ) -> StdResult<Vec<(u64, PlayRecord)>> {
+      self
+         .history
+         .prefix(addr)
+         .range(storage, None, start_after.map(Bound::exclusive), cosmwasm_std::Order::Descending)
+         .take(limit)
+         .collect()
+   }
+
+   /// Enforces the pair limit, the rate limit and the account age of `gate` on a help from
+   /// `helper` to `target`, counting the help when allowed
+   pub fn check_help(
+      &self,
+      storage: &mut dyn Storage,
+      gate: &HelpGate,
+      helper: &Addr,
+      target: &Addr,
+      now: Timestamp,
+   ) -> Result<(), ContractError> {
+      if let Some(min_age) = gate.min_account_age {
+         let first_join = self.first_joins.may_load(storage, helper)?.unwrap_or(now);
+         ensure!(
+            now.seconds().saturating_sub(first_join.seconds()) >= min_age,
+            ContractError::Invalid("account_age".to_string())
+         );
+      }
+
+      if let Some(max_helps) = gate.max_pair_helps {
+         let pair = if helper < target { (helper, target) } else { (target, helper) };
+         let helps = self.help_pairs.may_load(storage, pair)?.unwrap_or_default();
+         ensure!(helps < max_helps, ContractError::Invalid("help_pair_limit".to_string()));
+         self.help_pairs.save(storage, pair, &(helps + 1))?;
+      }
+
+      if let Some(limit) = &gate.help_rate_limit {
+         let (window_start, received) = match self.help_received.may_load(storage, target)? {
+            Some((start, received)) if now < start.plus_seconds(limit.window_seconds) => {
+               (start, received)
+            }
+            _ => (now, 0),
+         };
+         ensure!(received < limit.max_helps, ContractError::Invalid("help_rate_limit".to_string()));
+         self.help_received.save(storage, target, &(window_start, received + 1))?;
+      }
+      Ok(())
+   }
+
+   /// Applies `msg` from `account`, returns the claim and the decay factor of a rug
    pub fn play(
       &self,
       storage: &mut dyn Storage,
@@ -197,44 +640,121 @@ impl<'a> GameSM<'a> {
       account: &Addr,
       config: &Config,
       now: Timestamp,
-   ) -> Result<(), ContractError> {
+      paid: Uint128,
+   ) -> Result<Option<(Uint128, Decimal)>, ContractError> {
+      ensure!(
+         config.points.is_enabled(&msg),
+         ContractError::Invalid("action_disabled".to_string())
+      );
       let mut game_base = self.game_base.load(storage)?;
       let mut player = self.players.load(storage, account)?;
 
       // Define points and target player update based on `PlayMsg`
-      let (player_points, target_points, target, hit) = match &msg {
-         PlayMsg::Keep {} => (config.points.keep, 0, None, false),
-         PlayMsg::Hit { target } => (0, config.points.hit, Some(target), true),
+      let (player_points, target_points, target) = match &msg {
+         PlayMsg::Keep {} => (config.points.keep, 0, None),
+         PlayMsg::Hit { target } => (0, config.points.hit, Some(target)),
          PlayMsg::Help { target } => {
-            (config.points.help.myself, config.points.help.other, Some(target), false)
+            (config.points.help.myself, config.points.help.other, Some(target))
          }
+         PlayMsg::Steal { target } => (config.points.steal, -config.points.steal, Some(target)),
+         PlayMsg::Shield {} | PlayMsg::Rug {} => (0, 0, None),
+      };
+      let stake = self.accounts.load(storage, account)?.amount;
+      let mut player_points = config.weigh_points(player_points, stake);
+      let mut target_points = config.weigh_points(target_points, stake);
+      let attack = matches!(msg, PlayMsg::Hit { .. } | PlayMsg::Steal { .. });
+
+      // in team mode only members play, teammates help each other at the team rate
+      let team = match &config.teams {
+         Some(_) => Some(
+            self
+               .team_of
+               .may_load(storage, account)?
+               .ok_or(ContractError::Invalid("not_in_team".to_string()))?,
+         ),
+         None => None,
       };
+      if let (Some(rules), Some(target)) = (&config.teams, target) {
+         if team == self.team_of.may_load(storage, target)? {
+            ensure!(!attack, ContractError::Invalid("teammate".to_string()));
+            if let PlayMsg::Help { .. } = msg {
+               player_points = rules.teammate_help.points(player_points)?;
+               target_points = rules.teammate_help.points(target_points)?;
+            }
+         }
+      }
 
       if let Some(target) = target {
          ensure!(target != account, ContractError::Invalid("target".to_string()));
+         if attack {
+            ensure!(
+               !self.is_shielded(storage, target, now)?,
+               ContractError::Invalid("shielded".to_string())
+            );
+         } else {
+            self.check_help(storage, &config.help_gate, account, target, now)?;
+         }
+      }
+      // a steal moves no more than the target has
+      if let PlayMsg::Steal { target } = &msg {
+         player_points = player_points.min(self.players.load(storage, target)?.points);
+         target_points = -player_points;
+      }
+      let traded = player.points;
+      if let PlayMsg::Rug {} = msg {
+         player_points = -traded;
       }
 
       // Update the player's points and last play time
-      self.update_play(&mut player, player_points, msg.clone(), now, config.delay_play_seconds)?;
+      self.update_play(storage, &mut player, player_points, msg.clone(), now, config, paid)?;
+      // action costs go to the pot
+      game_base.decay_game.total += paid;
+      if let PlayMsg::Shield {} = msg {
+         self.shields.save(storage, account, &now.plus_seconds(config.points.shield_seconds))?;
+      }
 
       // Apply points to target player if applicable
+      let mut target_delta = 0;
       if let Some(target) = target {
          let mut target_player = self.players.load(storage, target)?;
-         self.apply_points(&mut target_player, target_points);
+         let before = target_player.points;
+         self.apply_points(storage, &mut target_player, target_points)?;
+         target_delta = target_player.points - before;
          self.players.save(storage, target, &target_player)?;
-         if hit && game_base.current_winner.clone().unwrap().0 == target {
-            self.update_winner(storage, &mut game_base)?;
-         } else {
-            self.check_winner(&mut game_base, &target, target_player.points);
-         }
       }
 
       // Save updated player state and check winner
+      let record = PlayRecord {
+         msg: msg.clone(),
+         at: now,
+         target: target.cloned(),
+         points: player.points - traded,
+         target_points: target_delta,
+      };
+      self.record_play(storage, &mut player, &record)?;
       self.players.save(storage, account, &player)?;
-      self.check_winner(&mut game_base, &account, player.points);
+      self.update_winner(storage, &mut game_base)?;
+      if let PlayMsg::Rug {} = msg {
+         let mut account_data = self.accounts.load(storage, account)?;
+         let bonus = config.points.rug_bonus * Decimal::from_ratio(traded.unsigned_abs(), 1u64);
+         game_base.decay_game.exit_with_bonus(&now, &mut account_data, bonus)?;
+         let amount = game_base.decay_game.claim(&mut account_data);
+         self.accounts.save(storage, account, &account_data)?;
+         self.game_base.save(storage, &game_base)?;
+         return Ok(Some((amount, account_data.decay_snapshot)));
+      }
       self.game_base.save(storage, &game_base)?;
 
-      Ok(())
+      Ok(None)
+   }
+
+   pub fn is_shielded(
+      &self,
+      storage: &dyn Storage,
+      addr: &Addr,
+      now: Timestamp,
+   ) -> StdResult<bool> {
+      Ok(self.shields.may_load(storage, addr)?.is_some_and(|until| now.lt(&until)))
    }
 
    pub fn is_ended(
@@ -244,12 +764,16 @@ impl<'a> GameSM<'a> {
    ) -> Result<bool, ContractError> {
       let game_base = self.game_base.load(storage)?;
       if now.le(&game_base.decay_game.decay_ends_at) {
-         return Ok(false);
+         Ok(false)
       } else {
-         return Ok(true);
+         Ok(true)
       }
    }
 
+   pub fn ends_at(&self, storage: &dyn Storage) -> StdResult<Timestamp> {
+      Ok(self.game_base.load(storage)?.decay_game.decay_ends_at)
+   }
+
    pub fn is_started(
       &self,
       storage: &mut dyn Storage,
@@ -257,9 +781,9 @@ impl<'a> GameSM<'a> {
    ) -> Result<bool, ContractError> {
       let game_base = self.game_base.load(storage)?;
       if now.lt(&game_base.decay_game.decay_starts_at) {
-         return Ok(false);
+         Ok(false)
       } else {
-         return Ok(true);
+         Ok(true)
       }
    }
 
@@ -270,12 +794,12 @@ impl<'a> GameSM<'a> {
    ) -> Result<bool, ContractError> {
       let game_base = self.game_base.load(storage)?;
       if now.le(&game_base.decay_game.decay_ends_at)
-         || !(game_base.decay_game.rewards
-            == game_base.decay_game.total - game_base.decay_game.exited)
+         || (game_base.decay_game.rewards
+            != game_base.decay_game.total - game_base.decay_game.exited)
       {
-         return Ok(false);
+         Ok(false)
       } else {
-         return Ok(true);
+         Ok(true)
       }
    }
 
@@ -284,11 +808,8 @@ impl<'a> GameSM<'a> {
    }
 
    pub fn has_exited(&self, storage: &mut dyn Storage, addr: &Addr) -> StdResult<bool> {
-      let acccount = self.accounts.load(storage, &addr)?;
-      if !acccount.decay_snapshot.is_zero() {
-         return Ok(true);
-      }
-      Ok(false)
+      let acccount = self.accounts.load(storage, addr)?;
+      Ok(!acccount.is_active())
    }
 
    pub fn get_ref_weights(
@@ -307,34 +828,94 @@ impl<'a> GameSM<'a> {
       Ok(referrals)
    }
 
-   pub fn get_snap(&self, storage: &dyn Storage) -> Result<Binary, ContractError> {
+   pub fn get_player(
+      &self,
+      storage: &dyn Storage,
+      addr: &Addr,
+   ) -> Result<Option<PlayerResponse>, ContractError> {
+      let Some(account) = self.accounts.may_load(storage, addr)? else {
+         return Ok(None);
+      };
+      let status = self.players.load(storage, addr)?;
+      Ok(Some(PlayerResponse { account, status }))
+   }
+
+   /// Snapshot of the live game with accounts and players paged by address, a `None` limit takes
+   /// every entry. Referrals are bounded to the address range covered by the page.
+   pub fn get_snap(
+      &self,
+      storage: &dyn Storage,
+      start_after: Option<&Addr>,
+      limit: Option<usize>,
+   ) -> Result<GameSmSnapshot, ContractError> {
       let game_base = self.game_base.load(storage)?;
       let decay_game = game_base.decay_game.clone();
       let current_winner = game_base.current_winner.clone();
 
-      let referrals = self.get_ref_weights(storage)?;
+      let accounts: Vec<(Addr, DecayGameAccount)> = self
+         .accounts
+         .range(storage, start_after.map(Bound::exclusive), None, cosmwasm_std::Order::Ascending)
+         .take(limit.unwrap_or(usize::MAX))
+         .collect::<StdResult<Vec<_>>>()?;
+
+      // a full page ends at its last account, the next one starts right after it
+      let end = match limit {
+         Some(limit) if accounts.len() == limit => accounts.last().map(|(addr, _)| addr),
+         _ => None,
+      };
 
       let players = self
          .players
-         .range(storage, None, None, cosmwasm_std::Order::Ascending)
+         .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            end.map(Bound::inclusive),
+            cosmwasm_std::Order::Ascending,
+         )
          .map(|item| {
             let (_, player) = item?;
             Ok(player)
          })
          .collect::<StdResult<Vec<_>>>()?;
 
-      let accounts: Vec<(Addr, DecayGameAccount)> = self
-         .accounts
-         .range(storage, None, None, cosmwasm_std::Order::Ascending)
-         .map(|item| {
-            let (address, account) = item?;
-            Ok((address, account))
-         })
+      let referrals = self
+         .ref_weight
+         .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            end.map(Bound::inclusive),
+            cosmwasm_std::Order::Ascending,
+         )
          .collect::<StdResult<Vec<_>>>()?;
 
-      let status = GameSmSnapshot { decay_game, current_winner, referrals, players, accounts };
+      let ranking = self.ranking.may_load(storage)?.unwrap_or_default();
 
-      Ok(to_json_binary(&status)?)
+      Ok(GameSmSnapshot { decay_game, current_winner, referrals, players, accounts, ranking })
+   }
+
+   /// Summary of the live round, archived when it restarts
+   pub fn get_summary(&self, storage: &dyn Storage) -> StdResult<RoundSummary> {
+      let game_base = self.game_base.load(storage)?;
+      let players = self
+         .players
+         .range(storage, None, None, cosmwasm_std::Order::Ascending)
+         .map(|item| item.map(|(_, player)| player))
+         .collect::<StdResult<Vec<_>>>()?;
+      Ok(RoundSummary {
+         starts_at: game_base.decay_game.decay_starts_at,
+         ends_at: game_base.decay_game.decay_ends_at,
+         winner: game_base.current_winner,
+         ranking: self.ranking.may_load(storage)?.unwrap_or_default(),
+         total: game_base.decay_game.total,
+         exited: game_base.decay_game.exited,
+         players,
+         referrals: self
+            .ref_weight
+            .range(storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?,
+         consolation: self.consolation.may_load(storage)?.unwrap_or_default(),
+         winning_team: game_base.winning_team,
+      })
    }
 }
 
@@ -342,7 +923,7 @@ impl<'a> GameSM<'a> {
 mod test {
    use super::*;
    use cosmwasm_std::testing::mock_dependencies;
-   use cosmwasm_std::{from_json, Addr, Decimal, Timestamp, Uint128};
+   use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 
    #[test]
    fn test_initialize() {
@@ -353,7 +934,7 @@ mod test {
       let start_time = Timestamp::from_seconds(0);
       let end_time = Timestamp::from_seconds(100);
 
-      let result = state.initialize(deps.storage, start_time, end_time);
+      let result = state.initialize(deps.storage, start_time, end_time, DecayCurve::Linear);
       assert!(result.is_ok(), "Initialization should succeed");
 
       // Boundary check: Verify decay start and end timestamps are set correctly
@@ -393,7 +974,12 @@ mod test {
       let user = Addr::unchecked("user");
 
       state
-         .initialize(deps.storage, Timestamp::from_seconds(0), Timestamp::from_seconds(100))
+         .initialize(
+            deps.storage,
+            Timestamp::from_seconds(0),
+            Timestamp::from_seconds(100),
+            DecayCurve::Linear,
+         )
          .unwrap();
 
       state.join(deps.storage, Timestamp::from_seconds(1), &user, Uint128::new(100)).unwrap();
@@ -414,7 +1000,12 @@ mod test {
       let deps = odeps.as_mut();
 
       state
-         .initialize(deps.storage, Timestamp::from_seconds(0), Timestamp::from_seconds(100))
+         .initialize(
+            deps.storage,
+            Timestamp::from_seconds(0),
+            Timestamp::from_seconds(100),
+            DecayCurve::Linear,
+         )
          .unwrap();
 
       // Endgame should succeed without participants and no winner should be set
@@ -431,12 +1022,13 @@ mod test {
       let mut player = PlayerStatus {
          address: Addr::unchecked("user"),
          points: i64::MAX - 1,
-         history: vec![],
+         plays: 0,
          last_play: Timestamp::from_seconds(0),
       };
       let state = GameSM::new();
 
-      state.apply_points(&mut player, 2);
+      let mut odeps = mock_dependencies();
+      state.apply_points(odeps.as_mut().storage, &mut player, 2).unwrap();
       assert_eq!(player.points, i64::MAX, "Points should max out at i64::MAX");
    }
 
@@ -447,7 +1039,12 @@ mod test {
       let deps = odeps.as_mut();
 
       state
-         .initialize(deps.storage, Timestamp::from_seconds(0), Timestamp::from_seconds(100))
+         .initialize(
+            deps.storage,
+            Timestamp::from_seconds(0),
+            Timestamp::from_seconds(100),
+            DecayCurve::Linear,
+         )
          .unwrap();
 
       // Boundary test: Game should not end right before decay_ends_at
@@ -466,7 +1063,7 @@ mod test {
       let end_time = Timestamp::from_seconds(100);
 
       // Initialize game state
-      state.initialize(deps.storage, start_time, end_time).unwrap();
+      state.initialize(deps.storage, start_time, end_time, DecayCurve::Linear).unwrap();
 
       // Add a player and increase ref counts
       let user = Addr::unchecked("user");
@@ -475,8 +1072,8 @@ mod test {
       state.increase_ref(deps.storage, &user).unwrap();
 
       // Take a snapshot
-      let snap = state.get_snap(deps.storage).unwrap();
-      let snapshot: GameSmSnapshot = from_json(snap).unwrap();
+      let snap = state.get_snap(deps.storage, None, None).unwrap();
+      let snapshot = snap;
       println!("{:?}", snapshot);
 
       // Verify snapshot structure
@@ -498,4 +1095,53 @@ mod test {
       assert_eq!(snapshot.referrals.len(), 1, "There should be one referral in snapshot");
       assert_eq!(snapshot.referrals[0].1, Decimal::percent(100), "Referral weight should match");
    }
+
+   #[test]
+   fn test_get_snap_pages() {
+      let mut odeps = mock_dependencies();
+      let state = GameSM::new();
+      let deps = odeps.as_mut();
+
+      state
+         .initialize(
+            deps.storage,
+            Timestamp::from_seconds(0),
+            Timestamp::from_seconds(100),
+            DecayCurve::Linear,
+         )
+         .unwrap();
+
+      let users: Vec<Addr> = ["a", "c", "e"].into_iter().map(Addr::unchecked).collect();
+      for user in &users {
+         state.join(deps.storage, Timestamp::from_seconds(1), user, Uint128::new(100)).unwrap();
+      }
+      for ambassador in ["b", "d", "f"] {
+         state.increase_ref(deps.storage, &Addr::unchecked(ambassador)).unwrap();
+      }
+
+      let page = |start_after: Option<&Addr>, limit: Option<usize>| -> GameSmSnapshot {
+         state.get_snap(deps.storage, start_after, limit).unwrap()
+      };
+
+      let first = page(None, Some(2));
+      assert_eq!(
+         first.accounts.iter().map(|(a, _)| a).collect::<Vec<_>>(),
+         vec![&users[0], &users[1]]
+      );
+      assert_eq!(first.players.len(), 2);
+      assert_eq!(first.referrals, vec![(Addr::unchecked("b"), Decimal::one())]);
+
+      // the last page takes the rest of the referrals
+      let last = page(Some(&users[1]), Some(2));
+      assert_eq!(last.accounts.len(), 1);
+      assert_eq!(last.players[0].address, users[2]);
+      assert_eq!(
+         last.referrals,
+         vec![(Addr::unchecked("d"), Decimal::one()), (Addr::unchecked("f"), Decimal::one())]
+      );
+
+      let all = page(None, None);
+      assert_eq!(all.accounts.len(), 3);
+      assert_eq!(all.referrals.len(), 3);
+   }
 }
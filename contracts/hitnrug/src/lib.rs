@@ -2,6 +2,7 @@ pub mod config;
 pub mod contract;
 mod error;
 pub mod game;
+pub mod migrations;
 pub mod msg;
 pub mod state;
 
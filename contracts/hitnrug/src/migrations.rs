@@ -0,0 +1,146 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+   from_json, Addr, Binary, Decimal, Empty, Order, StdResult, Storage, Timestamp, Uint128,
+};
+use cw_storage_plus::{Item, Map};
+use semver::Version;
+use wenruji_rs::{AccountStatus, DecayCurve, DecayGame, DecayGameAccount};
+
+use crate::{
+   config::{Config, PrizeTable, DEFAULT_ROUND_RETENTION},
+   game::{Consolation, PlayerStatus, RoundSummary},
+   msg::{ActionRules, Fees, HelpGate, PlayMsg, Points},
+   state::{archive_round, GAME_SM},
+   ContractError,
+};
+
+/// Config layout of the v0.1 releases, before the decay curve was configurable
+#[cw_serde]
+pub struct ConfigV0_1 {
+   pub owner: Addr,
+   pub ticket_denom: String,
+   pub ticket_amount: Uint128,
+   pub duration_seconds: u64,
+   pub game_delay_sec: u64,
+   pub delay_play_seconds: u64,
+   pub winner_share: Decimal,
+   pub fees: Fees,
+   pub points: Points,
+}
+
+/// Player layout of the v0.1 releases, with every play stored inline
+#[cw_serde]
+pub struct PlayerStatusV0_1 {
+   pub address: Addr,
+   pub points: i64,
+   pub history: Vec<PlayMsg>,
+   pub last_play: Timestamp,
+}
+
+impl From<PlayerStatusV0_1> for PlayerStatus {
+   /// The inline plays carry no time nor points and are dropped
+   fn from(player: PlayerStatusV0_1) -> Self {
+      Self { address: player.address, points: player.points, plays: 0, last_play: player.last_play }
+   }
+}
+
+/// Round snapshot of the v0.1 releases
+#[cw_serde]
+pub struct SnapshotV0_1 {
+   pub decay_game: DecayGame,
+   pub accounts: Vec<(Addr, DecayGameAccount)>,
+   pub current_winner: Option<(Addr, i64)>,
+   pub players: Vec<PlayerStatusV0_1>,
+   pub referrals: Vec<(Addr, Decimal)>,
+}
+
+pub static CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+/// Round snapshots of the v0.1 releases, JSON of `SnapshotV0_1`
+pub const SNAPSHOT_V0_1: Map<u64, Binary> = Map::new("snap");
+pub const PLAYERS_V0_1: Map<&Addr, PlayerStatusV0_1> = Map::new("gm/p");
+
+/// Applies in order every state transform between the stored version and the current one
+pub fn migrate_state(
+   storage: &mut dyn Storage,
+   stored: &Version,
+   now: Timestamp,
+) -> Result<(), ContractError> {
+   if stored.major == 0 && stored.minor < 2 {
+      migrate_v0_1(storage, now)?;
+   }
+   Ok(())
+}
+
+/// Adds the decay curve, the help gate and the prize table, records the first join of the live
+/// accounts, gives them a status, moves the plays out of the players, builds the leaderboard and
+/// converts the round snapshots
+fn migrate_v0_1(storage: &mut dyn Storage, now: Timestamp) -> Result<(), ContractError> {
+   let old = CONFIG_V0_1.load(storage)?;
+   let config = Config {
+      owner: old.owner,
+      ticket_denom: old.ticket_denom,
+      ticket_amount: old.ticket_amount,
+      duration_seconds: old.duration_seconds,
+      game_delay_sec: old.game_delay_sec,
+      delay_play_seconds: old.delay_play_seconds,
+      winner_share: old.winner_share,
+      fees: old.fees,
+      points: old.points,
+      decay_curve: DecayCurve::Linear,
+      help_gate: HelpGate::default(),
+      prize_table: PrizeTable::Winner,
+      settlement_fee: Decimal::zero(),
+      round_retention: DEFAULT_ROUND_RETENTION,
+      max_ticket_amount: None,
+      stake_weighted_points: false,
+      consolation_share: Decimal::zero(),
+      action_rules: ActionRules::default(),
+      teams: None,
+   };
+   config.save(storage)?;
+
+   // accounts of the live round are taken as joined at its start
+   let game_base = GAME_SM.game_base.load(storage)?;
+   let accounts = GAME_SM
+      .accounts
+      .keys(storage, None, None, Order::Ascending)
+      .collect::<StdResult<Vec<Addr>>>()?;
+   for account in accounts {
+      GAME_SM.first_joins.save(storage, &account, &game_base.decay_game.decay_starts_at)?;
+      // v0.1 claimed on exit without recording when, the migration time stands in for it
+      let mut account_data = GAME_SM.accounts.load(storage, &account)?;
+      if !account_data.decay_snapshot.is_zero() {
+         account_data.status = AccountStatus::Claimed { at: now };
+         GAME_SM.accounts.save(storage, &account, &account_data)?;
+      }
+   }
+
+   let players =
+      PLAYERS_V0_1.range(storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+   for (addr, player) in players {
+      GAME_SM.leaderboard.save(storage, (-player.points, &addr), &Empty {})?;
+      GAME_SM.players.save(storage, &addr, &player.into())?;
+   }
+
+   // round snapshots become typed summaries
+   let snapshots =
+      SNAPSHOT_V0_1.range(storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+   for (idx, snap) in snapshots {
+      let snap: SnapshotV0_1 = from_json(snap)?;
+      let summary = RoundSummary {
+         starts_at: snap.decay_game.decay_starts_at,
+         ends_at: snap.decay_game.decay_ends_at,
+         winner: snap.current_winner,
+         ranking: vec![],
+         total: snap.decay_game.total,
+         exited: snap.decay_game.exited,
+         players: snap.players.into_iter().map(PlayerStatus::from).collect(),
+         referrals: snap.referrals,
+         consolation: Consolation::default(),
+         winning_team: None,
+      };
+      archive_round(storage, idx, &summary, config.round_retention)?;
+      SNAPSHOT_V0_1.remove(storage, idx);
+   }
+   Ok(())
+}
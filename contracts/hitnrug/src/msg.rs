@@ -1,9 +1,11 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
+use wenruji_rs::{keeper::NextAction, DecayCurve, DecayGameAccount, PlayerStats};
 
 use crate::{
-   config::{Config, ConfigUpdate},
-   game::GameSmSnapshot,
+   config::{Config, ConfigUpdate, PrizeTable},
+   game::{Consolation, GameSmSnapshot, PlayRecord, PlayerStatus, RoundSummary, Team},
+   ContractError,
 };
 
 #[cw_serde]
@@ -17,29 +19,133 @@ pub struct InstantiateMsg {
    pub delay_play_seconds: u64,
    pub fees: Fees,
    pub points: Points,
+   pub decay_curve: Option<DecayCurve>,
+   pub help_gate: Option<HelpGate>,
+   pub prize_table: Option<PrizeTable>,
+   pub settlement_fee: Option<Decimal>,
+   /// Archived rounds kept in storage, defaults to 10
+   pub round_retention: Option<u64>,
+   /// Largest stake of an account, stakes are exactly `ticket_amount` when not set
+   pub max_ticket_amount: Option<Uint128>,
+   /// Scales the points of every play by the stake of the player, defaults to false
+   pub stake_weighted_points: Option<bool>,
+   /// Share of the forfeited stake paid back by points at settlement, defaults to zero
+   pub consolation_share: Option<Decimal>,
+   /// Costs and cooldowns of each play, free and only bound by `delay_play_seconds` by default
+   pub action_rules: Option<ActionRules>,
+   /// Team play, players play alone when not set
+   pub teams: Option<TeamRules>,
 }
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
-   Join { ref_code: Option<String> },
+   /// Settles the previous round first when it is over, see `Exit`
+   Join {
+      ref_code: Option<String>,
+   },
+   /// Once the round is over, settles it and starts the next one instead of exiting. The sender
+   /// is paid the settlement fee.
    Exit {},
+   /// Takes `amount` of the stake out at the current decay and keeps the rest, at least a ticket,
+   /// in play
+   ExitPartial {
+      amount: Uint128,
+   },
+   /// Adds the funds to the stake of the sender before the round starts, up to
+   /// `max_ticket_amount`
+   TopUp {},
+   /// Creates team `name` with the sender as its first member, in team mode and before the
+   /// round starts
+   CreateTeam {
+      name: String,
+   },
+   /// Joins team `name`, in team mode and before the round starts
+   JoinTeam {
+      name: String,
+   },
    Play(PlayMsg),
    EndGame {},
+   /// Settles the round once it is over and starts the next one, open to anyone and a no-op
+   /// while the round is live. The sender is paid the settlement fee.
+   Settle {},
    Restart {},
-   UpdateConfig { new_config: ConfigUpdate },
+   UpdateConfig {
+      new_config: ConfigUpdate,
+   },
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
+   /// Page of the live round keyed by player address
    #[returns(GameSmSnapshot)]
-   GameStatus { idx: Option<u64> },
+   GameStatus { start_after: Option<Addr>, limit: Option<u32> },
+
+   /// Archived round `idx`, see `Config::round_retention`
+   #[returns(Option<RoundSummary>)]
+   Round { idx: u64 },
+
+   /// Archived rounds from the latest, `start_after` is the last index of the previous page
+   #[returns(Vec<(u64, RoundSummary)>)]
+   RoundHistory { start_after: Option<u64>, limit: Option<u32> },
+
+   /// Indexes of the archived rounds won by `addr`, from the latest
+   #[returns(Vec<u64>)]
+   RoundsWon { addr: Addr, start_after: Option<u64>, limit: Option<u32> },
+
+   /// Indexes of the archived rounds joined by `addr`, from the latest
+   #[returns(Vec<u64>)]
+   RoundsPlayed { addr: Addr, start_after: Option<u64>, limit: Option<u32> },
 
    #[returns(Config)]
    Config {},
 
    #[returns(Uint128)]
    GameIndex {},
+
+   #[returns(Option<PlayerResponse>)]
+   Player { addr: Addr },
+
+   /// Lifetime statistics of `addr`, zeroed for unknown accounts
+   #[returns(PlayerStats)]
+   PlayerStats { addr: Addr },
+
+   /// Players of the live round from the most points, `start_after` is the last entry of the
+   /// previous page
+   #[returns(Vec<(Addr, i64)>)]
+   Leaderboard { start_after: Option<(Addr, i64)>, limit: Option<u32> },
+
+   /// Plays of `addr` in the live round from the latest, `start_after` is the last sequence of
+   /// the previous page
+   #[returns(Vec<(u64, PlayRecord)>)]
+   PlayerHistory { addr: Addr, start_after: Option<u64>, limit: Option<u32> },
+
+   /// Team `name` of the live round
+   #[returns(Option<Team>)]
+   Team { name: String },
+
+   /// Teams of the live round by name
+   #[returns(Vec<Team>)]
+   Teams { start_after: Option<String>, limit: Option<u32> },
+
+   /// Stake forfeited in the live round and its consolation payouts, empty until the round is
+   /// settled
+   #[returns(Consolation)]
+   Consolation {},
+
+   /// When a keeper should send `Settle {}` next
+   #[returns(NextAction)]
+   NextAction {},
+}
+
+#[cw_serde]
+pub struct PlayerResponse {
+   pub account: DecayGameAccount,
+   pub status: PlayerStatus,
 }
 
 #[cw_serde]
@@ -57,8 +163,33 @@ pub struct Fee {
 #[cw_serde]
 pub enum PlayMsg {
    Keep {},
-   Hit { target: Addr },
-   Help { target: Addr },
+   Hit {
+      target: Addr,
+   },
+   Help {
+      target: Addr,
+   },
+   /// Blocks hits and steals on the player for `Points::shield_seconds`
+   Shield {},
+   /// Moves `Points::steal` points of the target to the player
+   Steal {
+      target: Addr,
+   },
+   /// Trades all the points of the player for an exit at a better decay factor
+   Rug {},
+}
+
+impl PlayMsg {
+   pub fn kind(&self) -> &'static str {
+      match self {
+         PlayMsg::Keep {} => "keep",
+         PlayMsg::Hit { .. } => "hit",
+         PlayMsg::Help { .. } => "help",
+         PlayMsg::Shield {} => "shield",
+         PlayMsg::Steal { .. } => "steal",
+         PlayMsg::Rug {} => "rug",
+      }
+   }
 }
 
 #[cw_serde]
@@ -66,6 +197,28 @@ pub struct Points {
    pub keep: i64,
    pub hit: i64,
    pub help: Point,
+   /// Seconds a shield lasts, `PlayMsg::Shield` is off when zero
+   #[serde(default)]
+   pub shield_seconds: u64,
+   /// Points moved by a steal, never more than the target has. `PlayMsg::Steal` is off when
+   /// zero.
+   #[serde(default)]
+   pub steal: i64,
+   /// Added to the decay factor of a rug for each point traded, capped at 1. `PlayMsg::Rug` is
+   /// off when zero.
+   #[serde(default)]
+   pub rug_bonus: Decimal,
+}
+
+impl Points {
+   pub fn is_enabled(&self, msg: &PlayMsg) -> bool {
+      match msg {
+         PlayMsg::Shield {} => self.shield_seconds > 0,
+         PlayMsg::Steal { .. } => self.steal > 0,
+         PlayMsg::Rug {} => !self.rug_bonus.is_zero(),
+         _ => true,
+      }
+   }
 }
 
 #[cw_serde]
@@ -73,3 +226,97 @@ pub struct Point {
    pub myself: i64,
    pub other: i64,
 }
+
+/// Payment and wait required by a play
+#[cw_serde]
+#[derive(Default)]
+pub struct ActionRule {
+   /// Paid in `ticket_denom` with the play and added to the pot
+   pub cost: Uint128,
+   /// Seconds since the last play of the same kind by the player, on top of
+   /// `delay_play_seconds`
+   pub cooldown_seconds: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ActionRules {
+   pub keep: ActionRule,
+   pub hit: ActionRule,
+   pub help: ActionRule,
+   #[serde(default)]
+   pub shield: ActionRule,
+   #[serde(default)]
+   pub steal: ActionRule,
+   #[serde(default)]
+   pub rug: ActionRule,
+   /// Added to the hit cost for every earlier hit of the player on the same target this round
+   pub hit_cost_step: Uint128,
+}
+
+impl ActionRules {
+   pub fn rule(&self, msg: &PlayMsg) -> &ActionRule {
+      match msg {
+         PlayMsg::Keep {} => &self.keep,
+         PlayMsg::Hit { .. } => &self.hit,
+         PlayMsg::Help { .. } => &self.help,
+         PlayMsg::Shield {} => &self.shield,
+         PlayMsg::Steal { .. } => &self.steal,
+         PlayMsg::Rug {} => &self.rug,
+      }
+   }
+}
+
+/// Players group in teams before the round starts, the points of a team are the sum of its
+/// members' and the prize of the best team is split by contribution. Only team members play and
+/// they never hit their teammates.
+#[cw_serde]
+pub struct TeamRules {
+   pub max_members: u32,
+   pub teammate_help: TeammateHelp,
+}
+
+/// Points of a `PlayMsg::Help` between teammates
+#[cw_serde]
+pub enum TeammateHelp {
+   Allowed,
+   /// Both sides get `share` of the help points
+   Discounted {
+      share: Decimal,
+   },
+   Forbidden,
+}
+
+impl TeammateHelp {
+   pub fn points(&self, points: i64) -> Result<i64, ContractError> {
+      match self {
+         TeammateHelp::Allowed => Ok(points),
+         TeammateHelp::Discounted { share } => {
+            let discounted = Uint128::from(points.unsigned_abs()).mul_floor(*share).u128() as i64;
+            Ok(if points < 0 { -discounted } else { discounted })
+         }
+         TeammateHelp::Forbidden => Err(ContractError::Invalid("teammate_help".to_string())),
+      }
+   }
+}
+
+/// Limits on `PlayMsg::Help` against accounts pumping each other's points, each one is off when
+/// `None`
+#[cw_serde]
+#[derive(Default)]
+pub struct HelpGate {
+   /// Helps allowed between the same two accounts in a round, both directions counted
+   pub max_pair_helps: Option<u32>,
+   /// Helps an account can receive in each window
+   pub help_rate_limit: Option<HelpRateLimit>,
+   /// Seconds since the first join of the helper in this contract
+   pub min_account_age: Option<u64>,
+   /// Rounds the helper must have joined before the current one
+   pub min_prior_rounds: Option<u32>,
+}
+
+#[cw_serde]
+pub struct HelpRateLimit {
+   pub max_helps: u32,
+   pub window_seconds: u64,
+}
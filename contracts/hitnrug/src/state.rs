@@ -1,17 +1,29 @@
 use std::vec;
 
 use cosmwasm_std::{
-   coins, ensure, wasm_execute, Addr, Api, BankMsg, Binary, Event, QuerierWrapper, Response,
-   Storage, Timestamp, WasmMsg,
+   coins, ensure, wasm_execute, Addr, Api, BankMsg, Decimal, Empty, Event, Order, QuerierWrapper,
+   Response, StdResult, Storage, Timestamp, Uint128, WasmMsg,
 };
-use cw_storage_plus::{Item, Map};
-use wenruji_rs::{calculate_fee_distribution, to_addr};
+use cw_storage_plus::{Bound, Item, Map};
+use wenruji_rs::{calculate_fee_distribution, to_addr, DecayGameError, PlayerStatsSM};
 
-use crate::{config::Config, game::GameSM, ContractError};
+use crate::{
+   config::Config,
+   game::{Consolation, GameSM, Rank, RoundSummary, Team},
+   msg::PlayMsg,
+   ContractError,
+};
 
 pub const GAME_SM: GameSM = GameSM::new();
 pub static IDX: Item<u64> = Item::new("game_idx");
-pub const SNAPSHOT: Map<u64, Binary> = Map::new("snap");
+/// Last `Config::round_retention` rounds keyed by round index
+pub const ROUNDS: Map<u64, RoundSummary> = Map::new("rounds");
+/// Archived rounds won by an account
+pub const ROUNDS_WON: Map<(&Addr, u64), Empty> = Map::new("rounds/w");
+/// Archived rounds played by an account
+pub const ROUNDS_PLAYED: Map<(&Addr, u64), Empty> = Map::new("rounds/p");
+/// Lifetime statistics of every account
+pub const PLAYER_STATS: PlayerStatsSM = PlayerStatsSM::new();
 
 pub fn execute_ref(
    api: &dyn Api,
@@ -28,7 +40,7 @@ pub fn execute_ref(
    if !ambassador.is_empty() {
       let ambassador_addr = to_addr(ambassador.clone(), api)?;
       GAME_SM.increase_ref(storage, &ambassador_addr)?;
-      return Ok((Some(ambassador), None));
+      Ok((Some(ambassador), None))
    } else {
       if let Some(ref_code) = ref_code {
          // Query the referral contract to get the referrer address
@@ -46,71 +58,271 @@ pub fn execute_ref(
          )?;
          return Ok((Some(ambassador.clone()), Some(msg)));
       }
-      return Ok((None, None));
+      Ok((None, None))
+   }
+}
+
+/// Rounds that `account` joined before the current one, whatever the round retention
+pub fn prior_rounds(storage: &dyn Storage, account: &Addr) -> Result<u32, ContractError> {
+   let joined = PLAYER_STATS.load(storage, account)?.rounds_joined;
+   Ok(joined.saturating_sub(1).try_into().unwrap_or(u32::MAX))
+}
+
+/// Archives `summary` as round `idx` and drops the rounds past `retention`
+pub fn archive_round(
+   storage: &mut dyn Storage,
+   idx: u64,
+   summary: &RoundSummary,
+   retention: u64,
+) -> StdResult<()> {
+   ROUNDS.save(storage, idx, summary)?;
+   for player in &summary.players {
+      ROUNDS_PLAYED.save(storage, (&player.address, idx), &Empty {})?;
+   }
+   if let Some((winner, _)) = &summary.winner {
+      ROUNDS_WON.save(storage, (winner, idx), &Empty {})?;
+   }
+
+   let expired = ROUNDS
+      .range(storage, None, Some(Bound::inclusive(idx.saturating_sub(retention))), Order::Ascending)
+      .collect::<StdResult<Vec<_>>>()?;
+   for (idx, summary) in expired {
+      ROUNDS.remove(storage, idx);
+      for player in &summary.players {
+         ROUNDS_PLAYED.remove(storage, (&player.address, idx));
+      }
+      if let Some((winner, _)) = &summary.winner {
+         ROUNDS_WON.remove(storage, (winner, idx));
+      }
    }
+   Ok(())
+}
+
+pub fn execute_play(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   account: &Addr,
+   msg: PlayMsg,
+   paid: Uint128,
+) -> Result<Option<(Uint128, Decimal)>, ContractError> {
+   if let (PlayMsg::Help { .. }, Some(min_rounds)) = (&msg, config.help_gate.min_prior_rounds) {
+      ensure!(
+         prior_rounds(storage, account)? >= min_rounds,
+         ContractError::Invalid("prior_rounds".to_string())
+      );
+   }
+   let rug = GAME_SM.play(storage, msg.clone(), account, config, now, paid)?;
+   if let PlayMsg::Hit { target } = msg {
+      PLAYER_STATS.record_hit(storage, account, &target)?;
+   }
+   if let Some((amount, decay_snap)) = rug {
+      PLAYER_STATS.record_exit(storage, account, amount, decay_snap)?;
+   }
+   Ok(rug)
+}
+
+/// Adds `account` to team `name` before the round starts, creating the team when `create` is set
+pub fn execute_team(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   account: &Addr,
+   name: &str,
+   create: bool,
+) -> Result<Team, ContractError> {
+   let rules = config.teams.as_ref().ok_or(ContractError::Invalid("team_mode".to_string()))?;
+   ensure!(GAME_SM.has_joined(storage, account)?, ContractError::Invalid("not_joined".to_string()));
+   ensure!(!GAME_SM.is_started(storage, now)?, ContractError::Invalid("join_window".to_string()));
+   GAME_SM.add_to_team(storage, account, name, rules, create)
 }
 
 pub fn execute_restart(
    storage: &mut dyn Storage,
    now: Timestamp,
-   duration: u64,
-   delay: u64,
+   config: &Config,
 ) -> Result<u64, ContractError> {
    ensure!(GAME_SM.is_completed(storage, now)?, ContractError::GameNotEnded {});
-   let snap = GAME_SM.get_snap(storage)?;
+   let summary = GAME_SM.get_summary(storage)?;
    let idx = IDX.load(storage)?;
-   if idx.gt(&10u64) {
-      let remove_idx = idx - 10u64;
-      SNAPSHOT.remove(storage, remove_idx);
-      SNAPSHOT.save(storage, idx, &snap)?;
-   } else {
-      SNAPSHOT.save(storage, idx, &snap)?;
-   }
-   let start = now.plus_seconds(delay);
-   GAME_SM.restart(storage, start, start.plus_seconds(duration))?;
+   archive_round(storage, idx, &summary, config.round_retention)?;
+   let start = now.plus_seconds(config.game_delay_sec);
+   GAME_SM.restart(
+      storage,
+      start,
+      start.plus_seconds(config.duration_seconds),
+      config.decay_curve.clone(),
+   )?;
    IDX.save(storage, &(idx + 1u64))?;
    Ok(idx + 1u64)
 }
 
+/// Settles a round that is over and starts the next one, `None` while the round is live.
+/// Rounds nobody joined or already ended are only restarted.
+pub fn execute_rollover(
+   storage: &mut dyn Storage,
+   now: Timestamp,
+   config: &Config,
+   settler: &Addr,
+) -> Result<Option<Response>, ContractError> {
+   if !GAME_SM.is_ended(storage, now)? {
+      return Ok(None);
+   }
+   let response = match execute_endgame(storage, now, config, settler) {
+      Ok(response) => response,
+      Err(ContractError::DecayGameError(DecayGameError::NoRewards {})) => Response::new(),
+      Err(err) => return Err(err),
+   };
+   let idx = execute_restart(storage, now, config)?;
+   Ok(Some(
+      response.add_event(
+         Event::new("hitnrug/rollover")
+            .add_attribute("settler", settler)
+            .add_attribute("game_idx", idx.to_string()),
+      ),
+   ))
+}
+
 pub fn execute_endgame(
    storage: &mut dyn Storage,
    now: Timestamp,
    config: &Config,
+   settler: &Addr,
 ) -> Result<Response, ContractError> {
    let mut response = Response::new();
-   let (curr, amount) = GAME_SM.endgame(storage, now)?;
+   let (_, pot) = GAME_SM.endgame(storage, now)?;
 
-   let (winner, points) = curr.unwrap(); //safe unwrap endgame if one participant exist the winner exist otherwise endgame throw error norewards
+   // the settlement fee comes off the top of the pot
+   let settlement = pot.mul_floor(config.settlement_fee);
+   if !settlement.is_zero() {
+      response = response.add_message(BankMsg::Send {
+         to_address: settler.to_string(),
+         amount: coins(settlement.u128(), config.ticket_denom.clone()),
+      });
+   }
+   let ranking = GAME_SM.get_ranking(storage)?;
+   // in team mode the best team shares the prize by contribution
+   let winning_team = match config.teams {
+      Some(_) => GAME_SM.game_base.load(storage)?.winning_team,
+      None => None,
+   };
+   let prizes = match &winning_team {
+      Some((name, _)) => GAME_SM.team_shares(storage, name)?,
+      None => config.prize_table.split(&ranking),
+   };
+   ensure!(!prizes.is_empty(), ContractError::Invalid("no_winner".to_string()));
 
-   let ref_weights = GAME_SM.get_ref_weights(storage)?;
+   // accounts left in play without a prize forfeit their stake, a share of it is paid back to
+   // them by points
+   let winners: Vec<Addr> = prizes.iter().map(|(addr, _)| addr.clone()).collect();
+   let forfeited = GAME_SM.forfeit(storage, now, &winners)?;
+   let consolation = consolation_split(pot - settlement, config, &forfeited, &ranking);
+   GAME_SM.consolation.save(storage, &consolation)?;
+   let amount = pot - settlement - consolation.pool;
 
-   let mut fees = vec![
-      (winner.clone(), config.winner_share),
-      (config.fees.fee_platform.address.clone(), config.fees.fee_platform.bp),
-   ];
+   let ref_weights = GAME_SM.get_ref_weights(storage)?;
 
+   let mut fees: Vec<(Addr, Decimal)> =
+      prizes.iter().map(|(addr, share)| (addr.clone(), config.winner_share * share)).collect();
+   fees.push((config.fees.fee_platform.address.clone(), config.fees.fee_platform.bp));
    if !ref_weights.is_empty() {
       fees.push((config.fees.fee_ref.address.clone(), config.fees.fee_ref.bp));
    }
 
-   let fee_split =
-      calculate_fee_distribution(coins(amount.into(), config.ticket_denom.clone()), &fees);
+   // same rounding as the fee split
+   let total_weight: Decimal = fees.iter().map(|(_, weight)| weight).sum();
+   let ranking: Vec<Rank> = ranking
+      .into_iter()
+      .map(|(address, points)| {
+         let prize = fees[..prizes.len()]
+            .iter()
+            .find(|(addr, _)| addr == address)
+            .map_or(Uint128::zero(), |(_, weight)| amount.mul_floor(weight / total_weight));
+         Rank { address, points, prize }
+      })
+      .collect();
+   // in team mode the round goes to the best placed member of the winning team
+   let winner = match &winning_team {
+      Some(_) => ranking.iter().find(|rank| winners.contains(&rank.address)),
+      None => ranking.first(),
+   }
+   .ok_or(ContractError::Invalid("no_winner".to_string()))?;
+   GAME_SM.save_ranking(storage, &ranking, winner)?;
+   match &winning_team {
+      Some(_) => {
+         for member in &winners {
+            PLAYER_STATS.record_win(storage, member)?;
+         }
+      }
+      None => PLAYER_STATS.record_win(storage, &winner.address)?,
+   }
+   for rank in ranking.iter().filter(|rank| !rank.prize.is_zero()) {
+      PLAYER_STATS.record_prize(
+         storage,
+         &rank.address,
+         coins(rank.prize.u128(), config.ticket_denom.clone()),
+      )?;
+   }
 
-   response = response.add_messages(vec![
-      BankMsg::Send { to_address: fee_split[0].0.to_string(), amount: fee_split[0].1.clone() },
-      BankMsg::Send { to_address: fee_split[1].0.to_string(), amount: fee_split[1].1.clone() },
-   ]);
+   for (addr, payout) in &consolation.payouts {
+      PLAYER_STATS.record_prize(
+         storage,
+         addr,
+         coins(payout.u128(), config.ticket_denom.clone()),
+      )?;
+   }
 
-   if !ref_weights.is_empty() {
-      response = response.add_message(wasm_execute(
-         config.fees.fee_ref.address.clone(),
-         &referral::ExecuteMsg::DistributeRewards { referers: ref_weights },
-         fee_split[2].1.clone(),
-      )?);
+   let mut fee_split =
+      calculate_fee_distribution(coins(amount.into(), config.ticket_denom.clone()), &fees);
+   fee_split.extend(
+      consolation
+         .payouts
+         .iter()
+         .map(|(addr, payout)| (addr.clone(), coins(payout.u128(), config.ticket_denom.clone()))),
+   );
+
+   for (addr, amount) in fee_split {
+      if !ref_weights.is_empty() && addr == config.fees.fee_ref.address {
+         response = response.add_message(wasm_execute(
+            config.fees.fee_ref.address.clone(),
+            &referral::ExecuteMsg::DistributeRewards { referers: ref_weights.clone() },
+            amount,
+         )?);
+      } else {
+         response = response.add_message(BankMsg::Send { to_address: addr.to_string(), amount });
+      }
    }
 
    Ok(response
       .add_event(Event::new("hitnrug/endgame"))
-      .add_attribute("winner", winner)
-      .add_attribute("points", points.to_string()))
+      .add_attribute("winner", winner.address.as_str())
+      .add_attribute("points", winner.points.to_string())
+      .add_attribute("prizes", prizes.len().to_string())
+      .add_attribute("forfeited", consolation.forfeited)
+      .add_attribute("consolation", consolation.pool)
+      .add_attributes(winning_team.map(|(name, _)| ("team", name))))
+}
+
+/// Splits `config.consolation_share` of the forfeited stakes among the forfeiting accounts with
+/// positive points, pro rata to their points
+fn consolation_split(
+   available: Uint128,
+   config: &Config,
+   forfeited: &[(Addr, Uint128)],
+   ranking: &[(Addr, i64)],
+) -> Consolation {
+   let total: Uint128 = forfeited.iter().map(|(_, stake)| stake).sum();
+   let share = total.mul_floor(config.consolation_share).min(available);
+   let points: Vec<(&Addr, u64)> = ranking
+      .iter()
+      .filter(|(addr, points)| *points > 0 && forfeited.iter().any(|(account, _)| account == addr))
+      .map(|(addr, points)| (addr, points.unsigned_abs()))
+      .collect();
+   let total_points: u64 = points.iter().map(|(_, points)| points).sum();
+   let payouts: Vec<(Addr, Uint128)> = points
+      .into_iter()
+      .map(|(addr, points)| (addr.clone(), share.multiply_ratio(points, total_points)))
+      .filter(|(_, payout)| !payout.is_zero())
+      .collect();
+   Consolation { forfeited: total, pool: payouts.iter().map(|(_, payout)| payout).sum(), payouts }
 }
@@ -0,0 +1,295 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+   coin, coins, to_json_binary, Addr, Decimal, Order, StdResult, Timestamp, Uint128,
+};
+use cw2::{get_contract_version, set_contract_version};
+use wenruji_rs::{AccountStatus, DecayCurve, DecayGameAccount};
+
+use crate::{
+   config::Config,
+   game::PlayerStatus,
+   migrations::{ConfigV0_1, PlayerStatusV0_1, CONFIG_V0_1, PLAYERS_V0_1, SNAPSHOT_V0_1},
+   msg::{HelpGate, PlayMsg},
+   state::{GAME_SM, ROUNDS, ROUNDS_PLAYED, ROUNDS_WON},
+};
+
+use super::{test_helpers::TestEnv, test_macros::define_test};
+
+/// DecayGame layout of the v0.1 releases, before the decay curve was stored on the game
+#[cw_serde]
+struct DecayGameV0_1 {
+   decay_starts_at: Timestamp,
+   decay_ends_at: Timestamp,
+   total: Uint128,
+   exited: Uint128,
+   rewards: Uint128,
+}
+
+#[cw_serde]
+struct GameBaseV0_1 {
+   decay_game: DecayGameV0_1,
+   current_winner: Option<(Addr, i64)>,
+}
+
+/// DecayGameAccount layout of the v0.1 releases, before partial exits and the account status
+#[cw_serde]
+struct DecayGameAccountV0_1 {
+   amount: Uint128,
+   decay_snapshot: Decimal,
+   pending: Uint128,
+}
+
+#[cw_serde]
+struct SnapshotV0_1 {
+   decay_game: DecayGameV0_1,
+   accounts: Vec<(Addr, DecayGameAccount)>,
+   current_winner: Option<(Addr, i64)>,
+   players: Vec<PlayerStatusV0_1>,
+   referrals: Vec<(Addr, Decimal)>,
+}
+
+fn to_v0_1(player: PlayerStatus, history: Vec<PlayMsg>) -> PlayerStatusV0_1 {
+   PlayerStatusV0_1 {
+      address: player.address,
+      points: player.points,
+      history,
+      last_play: player.last_play,
+   }
+}
+
+/// Rewrites the live contract state with the v0.1.0-rc1 layouts listed in RELEASES.md
+fn downgrade_to_v0_1(env: &mut TestEnv) {
+   let mut storage = env.app.contract_storage_mut(&env.contracts.game);
+
+   let config = Config::load(storage.as_ref()).unwrap();
+   CONFIG_V0_1
+      .save(
+         storage.as_mut(),
+         &ConfigV0_1 {
+            owner: config.owner,
+            ticket_denom: config.ticket_denom,
+            ticket_amount: config.ticket_amount,
+            duration_seconds: config.duration_seconds,
+            game_delay_sec: config.game_delay_sec,
+            delay_play_seconds: config.delay_play_seconds,
+            winner_share: config.winner_share,
+            fees: config.fees,
+            points: config.points,
+         },
+      )
+      .unwrap();
+
+   let game_base = GAME_SM.game_base.load(storage.as_ref()).unwrap();
+   cw_storage_plus::Item::new("gm/b")
+      .save(
+         storage.as_mut(),
+         &GameBaseV0_1 {
+            decay_game: DecayGameV0_1 {
+               decay_starts_at: game_base.decay_game.decay_starts_at,
+               decay_ends_at: game_base.decay_game.decay_ends_at,
+               total: game_base.decay_game.total,
+               exited: game_base.decay_game.exited,
+               rewards: game_base.decay_game.rewards,
+            },
+            current_winner: game_base.current_winner,
+         },
+      )
+      .unwrap();
+
+   let accounts = GAME_SM
+      .accounts
+      .range(storage.as_ref(), None, None, Order::Ascending)
+      .collect::<StdResult<Vec<_>>>()
+      .unwrap();
+   for (addr, account) in accounts {
+      cw_storage_plus::Map::new("gm/a")
+         .save(
+            storage.as_mut(),
+            &addr,
+            &DecayGameAccountV0_1 {
+               amount: account.amount,
+               decay_snapshot: account.decay_snapshot,
+               pending: account.pending,
+            },
+         )
+         .unwrap();
+   }
+
+   let rounds = ROUNDS
+      .range(storage.as_ref(), None, None, Order::Ascending)
+      .collect::<StdResult<Vec<_>>>()
+      .unwrap();
+   for (idx, round) in rounds {
+      let snap = SnapshotV0_1 {
+         decay_game: DecayGameV0_1 {
+            decay_starts_at: round.starts_at,
+            decay_ends_at: round.ends_at,
+            total: round.total,
+            exited: round.exited,
+            rewards: round.total - round.exited,
+         },
+         accounts: vec![],
+         current_winner: round.winner,
+         players: round.players.into_iter().map(|player| to_v0_1(player, vec![])).collect(),
+         referrals: round.referrals,
+      };
+      SNAPSHOT_V0_1.save(storage.as_mut(), idx, &to_json_binary(&snap).unwrap()).unwrap();
+   }
+   ROUNDS.clear(storage.as_mut());
+   ROUNDS_WON.clear(storage.as_mut());
+   ROUNDS_PLAYED.clear(storage.as_mut());
+
+   let players = GAME_SM
+      .players
+      .range(storage.as_ref(), None, None, Order::Ascending)
+      .collect::<StdResult<Vec<_>>>()
+      .unwrap();
+   for (addr, player) in players {
+      let history = GAME_SM
+         .get_history(storage.as_ref(), &addr, None, usize::MAX)
+         .unwrap()
+         .into_iter()
+         .rev()
+         .map(|(_, record)| record.msg)
+         .collect();
+      PLAYERS_V0_1.save(storage.as_mut(), &addr, &to_v0_1(player, history)).unwrap();
+   }
+   GAME_SM.history.clear(storage.as_mut());
+
+   GAME_SM.first_joins.clear(storage.as_mut());
+   GAME_SM.leaderboard.clear(storage.as_mut());
+   set_contract_version(storage.as_mut(), "hitnrug", "0.1.0-rc1").unwrap();
+}
+
+fn set_version(env: &mut TestEnv, name: &str, version: &str) {
+   let mut storage = env.app.contract_storage_mut(&env.contracts.game);
+   set_contract_version(storage.as_mut(), name, version).unwrap();
+}
+
+define_test! {
+    name: test_migrate_from_v0_1,
+    game: {
+        owner: "owner",
+        ticket_denom: "denom",
+        ticket_amount: Uint128::new(100),
+        start_at: Timestamp::from_seconds(1000),
+        duration_seconds: 600u64,
+        delay_play_seconds: 60u64,
+        fees: vec![
+            Decimal::percent(10),
+            Decimal::percent(10),
+        ],
+        pt_keep: 4i64,
+        pt_hit: -5i64,
+        pt_help: (6i64, 4i64),
+    },
+    accounts: {
+        alice: coins(200u128, "denom"),
+        bob: coins(200u128, "denom"),
+        carol: coins(200u128, "denom"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // a first round won by alice is archived
+        env.set_block(Timestamp::from_seconds(999));
+        env.join("alice", None, coins(100, "denom")).unwrap();
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+        env.set_block(Timestamp::from_seconds(1601));
+        env.endgame("anyone").unwrap();
+        env.restart("anyone").unwrap();
+
+        env.join("alice", None, coins(100, "denom")).unwrap();
+        env.join("bob", None, coins(100, "denom")).unwrap();
+        env.join("carol", None, coins(100, "denom")).unwrap();
+        env.set_block(Timestamp::from_seconds(1611));
+        env.play_keep("bob").unwrap();
+        env.exit("carol").unwrap();
+
+        downgrade_to_v0_1(env);
+
+        // the old layout cannot be read by the new code
+        env.play_keep("alice").unwrap_err();
+
+        // only the admin can migrate
+        env.migrate("alice").unwrap_err();
+        env.migrate("owner").unwrap();
+
+        let version = get_contract_version(env.app.contract_storage(&env.contracts.game).as_ref()).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        let config = env.get_config();
+        assert_eq!(config.decay_curve, DecayCurve::Linear);
+        assert_eq!(config.points.keep, 4i64);
+        assert_eq!(config.help_gate, HelpGate::default());
+
+        // live accounts are aged from the start of the round
+        let first_join = GAME_SM.first_joins.load(env.app.contract_storage(&env.contracts.game).as_ref(), &env.addr("alice")).unwrap();
+        assert_eq!(first_join, Timestamp::from_seconds(1611));
+
+        // exits of v0.1 were claimed on the spot and are stamped with the migration time
+        let now = env.app.block_info().time;
+        let carol = env.get_player("carol").unwrap().account;
+        assert_eq!(carol.status, AccountStatus::Claimed { at: now });
+        assert_eq!(env.get_player("alice").unwrap().account.status, AccountStatus::Joined);
+
+        // inline plays are dropped, the history starts over
+        let bob = env.get_player("bob").unwrap().status;
+        assert_eq!((bob.points, bob.plays), (4, 0));
+        assert!(env.player_history("bob", None, 10).is_empty());
+
+        // archived snapshots are typed summaries
+        let round = env.round(1).unwrap();
+        assert_eq!(round.winner, Some((env.addr("alice"), 4)));
+        assert_eq!(round.players.len(), 1);
+        assert_eq!(round.total, Uint128::new(100));
+        assert_eq!(env.rounds_won("alice", None), vec![1]);
+        assert_eq!(env.rounds_played("alice", None), vec![1]);
+        assert!(SNAPSHOT_V0_1.is_empty(env.app.contract_storage(&env.contracts.game).as_ref()));
+
+        // the live round keeps going after the migration
+        let snap = env.get_snap();
+        assert_eq!(snap.decay_game.total, Uint128::new(300));
+        assert_eq!(snap.decay_game.curve, DecayCurve::Linear);
+        env.verify_winner("bob");
+        assert_eq!(env.leaderboard(None, 10), vec![(env.addr("bob"), 4), (env.addr("alice"), 0), (env.addr("carol"), 0)]);
+
+        env.set_block(Timestamp::from_seconds(1911));
+        env.play_keep("bob").unwrap();
+        assert_eq!(env.player_history("bob", None, 10)[0].0, 0);
+        env.exit("alice").unwrap();
+        // 88 won in the first round and half of the second ticket back
+        env.assert_balance("alice", coin(138u128, "denom"));
+
+        // migrating again from the same version is a no-op
+        env.migrate("owner").unwrap();
+    }
+}
+
+define_test! {
+    name: test_migrate_refuses_downgrade,
+    game: {
+        owner: "owner",
+        ticket_denom: "denom",
+        ticket_amount: Uint128::new(100),
+        start_at: Timestamp::from_seconds(1000),
+        duration_seconds: 600u64,
+        delay_play_seconds: 60u64,
+        fees: vec![
+            Decimal::percent(10),
+            Decimal::percent(10),
+        ],
+        pt_keep: 4i64,
+        pt_hit: -5i64,
+        pt_help: (6i64, 4i64),
+    },
+    accounts: {
+        alice: coins(200u128, "denom"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        set_version(env, "hitnrug", "99.0.0");
+        env.migrate("owner").unwrap_err();
+
+        set_version(env, "referral", "0.1.0-rc1");
+        env.migrate("owner").unwrap_err();
+    }
+}
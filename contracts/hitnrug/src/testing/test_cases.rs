@@ -1,7 +1,15 @@
-use crate::{config::ConfigUpdate, game::GameSmSnapshot, msg::QueryMsg};
-use cosmwasm_std::{coin, coins, Decimal, Timestamp, Uint128};
-
-use super::test_macros::define_test;
+use crate::{
+   config::{ConfigUpdate, PrizeTable},
+   game::{Consolation, PlayRecord, Rank},
+   msg::{ActionRule, ActionRules, HelpGate, HelpRateLimit, PlayMsg, TeamRules, TeammateHelp},
+};
+use cosmwasm_std::{coin, coins, Addr, Decimal, Event, Timestamp, Uint128};
+use wenruji_rs::{
+   keeper::{KeeperMsg, NextAction},
+   AccountStatus, DecayCurve,
+};
+
+use super::{test_helpers::assert_gated, test_macros::define_test};
 
 define_test! {
     name: test_lifecycle,
@@ -77,17 +85,22 @@ define_test! {
         // assert_eq!(referral_balances, coins(30, "denom")); // Referral contract receives 10% of pot
 
         // Save the Game Snapshot
-        let game_snap = env.get_snap(None);
+        let game_snap = env.get_snap();
 
         // **Game Restart**
         // Restart the game to reset for a new round
 
         env.restart("anyone").unwrap(); // Game restarts for the next round
 
-        // **Verify Snapshots**
-        let game_snap_old = env.get_snap(Some(1));
-        assert_eq!(game_snap, game_snap_old);
-        assert_ne!(game_snap, env.get_snap(None))
+        // **Verify Summaries**
+        let round = env.round(1).unwrap();
+        assert_eq!(round.winner, game_snap.current_winner);
+        assert_eq!(round.ranking, game_snap.ranking);
+        assert_eq!(round.players, game_snap.players);
+        assert_eq!(round.referrals, game_snap.referrals);
+        assert_eq!(round.total, game_snap.decay_game.total);
+        assert_eq!(round.ends_at, game_snap.decay_game.decay_ends_at);
+        assert_ne!(game_snap, env.get_snap())
 
     }
 }
@@ -349,11 +362,10 @@ define_test! {
         // Verify state reset - no players in the game
         env.join("alice", None, coins(100, "denom")).unwrap();
 
-        // Verify Snap 1 got erased and snap 11 exist
-        env.get_snap(Some(11));
-        env.app.wrap()
-        .query_wasm_smart::<GameSmSnapshot>(env.contracts.game.clone(), &QueryMsg::GameStatus { idx: Some(1) })
-        .unwrap_err()
+        // Verify round 1 got erased and round 11 exist
+        env.round(11).unwrap();
+        assert_eq!(env.round(1), None);
+        assert_eq!(env.rounds_played("alice", None).len(), 10);
     }
 }
 
@@ -423,7 +435,17 @@ define_test! {
             delay_play_seconds: None,
             game_delay_sec: None,
             fees: None,
-            points: None
+            points: None,
+            decay_curve: Some(DecayCurve::Exponential { half_life_seconds: 60 }),
+            help_gate: None,
+            prize_table: None,
+            settlement_fee: None,
+            round_retention: None,
+            max_ticket_amount: None,
+            stake_weighted_points: None,
+            consolation_share: None,
+            action_rules: None,
+            teams: None,
         };
 
         env.update_config("owner", new_config.clone()).unwrap_err(); //error the game should be finished
@@ -437,5 +459,904 @@ define_test! {
         let config = env.get_config();
         assert_ne!(old_config, config);
         assert_eq!(new_config.owner.unwrap(), config.owner);
+        assert_eq!(new_config.decay_curve.unwrap(), config.decay_curve);
+
+        // the next round uses the updated curve
+        env.restart("anyone").unwrap();
+        let snap = env.get_snap();
+        assert_eq!(snap.decay_game.curve, DecayCurve::Exponential { half_life_seconds: 60 });
+    }
+}
+
+define_test! {
+    name: test_paginated_queries,
+    accounts: {
+        alice: coins(200u128, "denom"),
+        bob: coins(200u128, "denom"),
+        charlie: coins(200u128, "denom"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.set_block(Timestamp::from_seconds(999));
+        env.join("alice", None, coins(100, "denom")).unwrap();
+        env.join("bob", None, coins(100, "denom")).unwrap();
+        env.join("charlie", None, coins(100, "denom")).unwrap();
+
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+
+        // single player lookups
+        let alice = env.get_player("alice").unwrap();
+        assert_eq!(alice.account.amount, Uint128::new(100));
+        assert_eq!(alice.status.points, 4i64);
+        assert_eq!(alice.status.plays, 1);
+        assert!(env.get_player("owner").is_none());
+
+        // walking the pages returns every player exactly once
+        let first = env.get_snap_page(None, 2);
+        assert_eq!(first.accounts.len(), 2);
+        assert_eq!(first.players.len(), 2);
+        let second = env.get_snap_page(Some(first.accounts[1].0.clone()), 2);
+        assert_eq!(second.accounts.len(), 1);
+        assert_eq!(second.players.len(), 1);
+
+        let mut paged: Vec<_> = first.accounts.into_iter().chain(second.accounts).collect();
+        paged.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(paged, env.get_snap().accounts);
+    }
+}
+
+define_test! {
+    name: test_help_pair_limit,
+    game: {
+        help_gate: Some(HelpGate { max_pair_helps: Some(2), ..HelpGate::default() }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie"]);
+
+        // alice and bob pump each other
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_help("bob", "alice").unwrap();
+        env.play_help("alice", "bob").unwrap();
+        env.set_block(Timestamp::from_seconds(1060));
+        assert_gated(env.play_help("bob", "alice"), "help_pair_limit");
+        assert_gated(env.play_help("alice", "bob"), "help_pair_limit");
+
+        // other pairs and actions are not affected
+        env.play_help("charlie", "alice").unwrap();
+        env.play_hit("bob", "alice").unwrap();
+
+        // the limit resets with the round
+        env.next_round(1601, &["alice", "bob"]);
+        env.play_help("bob", "alice").unwrap();
+    }
+}
+
+define_test! {
+    name: test_help_rate_limit,
+    game: {
+        help_gate: Some(HelpGate {
+            help_rate_limit: Some(HelpRateLimit { max_helps: 2, window_seconds: 300 }),
+            ..HelpGate::default()
+        }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie", "dave"]);
+
+        // three accounts funnel points into alice
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_help("bob", "alice").unwrap();
+        env.play_help("charlie", "alice").unwrap();
+        assert_gated(env.play_help("dave", "alice"), "help_rate_limit");
+        env.play_help("dave", "bob").unwrap();
+
+        env.set_block(Timestamp::from_seconds(1299));
+        assert_gated(env.play_help("bob", "alice"), "help_rate_limit");
+
+        // a new window opens
+        env.set_block(Timestamp::from_seconds(1300));
+        env.play_help("bob", "alice").unwrap();
+        env.play_help("charlie", "alice").unwrap();
+        assert_gated(env.play_help("dave", "alice"), "help_rate_limit");
+    }
+}
+
+define_test! {
+    name: test_help_account_age,
+    game: {
+        help_gate: Some(HelpGate { min_account_age: Some(3600), ..HelpGate::default() }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+
+        // fresh accounts cannot help
+        env.set_block(Timestamp::from_seconds(1000));
+        assert_gated(env.play_help("bob", "alice"), "account_age");
+        env.play_keep("bob").unwrap();
+        env.play_hit("alice", "bob").unwrap();
+
+        // bob keeps its first join across rounds, erin is a new address
+        env.next_round(4590, &["alice", "bob", "erin"]);
+        env.play_help("bob", "alice").unwrap();
+        assert_gated(env.play_help("erin", "alice"), "account_age");
+    }
+}
+
+define_test! {
+    name: test_help_prior_rounds,
+    game: {
+        help_gate: Some(HelpGate { min_prior_rounds: Some(2), ..HelpGate::default() }),
+        round_retention: Some(1),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+
+        env.set_block(Timestamp::from_seconds(1000));
+        assert_gated(env.play_help("bob", "alice"), "prior_rounds");
+        env.play_keep("bob").unwrap();
+
+        env.next_round(1601, &["alice", "bob", "erin"]);
+        assert_gated(env.play_help("bob", "alice"), "prior_rounds");
+        env.play_keep("bob").unwrap();
+
+        // rounds pruned from the archive still count
+        env.next_round(2212, &["alice", "bob", "erin"]);
+        assert_eq!(env.round(1), None);
+        env.play_help("bob", "alice").unwrap();
+        assert_gated(env.play_help("erin", "alice"), "prior_rounds");
+        env.play_keep("erin").unwrap();
+    }
+}
+
+define_test! {
+    name: test_prize_table,
+    game: {
+        prize_table: Some(PrizeTable::Top {
+            shares: vec![Decimal::percent(60), Decimal::percent(25), Decimal::percent(15)],
+        }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie", "dave"]);
+
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_help("charlie", "dave").unwrap(); // charlie 6, dave 4
+        env.play_keep("bob").unwrap(); // bob 4
+        env.set_block(Timestamp::from_seconds(1010));
+        env.play_keep("alice").unwrap(); // alice 4
+
+        env.set_block(Timestamp::from_seconds(1601));
+        assert!(env.get_snap().ranking.is_empty());
+        env.endgame("anyone").unwrap();
+
+        // ties on 4 points go to the lowest address
+        assert!(env.addr("alice") < env.addr("bob") && env.addr("bob") < env.addr("dave"));
+        let rank = |env: &TestEnv, account: &str, points: i64, prize: u128| Rank {
+            address: env.addr(account),
+            points,
+            prize: Uint128::new(prize),
+        };
+        // 400 pot, the 80% winner share and the 10% platform fee are split over 90%
+        let ranking = vec![
+            rank(env, "charlie", 6, 213),
+            rank(env, "alice", 4, 88),
+            rank(env, "bob", 4, 53),
+            rank(env, "dave", 4, 0),
+        ];
+        let snap = env.get_snap();
+        assert_eq!(snap.ranking, ranking);
+        assert_eq!(snap.current_winner, Some((env.addr("charlie"), 6)));
+
+        env.assert_balance("charlie", coin(1113u128, "denom"));
+        env.assert_balance("alice", coin(988u128, "denom"));
+        env.assert_balance("bob", coin(953u128, "denom"));
+        env.assert_balance("dave", coin(900u128, "denom"));
+        env.assert_balance("owner", coin(44u128, "denom"));
+
+        // the ranking is archived with the round
+        env.restart("anyone").unwrap();
+        assert_eq!(env.round(1).unwrap().ranking, ranking);
+        assert!(env.get_snap().ranking.is_empty());
+    }
+}
+
+define_test! {
+    name: test_leaderboard,
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie", "dave"]);
+
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap(); // alice 4
+        env.play_help("bob", "charlie").unwrap(); // bob 6, charlie 4
+        env.play_keep("dave").unwrap(); // dave 4
+        env.verify_winner("bob");
+        assert_eq!(env.leaderboard(None, 1), vec![(env.addr("bob"), 6)]);
+
+        // hitting the leader hands the lead to the lowest address on 4 points
+        env.set_block(Timestamp::from_seconds(1060));
+        env.play_hit("alice", "bob").unwrap(); // bob 1
+        let mut tied: Vec<Addr> = ["alice", "charlie", "dave"].map(|a| env.addr(a)).into();
+        tied.sort();
+        let snap = env.get_snap();
+        assert_eq!(snap.current_winner, Some((tied[0].clone(), 4)));
+
+        // pages walk down the points in the same order, ties by address
+        let mut expected: Vec<(Addr, i64)> = tied.iter().map(|a| (a.clone(), 4)).collect();
+        expected.push((env.addr("bob"), 1));
+        let first = env.leaderboard(None, 2);
+        assert_eq!(first, expected[..2]);
+        let second = env.leaderboard(first.last().cloned(), 2);
+        assert_eq!(second, expected[2..]);
+        assert!(env.leaderboard(second.last().cloned(), 2).is_empty());
+
+        // the next round starts with an empty board
+        env.next_round(1601, &["erin"]);
+        assert_eq!(env.leaderboard(None, 10), vec![(env.addr("erin"), 0)]);
+    }
+}
+
+define_test! {
+    name: test_lazy_rollover,
+    game: {
+        settlement_fee: Some(Decimal::percent(5)),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+
+        // the first join after the end settles the round and joins the next one
+        env.set_block(Timestamp::from_seconds(1601));
+        env.join("charlie", None, coins(100, "denom")).unwrap();
+        assert_eq!(env.game_index(), 2);
+        assert_eq!(env.round(1).unwrap().ranking[0].address, env.addr("alice"));
+        // 5% of the 200 pot to charlie, the 190 left is split over the 90% of fees without referrals
+        env.assert_balance("charlie", coin(910u128, "denom"));
+        env.assert_balance("alice", coin(1068u128, "denom"));
+        env.assert_balance("owner", coin(21u128, "denom"));
+        let snap = env.get_snap();
+        assert_eq!(snap.accounts.len(), 1);
+        assert_eq!(snap.decay_game.decay_starts_at, Timestamp::from_seconds(1611));
+
+        // exiting a finished round settles it without exiting
+        env.set_block(Timestamp::from_seconds(2212));
+        env.exit("charlie").unwrap();
+        assert_eq!(env.game_index(), 3);
+        env.assert_balance("charlie", coin(999u128, "denom"));
+
+        // an empty round is only restarted
+        env.set_block(Timestamp::from_seconds(2900));
+        env.join("alice", None, coins(100, "denom")).unwrap();
+        assert_eq!(env.game_index(), 4);
+        assert_eq!(env.get_snap().accounts.len(), 1);
+
+        // live rounds are not settled
+        env.join("bob", None, coins(100, "denom")).unwrap();
+        assert_eq!(env.game_index(), 4);
+    }
+}
+
+define_test! {
+    name: test_consolation,
+    game: {
+        consolation_share: Some(Decimal::percent(50)),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie", "dave"]);
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+        env.play_keep("bob").unwrap();
+        env.play_keep("charlie").unwrap();
+        env.set_block(Timestamp::from_seconds(1061));
+        env.play_keep("alice").unwrap();
+        env.set_block(Timestamp::from_seconds(1300));
+        env.exit("dave").unwrap();
+        assert_eq!(env.consolation(), Consolation::default());
+
+        env.set_block(Timestamp::from_seconds(1601));
+        env.endgame("anyone").unwrap();
+
+        // bob and charlie forfeit 200, half of it is split on their points
+        let consolation = env.consolation();
+        assert_eq!(consolation.forfeited, Uint128::new(200));
+        assert_eq!(consolation.pool, Uint128::new(100));
+        assert_eq!(
+            consolation.payouts,
+            vec![(env.addr("charlie"), Uint128::new(50)), (env.addr("bob"), Uint128::new(50))]
+        );
+        env.assert_balance("bob", coin(950u128, "denom"));
+        env.assert_balance("charlie", coin(950u128, "denom"));
+        // the 250 left is split over the 90% of fees without referrals
+        env.assert_balance("alice", coin(1122u128, "denom"));
+        env.assert_balance("owner", coin(27u128, "denom"));
+        assert_eq!(env.player_stats("bob").prizes, coins(50, "denom"));
+
+        let forfeited = AccountStatus::Forfeited { at: Timestamp::from_seconds(1601) };
+        assert_eq!(env.get_player("bob").unwrap().account.status, forfeited);
+        assert_eq!(env.get_player("alice").unwrap().account.status, AccountStatus::Joined);
+        assert_eq!(
+            env.get_player("dave").unwrap().account.status,
+            AccountStatus::Claimed { at: Timestamp::from_seconds(1300) }
+        );
+
+        // the payouts are archived with the round
+        env.restart("anyone").unwrap();
+        assert_eq!(env.round(1).unwrap().consolation, consolation);
+        assert_eq!(env.consolation(), Consolation::default());
+    }
+}
+
+define_test! {
+    name: test_settle,
+    game: {
+        settlement_fee: Some(Decimal::percent(5)),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        assert_eq!(
+            env.next_action(),
+            NextAction { at: Timestamp::from_seconds(1601), msg: KeeperMsg::Settle {} }
+        );
+
+        // a live round is left alone
+        env.set_block(Timestamp::from_seconds(1600));
+        env.settle("dave").unwrap();
+        assert_eq!(env.game_index(), 1);
+
+        // anyone settles a finished round and takes the fee
+        env.set_block(Timestamp::from_seconds(1601));
+        env.settle("dave").unwrap();
+        assert_eq!(env.game_index(), 2);
+        env.assert_balance("dave", coin(1010u128, "denom"));
+        assert_eq!(env.next_action().at, Timestamp::from_seconds(2212));
+
+        // settling again is a no-op
+        env.settle("erin").unwrap();
+        assert_eq!(env.game_index(), 2);
+        env.assert_balance("erin", coin(1000u128, "denom"));
+    }
+}
+
+define_test! {
+    name: test_round_history,
+    game: {
+        round_retention: Some(2),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+
+        env.next_round(1601, &["alice", "bob"]);
+        env.play_keep("bob").unwrap();
+        env.next_round(2212, &["alice"]);
+        env.play_keep("alice").unwrap();
+        env.next_round(2823, &[]);
+
+        // only the last 2 rounds are kept
+        assert_eq!(env.round(1), None);
+        let history = env.round_history(None, 10);
+        assert_eq!(history.iter().map(|(idx, _)| *idx).collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(env.round_history(Some(3), 1)[0].0, 2);
+
+        let round = env.round(2).unwrap();
+        assert_eq!(round.winner, Some((env.addr("bob"), 4)));
+        assert_eq!(round.ranking[0].address, env.addr("bob"));
+        // 80% of the 200 pot over the 90% of fees without referrals
+        assert_eq!(round.ranking[0].prize, Uint128::new(177));
+        assert_eq!(round.players.len(), 2);
+        assert_eq!(round.total, Uint128::new(200));
+        assert_eq!(round.starts_at, Timestamp::from_seconds(1611));
+
+        // per account indexes follow the retention
+        assert_eq!(env.rounds_won("alice", None), vec![3]);
+        assert_eq!(env.rounds_won("bob", None), vec![2]);
+        assert_eq!(env.rounds_played("alice", None), vec![3, 2]);
+        assert_eq!(env.rounds_played("alice", Some(3)), vec![2]);
+        assert_eq!(env.rounds_played("bob", None), vec![2]);
+    }
+}
+
+define_test! {
+    name: test_player_stats,
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie"]);
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+        env.play_keep("bob").unwrap();
+        env.play_hit("charlie", "bob").unwrap();
+        env.set_block(Timestamp::from_seconds(1300));
+        env.exit("charlie").unwrap();
+
+        env.next_round(1601, &["alice"]);
+        env.set_block(Timestamp::from_seconds(1911));
+        env.exit("alice").unwrap();
+
+        let alice = env.player_stats("alice");
+        assert_eq!((alice.rounds_joined, alice.rounds_exited, alice.wins), (2, 1, 1));
+        assert_eq!(alice.avg_exit_decay, Decimal::percent(50));
+        assert_eq!(alice.paid_in, Uint128::new(200));
+        assert_eq!(alice.paid_out, Uint128::new(50));
+        // 80% of the 250 pot over the 90% of fees without referrals
+        assert_eq!(alice.prizes, coins(222, "denom"));
+
+        let bob = env.player_stats("bob");
+        assert_eq!((bob.rounds_joined, bob.wins, bob.hits_received), (1, 0, 1));
+        assert!(bob.prizes.is_empty());
+
+        let charlie = env.player_stats("charlie");
+        assert_eq!((charlie.rounds_exited, charlie.hits_given), (1, 1));
+        assert_eq!(charlie.paid_out, Uint128::new(50));
+
+        // unknown accounts have empty stats
+        assert_eq!(env.player_stats("dave").rounds_joined, 0);
+    }
+}
+
+define_test! {
+    name: test_variable_stakes,
+    game: {
+        max_ticket_amount: Some(Uint128::new(300)),
+        stake_weighted_points: Some(true),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+
+        // stakes within the ticket range
+        env.join("erin", None, coins(50, "denom")).unwrap_err();
+        env.join("erin", None, coins(301, "denom")).unwrap_err();
+        env.join("charlie", None, coins(250, "denom")).unwrap();
+        env.assert_pot();
+
+        // top-ups before the decay starts, up to the largest stake
+        env.top_up("alice", coins(100, "denom")).unwrap();
+        env.top_up("bob", coins(250, "denom")).unwrap_err();
+        env.top_up("dave", coins(100, "denom")).unwrap_err();
+        assert_eq!(env.get_player("alice").unwrap().account.amount, Uint128::new(200));
+        assert_eq!(env.player_stats("alice").paid_in, Uint128::new(200));
+        env.assert_pot();
+
+        env.set_block(Timestamp::from_seconds(1000));
+        env.top_up("bob", coins(100, "denom")).unwrap_err();
+
+        // points scale with the stake of the player
+        env.play_keep("alice").unwrap();
+        env.play_keep("charlie").unwrap();
+        env.play_hit("bob", "charlie").unwrap();
+        assert_eq!(
+            env.leaderboard(None, 10),
+            vec![(env.addr("alice"), 8), (env.addr("charlie"), 5), (env.addr("bob"), 0)]
+        );
+
+        // exits are pro-rata to the stake
+        env.set_block(Timestamp::from_seconds(1300));
+        env.exit("charlie").unwrap();
+        env.assert_balance("charlie", coin(875u128, "denom"));
+        env.assert_pot();
+        let game = env.get_snap().decay_game;
+        assert_eq!((game.total, game.exited), (Uint128::new(550), Uint128::new(125)));
+
+        // the whole pool is paid out at settlement, only the floor rounding of the split is left
+        env.set_block(Timestamp::from_seconds(1601));
+        env.endgame("anyone").unwrap();
+        let snap = env.get_snap();
+        assert_eq!(snap.decay_game.rewards, Uint128::new(425));
+        assert_eq!(env.game_balance("denom"), Uint128::one());
+        let ranking = snap.ranking;
+        assert_eq!(ranking[0].address, env.addr("alice"));
+        // 80% of the 425 pot over the 90% of fees without referrals
+        assert_eq!(ranking[0].prize, Uint128::new(377));
+    }
+}
+
+define_test! {
+    name: test_fixed_stake,
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice"]);
+        env.join("bob", None, coins(101, "denom")).unwrap_err();
+        env.top_up("alice", coins(1, "denom")).unwrap_err();
+        env.assert_pot();
+    }
+}
+
+define_test! {
+    name: test_exit_partial,
+    game: {
+        max_ticket_amount: Some(Uint128::new(200)),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        env.top_up("alice", coins(100, "denom")).unwrap();
+        env.exit_partial("alice", 40).unwrap_err();
+
+        // a quarter of the decay
+        env.set_block(Timestamp::from_seconds(1150));
+        env.exit_partial("alice", 40).unwrap();
+        env.assert_balance("alice", coin(830u128, "denom"));
+        let account = env.get_player("alice").unwrap().account;
+        assert_eq!(account.amount, Uint128::new(160));
+        assert_eq!(account.partial_exits[0].decay_snapshot, Decimal::percent(75));
+        env.assert_pot();
+
+        // the stake left can't drop below a ticket
+        assert_gated(env.exit_partial("alice", 70), "exit_amount");
+        assert_gated(env.exit_partial("bob", 40), "exit_amount");
+
+        // the rest stays in play until the full exit
+        env.exit_partial("bob", 100).unwrap_err();
+        env.set_block(Timestamp::from_seconds(1210));
+        env.play_keep("alice").unwrap();
+        env.set_block(Timestamp::from_seconds(1300));
+        env.exit("alice").unwrap();
+        env.assert_balance("alice", coin(910u128, "denom"));
+        env.exit_partial("alice", 10).unwrap_err();
+        env.assert_pot();
+
+        let stats = env.player_stats("alice");
+        assert_eq!((stats.rounds_exited, stats.paid_out), (1, Uint128::new(110)));
+        assert_eq!(stats.avg_exit_decay, Decimal::percent(50));
+    }
+}
+
+define_test! {
+    name: test_exit_partial_after_decay_end,
+    game: {
+        max_ticket_amount: Some(Uint128::new(200)),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        env.top_up("alice", coins(100, "denom")).unwrap();
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+
+        // a partial exit from a finished round settles it instead, nothing is paid back
+        env.set_block(Timestamp::from_seconds(1601));
+        env.exit_partial("alice", 100).unwrap();
+        assert_eq!(env.game_index(), 2);
+        assert_eq!(env.round(1).unwrap().ranking[0].address, env.addr("alice"));
+        assert!(env.get_player("alice").is_none());
+        assert_eq!(env.player_stats("alice").paid_out, Uint128::zero());
+        env.assert_balance("alice", coin(1066u128, "denom"));
+    }
+}
+
+define_test! {
+    name: test_action_costs,
+    game: {
+        action_rules: Some(ActionRules {
+            keep: ActionRule { cost: Uint128::new(10), cooldown_seconds: 120 },
+            hit: ActionRule { cost: Uint128::new(20), cooldown_seconds: 0 },
+            help: ActionRule { cost: Uint128::new(0), cooldown_seconds: 0 },
+            hit_cost_step: Uint128::new(10),
+            ..ActionRules::default()
+        }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        let keep = PlayMsg::Keep {};
+        let hit_bob = PlayMsg::Hit { target: env.addr("bob") };
+
+        env.set_block(Timestamp::from_seconds(1000));
+        assert_gated(env.play_keep("alice"), "action_cost");
+        assert_gated(env.play_paid("alice", keep.clone(), &coins(20, "denom")), "action_cost");
+        env.play_paid("alice", keep.clone(), &coins(10, "denom")).unwrap();
+        env.play_paid("bob", PlayMsg::Hit { target: env.addr("alice") }, &coins(20, "denom")).unwrap();
+
+        // each kind of play has its own cooldown
+        env.set_block(Timestamp::from_seconds(1061));
+        assert_gated(env.play_paid("alice", keep.clone(), &coins(10, "denom")), "action_cooldown");
+        env.play_paid("alice", hit_bob.clone(), &coins(20, "denom")).unwrap();
+
+        // hits on the same target cost more each time
+        env.set_block(Timestamp::from_seconds(1122));
+        assert_gated(env.play_paid("alice", hit_bob.clone(), &coins(20, "denom")), "action_cost");
+        env.play_paid("alice", hit_bob.clone(), &coins(30, "denom")).unwrap();
+        env.set_block(Timestamp::from_seconds(1183));
+        env.play_paid("alice", keep.clone(), &coins(10, "denom")).unwrap();
+        env.play_help("bob", "alice").unwrap();
+
+        // costs are added to the pot
+        assert_eq!(env.get_snap().decay_game.total, Uint128::new(290));
+        env.assert_pot();
+
+        // the escalation starts over with the round
+        env.next_round(1601, &["alice", "bob"]);
+        env.play_paid("alice", hit_bob, &coins(20, "denom")).unwrap();
+    }
+}
+
+define_test! {
+    name: test_shield,
+    game: {
+        points.shield_seconds: 100,
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie"]);
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+        env.play_keep("bob").unwrap();
+        env.set_block(Timestamp::from_seconds(1061));
+        env.play_paid("bob", PlayMsg::Shield {}, &[]).unwrap();
+
+        // hits are blocked until the shield is over, helps still go through
+        assert_gated(env.play_hit("alice", "bob"), "shielded");
+        env.play_help("charlie", "bob").unwrap();
+        assert_eq!(env.points("bob"), 8);
+        env.set_block(Timestamp::from_seconds(1161));
+        env.play_hit("alice", "bob").unwrap();
+        assert_eq!(env.points("bob"), 3);
+
+        let plays: Vec<PlayMsg> =
+            env.player_history("bob", None, 10).into_iter().rev().map(|(_, record)| record.msg).collect();
+        assert_eq!(plays, vec![PlayMsg::Keep {}, PlayMsg::Shield {}]);
+    }
+}
+
+define_test! {
+    name: test_steal,
+    game: {
+        points.steal: 3,
+        points.shield_seconds: 100,
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie"]);
+        env.set_block(Timestamp::from_seconds(1000));
+        assert_gated(env.play_paid("charlie", PlayMsg::Rug {}, &[]), "action_disabled");
+        env.play_keep("alice").unwrap();
+        env.play_keep("bob").unwrap();
+        env.verify_winner("alice");
+
+        // points move from the target to the player
+        env.set_block(Timestamp::from_seconds(1061));
+        env.play_paid("bob", PlayMsg::Steal { target: env.addr("alice") }, &[]).unwrap();
+        assert_eq!((env.points("alice"), env.points("bob")), (1, 7));
+        env.verify_winner("bob");
+
+        // never more than the target has
+        env.set_block(Timestamp::from_seconds(1122));
+        env.play_paid("bob", PlayMsg::Steal { target: env.addr("alice") }, &[]).unwrap();
+        assert_eq!((env.points("alice"), env.points("bob")), (0, 8));
+
+        // shields block steals
+        env.play_paid("alice", PlayMsg::Shield {}, &[]).unwrap();
+        env.set_block(Timestamp::from_seconds(1183));
+        assert_gated(
+            env.play_paid("bob", PlayMsg::Steal { target: env.addr("alice") }, &[]),
+            "shielded",
+        );
+
+        let steal = |at: u64, points: i64| PlayRecord {
+            msg: PlayMsg::Steal { target: env.addr("alice") },
+            at: Timestamp::from_seconds(at),
+            target: Some(env.addr("alice")),
+            points,
+            target_points: -points,
+        };
+        assert_eq!(env.player_history("bob", None, 2), vec![(2, steal(1122, 1)), (1, steal(1061, 3))]);
+        assert_eq!(env.player_stats("alice").hits_received, 0);
+    }
+}
+
+define_test! {
+    name: test_rug,
+    game: {
+        points.rug_bonus: Decimal::percent(5),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_keep("alice").unwrap();
+        env.play_keep("bob").unwrap();
+        env.verify_winner("alice");
+
+        // half of the decay left plus 5% for each of the 4 points traded
+        env.set_block(Timestamp::from_seconds(1300));
+        env.play_paid("alice", PlayMsg::Rug {}, &[]).unwrap();
+        env.assert_balance("alice", coin(970u128, "denom"));
+        env.assert_pot();
+
+        let alice = env.get_player("alice").unwrap();
+        assert_eq!(alice.status.points, 0);
+        let (_, rug) = env.player_history("alice", None, 1).remove(0);
+        assert_eq!((rug.msg, rug.points), (PlayMsg::Rug {}, -4));
+        assert_eq!(alice.account.decay_snapshot, Decimal::percent(70));
+        assert_eq!(alice.account.status, AccountStatus::Claimed { at: Timestamp::from_seconds(1300) });
+        assert_eq!(env.player_stats("alice").paid_out, Uint128::new(70));
+        env.verify_winner("bob");
+
+        // a rug is an exit
+        env.set_block(Timestamp::from_seconds(1400));
+        assert_gated(env.play_keep("alice"), "exited_cannot_play");
+        env.exit("alice").unwrap_err();
+    }
+}
+
+define_test! {
+    name: test_player_history,
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        for (at, hit) in [(1000, false), (1061, true), (1122, false)] {
+            env.set_block(Timestamp::from_seconds(at));
+            match hit {
+                true => env.play_hit("alice", "bob").unwrap(),
+                false => env.play_keep("alice").unwrap(),
+            };
+        }
+        env.play_keep("bob").unwrap();
+
+        // pages walk from the latest play
+        let first = env.player_history("alice", None, 2);
+        assert_eq!(first.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(
+            first[1].1,
+            PlayRecord {
+                msg: PlayMsg::Hit { target: env.addr("bob") },
+                at: Timestamp::from_seconds(1061),
+                target: Some(env.addr("bob")),
+                points: 0,
+                target_points: 0,
+            }
+        );
+        let second = env.player_history("alice", Some(1), 2);
+        assert_eq!(second.len(), 1);
+        assert_eq!((second[0].0, second[0].1.points), (0, 4));
+        assert_eq!(env.get_player("alice").unwrap().status.plays, 3);
+        assert_eq!(env.player_history("bob", None, 10).len(), 1);
+
+        // the history is pruned when the round restarts
+        env.next_round(1601, &["alice"]);
+        assert!(env.player_history("alice", None, 10).is_empty());
+        assert!(env.player_history("bob", None, 10).is_empty());
+        env.play_keep("alice").unwrap();
+        assert_eq!(env.player_history("alice", None, 10)[0].0, 0);
+    }
+}
+
+define_test! {
+    name: test_teams,
+    game: {
+        teams: Some(TeamRules {
+            max_members: 2,
+            teammate_help: TeammateHelp::Discounted { share: Decimal::percent(50) },
+        }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie", "dave", "erin"]);
+        env.create_team("alice", "red").unwrap();
+        env.join_team("bob", "red").unwrap();
+        env.create_team("charlie", "blue").unwrap();
+        env.join_team("dave", "blue").unwrap();
+        assert_gated(env.join_team("erin", "red"), "team_full");
+        assert_gated(env.create_team("alice", "green"), "in_team");
+        assert_gated(env.create_team("erin", "red"), "team_exists");
+        assert_gated(env.join_team("erin", "green"), "team_not_found");
+        assert_gated(env.create_team("erin", ""), "team_name");
+        assert_gated(env.create_team("owner", "green"), "not_joined");
+
+        env.set_block(Timestamp::from_seconds(1000));
+        assert_gated(env.create_team("erin", "green"), "join_window");
+        assert_gated(env.play_keep("erin"), "not_in_team");
+        assert_gated(env.play_hit("alice", "bob"), "teammate");
+
+        // help between teammates is paid at half rate
+        env.play_help("alice", "bob").unwrap();
+        assert_eq!(env.get_player("alice").unwrap().status.points, 3);
+        assert_eq!(env.get_player("bob").unwrap().status.points, 2);
+        env.play_keep("charlie").unwrap();
+        env.play_keep("dave").unwrap();
+        env.set_block(Timestamp::from_seconds(1061));
+        env.play_keep("dave").unwrap();
+        env.play_hit("charlie", "alice").unwrap();
+        assert_eq!(env.team("red").unwrap().points, 2);
+        let blue = env.team("blue").unwrap();
+        assert_eq!((blue.members, blue.points), (vec![env.addr("charlie"), env.addr("dave")], 12));
+        assert_eq!(env.team("green"), None);
+
+        // blue splits the prize by points, the other accounts are forfeited
+        env.set_block(Timestamp::from_seconds(1601));
+        env.endgame("anyone").unwrap();
+        env.assert_balance("charlie", coin(1048u128, "denom"));
+        env.assert_balance("dave", coin(1196u128, "denom"));
+        env.assert_balance("owner", coin(55u128, "denom"));
+        env.assert_balance("alice", coin(900u128, "denom"));
+        assert_eq!(env.player_stats("charlie").wins, 1);
+        assert_eq!(env.player_stats("dave").wins, 1);
+        let forfeited = AccountStatus::Forfeited { at: Timestamp::from_seconds(1601) };
+        assert_eq!(env.get_player("erin").unwrap().account.status, forfeited);
+
+        // teams are archived with the round and cleared for the next one
+        env.restart("anyone").unwrap();
+        assert_eq!(env.round(1).unwrap().winning_team, Some(("blue".to_string(), 12)));
+        assert_eq!(env.team("blue"), None);
+    }
+}
+
+define_test! {
+    name: test_team_winner,
+    game: {
+        teams: Some(TeamRules { max_members: 2, teammate_help: TeammateHelp::Allowed }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie", "dave"]);
+        env.create_team("alice", "red").unwrap();
+        env.join_team("bob", "red").unwrap();
+        env.create_team("charlie", "blue").unwrap();
+        env.join_team("dave", "blue").unwrap();
+
+        // alice leads the players but red trails blue 14 to 16
+        env.set_block(Timestamp::from_seconds(1000));
+        env.play_help("alice", "bob").unwrap();
+        env.play_keep("charlie").unwrap();
+        env.play_keep("dave").unwrap();
+        env.set_block(Timestamp::from_seconds(1061));
+        env.play_keep("alice").unwrap();
+        env.play_keep("charlie").unwrap();
+        env.play_keep("dave").unwrap();
+        env.verify_winner("alice");
+        assert_eq!((env.team("red").unwrap().points, env.team("blue").unwrap().points), (14, 16));
+
+        // the round goes to the best placed member of blue, the tie on 8 to the lowest address
+        assert!(env.addr("charlie") < env.addr("dave"));
+        env.set_block(Timestamp::from_seconds(1601));
+        let res = env.endgame("anyone").unwrap();
+        let charlie = env.addr("charlie");
+        assert!(res.has_event(&Event::new("wasm").add_attribute("winner", charlie.as_str())));
+        assert_eq!(env.get_snap().current_winner, Some((charlie.clone(), 8)));
+        assert_eq!(env.player_stats("alice").wins, 0);
+
+        env.restart("anyone").unwrap();
+        assert_eq!(env.round(1).unwrap().winner, Some((charlie, 8)));
+        assert_eq!(env.rounds_won("charlie", None), vec![1]);
+        assert!(env.rounds_won("alice", None).is_empty());
+    }
+}
+
+define_test! {
+    name: test_team_no_plays,
+    game: {
+        teams: Some(TeamRules { max_members: 2, teammate_help: TeammateHelp::Allowed }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie"]);
+        env.create_team("alice", "red").unwrap();
+        env.join_team("bob", "red").unwrap();
+        env.create_team("charlie", "blue").unwrap();
+
+        // the teams tie on no points, the first name wins
+        env.set_block(Timestamp::from_seconds(1601));
+        env.endgame("anyone").unwrap();
+        assert_eq!(env.player_stats("charlie").wins, 1);
+        let forfeited = AccountStatus::Forfeited { at: Timestamp::from_seconds(1601) };
+        assert_eq!(env.get_player("alice").unwrap().account.status, forfeited);
+        env.restart("anyone").unwrap();
+        assert_eq!(env.round(1).unwrap().winning_team, Some(("blue".to_string(), 0)));
+    }
+}
+
+define_test! {
+    name: test_team_mode_off,
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob"]);
+        assert_gated(env.create_team("alice", "red"), "team_mode");
+    }
+}
+
+define_test! {
+    name: test_team_rules,
+    game: {
+        teams: Some(TeamRules { max_members: 3, teammate_help: TeammateHelp::Forbidden }),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.join_round(&["alice", "bob", "charlie"]);
+        env.create_team("alice", "red").unwrap();
+        env.join_team("bob", "red").unwrap();
+        env.create_team("charlie", "blue").unwrap();
+        env.set_block(Timestamp::from_seconds(1000));
+        assert_gated(env.play_help("alice", "bob"), "teammate_help");
+        env.play_help("alice", "charlie").unwrap();
+        env.play_keep("bob").unwrap();
+
+        // helping a rival team is still allowed
+        assert_eq!(env.team("red").unwrap().points, 10);
+        assert_eq!(env.team("blue").unwrap().points, 4);
+        env.verify_winner("alice");
     }
 }
@@ -1,13 +1,20 @@
-use cosmwasm_std::{testing::MockApi, to_json_string, Addr, Coin, Decimal, Timestamp, Uint128};
+use cosmwasm_std::{
+   coins, testing::MockApi, to_json_string, Addr, Coin, Decimal, Timestamp, Uint128,
+};
 use cw_multi_test::{App, AppResponse, BasicAppBuilder, ContractWrapper, Executor};
+use wenruji_rs::{keeper::NextAction, DecayCurve, PlayerStats};
 
 use crate::{
-   config::{Config, ConfigUpdate},
-   contract::{execute, instantiate, query},
-   game::GameSmSnapshot,
-   msg::{ExecuteMsg, Fee, Fees, InstantiateMsg, Point, Points, QueryMsg},
+   config::{Config, ConfigUpdate, PrizeTable},
+   contract::{execute, instantiate, migrate, query},
+   game::{Consolation, GameSmSnapshot, PlayRecord, RoundSummary, Team},
+   msg::{
+      ActionRules, ExecuteMsg, Fee, Fees, HelpGate, InstantiateMsg, MigrateMsg, PlayerResponse,
+      Point, Points, QueryMsg, TeamRules,
+   },
 };
 
+#[allow(dead_code)]
 pub struct TestEnv {
    pub app: App,
    pub owner: Addr,
@@ -29,6 +36,16 @@ pub struct PartialInstantiate {
    pub delay_play_seconds: u64,
    pub fees: Vec<Decimal>,
    pub points: Points,
+   pub decay_curve: Option<DecayCurve>,
+   pub help_gate: Option<HelpGate>,
+   pub prize_table: Option<PrizeTable>,
+   pub settlement_fee: Option<Decimal>,
+   pub round_retention: Option<u64>,
+   pub max_ticket_amount: Option<Uint128>,
+   pub stake_weighted_points: Option<bool>,
+   pub consolation_share: Option<Decimal>,
+   pub action_rules: Option<ActionRules>,
+   pub teams: Option<TeamRules>,
 }
 
 pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantiate) -> TestEnv {
@@ -53,6 +70,8 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
             owner: app.api().addr_make("owner"),
             whitelisted_denoms: referral::msg::Whitelist::All,
             whitelisted_contracts: referral::msg::Whitelist::All,
+            tiers: None,
+            max_depth: None,
          },
          &[],
          "referral",
@@ -60,7 +79,9 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
       )
       .unwrap();
 
-   let game_code_id = app.store_code(Box::new(ContractWrapper::new(execute, instantiate, query)));
+   let game_code_id = app.store_code(Box::new(
+      ContractWrapper::new(execute, instantiate, query).with_migrate(migrate),
+   ));
 
    let game_addr = app
       .instantiate_contract(
@@ -79,10 +100,20 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
                fee_ref: Fee { address: ref_addr.clone(), bp: config.fees[1] },
             },
             points: config.points,
+            decay_curve: config.decay_curve,
+            help_gate: config.help_gate,
+            prize_table: config.prize_table,
+            settlement_fee: config.settlement_fee,
+            round_retention: config.round_retention,
+            max_ticket_amount: config.max_ticket_amount,
+            stake_weighted_points: config.stake_weighted_points,
+            consolation_share: config.consolation_share,
+            action_rules: config.action_rules,
+            teams: config.teams,
          },
          &[],
          "game",
-         None,
+         Some(app.api().addr_make("owner").to_string()),
       )
       .unwrap();
 
@@ -93,6 +124,7 @@ pub fn setup_test_env(balances: Vec<(Addr, Vec<Coin>)>, config: PartialInstantia
    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_partial_instantiate(
    owner: &str,
    ticket_denom: &str,
@@ -106,7 +138,7 @@ pub fn create_partial_instantiate(
    pt_help: (i64, i64),
 ) -> PartialInstantiate {
    PartialInstantiate {
-      owner: MockApi::default().addr_make(&owner),
+      owner: MockApi::default().addr_make(owner),
       ticket_denom: ticket_denom.to_string(),
       ticket_amount,
       starts_at,
@@ -117,16 +149,90 @@ pub fn create_partial_instantiate(
          keep: pt_keep,
          hit: pt_hit,
          help: Point { myself: pt_help.0, other: pt_help.1 },
+         shield_seconds: 0,
+         steal: 0,
+         rug_bonus: Decimal::zero(),
       },
       game_delay_sec: 10u64,
+      decay_curve: None,
+      help_gate: None,
+      prize_table: None,
+      settlement_fee: None,
+      round_retention: None,
+      max_ticket_amount: None,
+      stake_weighted_points: None,
+      consolation_share: None,
+      action_rules: None,
+      teams: None,
+   }
+}
+
+/// A 100 ticket round from 1000 to 1600 with a 60 seconds play delay, 10% for each fee and
+/// 4 points to keep, -5 to hit and 6/4 to help
+impl Default for PartialInstantiate {
+   fn default() -> Self {
+      create_partial_instantiate(
+         "owner",
+         "denom",
+         Uint128::new(100),
+         Timestamp::from_seconds(1000),
+         600u64,
+         60u64,
+         vec![Decimal::percent(10), Decimal::percent(10)],
+         4i64,
+         -5i64,
+         (6i64, 4i64),
+      )
    }
 }
 
+/// alice, bob, charlie, dave and erin with 1000 denom each
+pub fn default_accounts() -> Vec<(Addr, Vec<Coin>)> {
+   ["alice", "bob", "charlie", "dave", "erin"]
+      .iter()
+      .map(|account| (MockApi::default().addr_make(account), coins(1000u128, "denom")))
+      .collect()
+}
+
+/// Asserts that `res` failed on the `gate` check
+pub fn assert_gated(res: anyhow::Result<AppResponse>, gate: &str) {
+   assert_eq!(res.unwrap_err().root_cause().to_string(), format!("Invalid: {gate}"));
+}
+
 impl TestEnv {
    pub fn addr(&self, account: &str) -> Addr {
       self.app.api().addr_make(account)
    }
 
+   /// Joins `players` with a 100 ticket at 999, before the first round starts
+   pub fn join_round(&mut self, players: &[&str]) {
+      self.set_block(Timestamp::from_seconds(999));
+      for player in players {
+         self.join(player, None, coins(100, "denom")).unwrap();
+      }
+   }
+
+   /// Ends the current round at `now`, restarts it, joins `players` and moves to its start
+   pub fn next_round(&mut self, now: u64, players: &[&str]) {
+      self.set_block(Timestamp::from_seconds(now));
+      self.endgame("anyone").unwrap();
+      self.restart("anyone").unwrap();
+      for player in players {
+         self.join(player, None, coins(100, "denom")).unwrap();
+      }
+      self.set_block(Timestamp::from_seconds(now + 10));
+   }
+
+   /// The game holds exactly the pool left to the winners
+   pub fn assert_pot(&mut self) {
+      let game = self.get_snap().decay_game;
+      assert_eq!(self.game_balance("denom"), game.total - game.exited);
+   }
+
+   pub fn points(&mut self, account: &str) -> i64 {
+      self.get_player(account).unwrap().status.points
+   }
+
    pub fn join(
       &mut self,
       account: &str,
@@ -168,6 +274,64 @@ impl TestEnv {
       )
    }
 
+   pub fn create_team(&mut self, account: &str, name: &str) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::CreateTeam { name: name.to_string() },
+         &[],
+      )
+   }
+
+   pub fn join_team(&mut self, account: &str, name: &str) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::JoinTeam { name: name.to_string() },
+         &[],
+      )
+   }
+
+   pub fn exit_partial(&mut self, account: &str, amount: u128) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::ExitPartial { amount: Uint128::new(amount) },
+         &[],
+      )
+   }
+
+   pub fn top_up(&mut self, account: &str, funds: Vec<Coin>) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::TopUp {},
+         &funds,
+      )
+   }
+
+   /// Funds held by the game contract
+   pub fn game_balance(&self, denom: &str) -> Uint128 {
+      self.app.wrap().query_balance(self.contracts.game.clone(), denom).unwrap().amount
+   }
+
+   pub fn settle(&mut self, account: &str) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::Settle {},
+         &[],
+      )
+   }
+
+   pub fn next_action(&self) -> NextAction {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::NextAction {})
+         .unwrap()
+   }
+
    pub fn play_keep(&mut self, account: &str) -> anyhow::Result<AppResponse> {
       self.app.execute_contract(
          self.addr(account),
@@ -194,6 +358,21 @@ impl TestEnv {
       )
    }
 
+   /// Play paying `funds` for its cost
+   pub fn play_paid(
+      &mut self,
+      account: &str,
+      msg: crate::msg::PlayMsg,
+      funds: &[Coin],
+   ) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &ExecuteMsg::Play(msg),
+         funds,
+      )
+   }
+
    pub fn update_config(
       &mut self,
       account: &str,
@@ -207,17 +386,152 @@ impl TestEnv {
       )
    }
 
-   pub fn get_snap(&mut self, idx: Option<u64>) -> GameSmSnapshot {
+   pub fn migrate(&mut self, account: &str) -> anyhow::Result<AppResponse> {
+      let code_id = self.app.contract_data(&self.contracts.game)?.code_id;
+      self.app.migrate_contract(
+         self.addr(account),
+         self.contracts.game.clone(),
+         &MigrateMsg {},
+         code_id,
+      )
+   }
+
+   pub fn get_snap(&mut self) -> GameSmSnapshot {
       self
          .app
          .wrap()
          .query_wasm_smart::<GameSmSnapshot>(
             self.contracts.game.clone(),
-            &QueryMsg::GameStatus { idx },
+            &QueryMsg::GameStatus { start_after: None, limit: None },
          )
          .unwrap()
    }
 
+   pub fn round(&self, idx: u64) -> Option<RoundSummary> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::Round { idx })
+         .unwrap()
+   }
+
+   pub fn round_history(&self, start_after: Option<u64>, limit: u32) -> Vec<(u64, RoundSummary)> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::RoundHistory { start_after, limit: Some(limit) },
+         )
+         .unwrap()
+   }
+
+   pub fn rounds_won(&self, account: &str, start_after: Option<u64>) -> Vec<u64> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::RoundsWon { addr: self.addr(account), start_after, limit: None },
+         )
+         .unwrap()
+   }
+
+   pub fn rounds_played(&self, account: &str, start_after: Option<u64>) -> Vec<u64> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::RoundsPlayed { addr: self.addr(account), start_after, limit: None },
+         )
+         .unwrap()
+   }
+
+   pub fn get_snap_page(&mut self, start_after: Option<Addr>, limit: u32) -> GameSmSnapshot {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart::<GameSmSnapshot>(
+            self.contracts.game.clone(),
+            &QueryMsg::GameStatus { start_after, limit: Some(limit) },
+         )
+         .unwrap()
+   }
+
+   pub fn get_player(&mut self, account: &str) -> Option<PlayerResponse> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::Player { addr: self.addr(account) },
+         )
+         .unwrap()
+   }
+
+   pub fn player_stats(&self, account: &str) -> PlayerStats {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::PlayerStats { addr: self.addr(account) },
+         )
+         .unwrap()
+   }
+
+   pub fn leaderboard(&self, start_after: Option<(Addr, i64)>, limit: u32) -> Vec<(Addr, i64)> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::Leaderboard { start_after, limit: Some(limit) },
+         )
+         .unwrap()
+   }
+
+   pub fn player_history(
+      &self,
+      account: &str,
+      start_after: Option<u64>,
+      limit: u32,
+   ) -> Vec<(u64, PlayRecord)> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::PlayerHistory { addr: self.addr(account), start_after, limit: Some(limit) },
+         )
+         .unwrap()
+   }
+
+   pub fn consolation(&self) -> Consolation {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::Consolation {})
+         .unwrap()
+   }
+
+   pub fn team(&self, name: &str) -> Option<Team> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::Team { name: name.to_string() })
+         .unwrap()
+   }
+
+   pub fn game_index(&self) -> u64 {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::GameIndex {})
+         .unwrap()
+   }
+
    pub fn get_config(&mut self) -> Config {
       self
          .app
@@ -230,7 +544,10 @@ impl TestEnv {
       let query: GameSmSnapshot = self
          .app
          .wrap()
-         .query_wasm_smart(self.contracts.game.clone(), &QueryMsg::GameStatus { idx: None })
+         .query_wasm_smart(
+            self.contracts.game.clone(),
+            &QueryMsg::GameStatus { start_after: None, limit: None },
+         )
          .unwrap();
 
       assert_eq!(self.addr(account), query.current_winner.unwrap().0)
@@ -270,7 +587,24 @@ fn test_instantiate_msg_json() {
             bp: Decimal::bps(1000),
          },
       },
-      points: Points { keep: 4i64, hit: -5i64, help: Point { myself: 6, other: 4 } },
+      points: Points {
+         keep: 4i64,
+         hit: -5i64,
+         help: Point { myself: 6, other: 4 },
+         shield_seconds: 0,
+         steal: 0,
+         rug_bonus: Decimal::zero(),
+      },
+      decay_curve: None,
+      help_gate: None,
+      prize_table: None,
+      settlement_fee: None,
+      round_retention: None,
+      max_ticket_amount: None,
+      stake_weighted_points: None,
+      consolation_share: None,
+      action_rules: None,
+      teams: None,
    };
 
    // Serialize the instance to JSON and print it
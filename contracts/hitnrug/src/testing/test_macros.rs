@@ -2,48 +2,25 @@ mod macros {
    macro_rules! define_test {
          (
              name: $name:ident,
-             game: {
-                 owner: $owner:expr,
-                 ticket_denom: $ticket_denom:expr,
-                 ticket_amount: $ticket_amount:expr,
-                 start_at: $start_at:expr,
-                 duration_seconds: $duration_seconds:expr,
-                 delay_play_seconds: $delay_play_seconds:expr,
-                 fees: $fees:expr,
-                 pt_keep: $pt_keep:expr,
-                 pt_hit: $pt_hit:expr,
-                 pt_help: $pt_help:expr,
-             },
-             accounts: {
+             $(game: {
+                 $($game:tt)*
+             },)?
+             $(accounts: {
                  $($account:ident: $balance:expr),* $(,)?
-             },
+             },)?
              test_fn: $test_fn:expr $(,)?
          ) => {
              #[test]
              fn $name() {
-                 use crate::testing::test_helpers::{setup_test_env, TestEnv, create_partial_instantiate};
-                 use cosmwasm_std::testing::MockApi;
+                 use crate::testing::test_helpers::{setup_test_env, TestEnv, PartialInstantiate};
 
-                 // Set up the contract competition configuration
-                 let config = create_partial_instantiate (
-                    $owner,
-                    $ticket_denom,
-                    $ticket_amount,
-                    $start_at,
-                    $duration_seconds,
-                    $delay_play_seconds,
-                    $fees,
-                    $pt_keep,
-                    $pt_hit,
-                    $pt_help
-                );
+                 // Set up the contract competition configuration, `game` overrides the defaults
+                 #[allow(unused_mut)]
+                 let mut config = PartialInstantiate::default();
+                 $(define_test!(@game config; $($game)*);)?
 
-                 // Set up the accounts
-                 let accounts = vec![
-                     $(
-                         (MockApi::default().addr_make(stringify!($account)), $balance),
-                     )*
-                 ];
+                 // Set up the accounts, `default_accounts` unless listed
+                 let accounts = define_test!(@accounts $($($account: $balance),*)?);
 
                  // Initialize the test environment
                  let mut env = setup_test_env( accounts, config);
@@ -52,6 +29,46 @@ mod macros {
                  $test_fn(&mut env);
              }
          };
+         (@accounts) => {
+             crate::testing::test_helpers::default_accounts()
+         };
+         (@accounts $($account:ident: $balance:expr),+) => {
+             vec![
+                 $(
+                     (cosmwasm_std::testing::MockApi::default().addr_make(stringify!($account)), $balance),
+                 )+
+             ]
+         };
+         (@game $config:ident;) => {};
+         (@game $config:ident; owner: $owner:expr $(, $($rest:tt)*)?) => {
+             $config.owner = cosmwasm_std::testing::MockApi::default().addr_make($owner);
+             define_test!(@game $config; $($($rest)*)?);
+         };
+         (@game $config:ident; ticket_denom: $ticket_denom:expr $(, $($rest:tt)*)?) => {
+             $config.ticket_denom = $ticket_denom.to_string();
+             define_test!(@game $config; $($($rest)*)?);
+         };
+         (@game $config:ident; start_at: $start_at:expr $(, $($rest:tt)*)?) => {
+             $config.starts_at = $start_at;
+             define_test!(@game $config; $($($rest)*)?);
+         };
+         (@game $config:ident; pt_keep: $pt_keep:expr $(, $($rest:tt)*)?) => {
+             $config.points.keep = $pt_keep;
+             define_test!(@game $config; $($($rest)*)?);
+         };
+         (@game $config:ident; pt_hit: $pt_hit:expr $(, $($rest:tt)*)?) => {
+             $config.points.hit = $pt_hit;
+             define_test!(@game $config; $($($rest)*)?);
+         };
+         (@game $config:ident; pt_help: $pt_help:expr $(, $($rest:tt)*)?) => {
+             let (myself, other) = $pt_help;
+             $config.points.help = crate::msg::Point { myself, other };
+             define_test!(@game $config; $($($rest)*)?);
+         };
+         (@game $config:ident; $($field:ident).+: $value:expr $(, $($rest:tt)*)?) => {
+             $config.$($field).+ = $value;
+             define_test!(@game $config; $($($rest)*)?);
+         };
      }
 
    pub(crate) use define_test;
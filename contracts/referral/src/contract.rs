@@ -2,12 +2,13 @@ use cosmwasm_std::{
    ensure, entry_point, to_json_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, Event,
    MessageInfo, Response,
 };
-use cw2::set_contract_version;
+use cw2::{ensure_from_older_version, set_contract_version};
 use cw_utils::PaymentError;
-use wenruji_rs::{calculate_fee_distribution, RewardsSM};
+use wenruji_rs::{calculate_fee_distribution, page_limit, RewardsSM};
 
 use crate::{
-   msg::{PendingRewardsResponse, Whitelist},
+   migrations::migrate_state,
+   msg::{MigrateMsg, PendingRewardsResponse, TierEarnings, Whitelist},
    state::{config::Config, referral::ReferralSM},
    ContractError, ExecuteMsg, InstantiateMsg, QueryMsg,
 };
@@ -44,14 +45,27 @@ pub fn execute(
    let mut config: Config = Config::load(deps.storage)?;
    match msg {
       ExecuteMsg::GenCode { code } => {
-         REFERRAL.gen_code(deps.storage, &info.sender.to_string(), &code)?;
+         REFERRAL.gen_code(deps.storage, info.sender.as_str(), &code)?;
          Ok(Response::default().add_event(Event::new("referral/gen_code")))
       }
+      ExecuteMsg::ChangeCode { code } => {
+         REFERRAL.change_code(deps.storage, info.sender.as_str(), &code)?;
+         Ok(Response::default().add_event(Event::new("referral/change_code")))
+      }
+      ExecuteMsg::RevokeCode { code } => {
+         REFERRAL.revoke_code(deps.storage, info.sender.as_str(), &code)?;
+         Ok(Response::default().add_event(Event::new("referral/revoke_code")))
+      }
+      ExecuteMsg::TransferCode { code, to } => {
+         let to = deps.api.addr_validate(to.as_str())?;
+         REFERRAL.transfer_code(deps.storage, info.sender.as_str(), &code, to.as_str())?;
+         Ok(Response::default().add_event(Event::new("referral/transfer_code")))
+      }
       ExecuteMsg::AddReferee { code, referee } => {
          if let Whitelist::Some(whitelist) = &config.whitelisted_contracts {
             ensure!(whitelist.contains(&info.sender.to_string()), ContractError::Unauthorized {});
          }
-         REFERRAL.add_referee(deps.storage, &referee.to_string(), &code)?;
+         REFERRAL.add_referee(deps.storage, referee.as_str(), &code)?;
          Ok(Response::default().add_event(Event::new("referral/add_referee")))
       }
       ExecuteMsg::UpdateConfig(msg) => {
@@ -67,7 +81,7 @@ pub fn execute(
          let return_msg: BankMsg =
             BankMsg::Send { to_address: info.sender.to_string(), amount: coins.clone() };
 
-         let event = Event::new(format!("referral/claim"))
+         let event = Event::new("referral/claim")
             .add_attributes(vec![("action", "claim"), ("staker", info.sender.as_str())]);
 
          Ok(Response::new().add_message(return_msg).add_event(event))
@@ -88,8 +102,14 @@ pub fn execute(
          // Distribution split
          let distribution = calculate_fee_distribution(info.funds, &referers);
 
-         for (user, rewards) in distribution.iter() {
-            REWARDS.add_accrued_rewards(deps.storage, &user, rewards)?;
+         for (referer, rewards) in distribution.iter() {
+            let split =
+               REFERRAL.split_tiers(deps.storage, referer.as_str(), rewards, &config.tiers)?;
+            for (user, level, earned) in split {
+               let user = deps.api.addr_validate(&user)?;
+               REWARDS.add_accrued_rewards(deps.storage, &user, &earned)?;
+               REFERRAL.add_tier_earnings(deps.storage, user.as_str(), level, &earned)?;
+            }
          }
 
          let event = Event::new("referral/distribute_rewards").add_attributes(vec![
@@ -102,24 +122,55 @@ pub fn execute(
    }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+   let stored = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+   migrate_state(deps.storage, &stored)?;
+
+   Ok(Response::new().add_event(
+      Event::new("referral/migrate")
+         .add_attribute("from_version", stored.to_string())
+         .add_attribute("to_version", CONTRACT_VERSION),
+   ))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
    let config = Config::load(deps.storage)?;
    Ok(match msg {
       QueryMsg::Config {} => to_json_binary(&config),
       QueryMsg::GetCode { user } => {
-         to_json_binary(&REFERRAL.get_code(deps.storage, &user.to_string())?)
+         to_json_binary(&REFERRAL.get_code(deps.storage, user.as_str())?)
       }
       QueryMsg::GetAddr { code } => to_json_binary(&REFERRAL.get_addr(deps.storage, &code)?),
       QueryMsg::GetReferrer { user } => {
-         to_json_binary(&REFERRAL.get_referrer(deps.storage, &user.to_string())?)
+         to_json_binary(&REFERRAL.get_referrer(deps.storage, user.as_str())?)
+      }
+      QueryMsg::ReferralStructure { user, start_after, limit } => {
+         to_json_binary(&REFERRAL.get_referral_struct(
+            deps.storage,
+            user.as_str(),
+            start_after.as_deref(),
+            Some(page_limit(limit)),
+         )?)
       }
-      QueryMsg::ReferralStructure { user } => {
-         to_json_binary(&REFERRAL.get_referral_struct(deps.storage, &user.to_string())?)
+      QueryMsg::ReferralCount { user } => {
+         to_json_binary(&REFERRAL.get_referral_count(deps.storage, user.as_str())?)
       }
       QueryMsg::PendingRewards { user } => {
          let accrued = REWARDS.get_accrued(deps.storage, &user)?;
          to_json_binary(&PendingRewardsResponse { rewards: accrued })
       }
+      QueryMsg::Upline { user } => {
+         to_json_binary(&REFERRAL.get_upline(deps.storage, user.as_str(), config.max_depth)?)
+      }
+      QueryMsg::TierEarnings { user } => {
+         let earnings: Vec<TierEarnings> = REFERRAL
+            .get_tier_earnings(deps.storage, user.as_str())?
+            .into_iter()
+            .map(|(level, earned)| TierEarnings { level, earned })
+            .collect();
+         to_json_binary(&earnings)
+      }
    }?)
 }
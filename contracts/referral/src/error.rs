@@ -18,4 +18,7 @@ pub enum ContractError {
 
    #[error("Reward denom not on whitelist")]
    RewardNotWhitelisted {},
+
+   #[error("Invalid: {0}")]
+   Invalid(String),
 }
@@ -1,11 +1,12 @@
 pub mod contract;
 pub mod error;
+pub mod migrations;
 pub mod msg;
 pub mod state;
 
 #[cfg(test)]
 mod testing;
 
-pub use crate::contract::{execute, instantiate, query};
+pub use crate::contract::{execute, instantiate, migrate, query};
 pub use crate::error::ContractError;
-pub use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+pub use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
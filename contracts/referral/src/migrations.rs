@@ -0,0 +1,69 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Order, StdResult, Storage};
+use cw_storage_plus::Item;
+use semver::Version;
+
+use crate::{
+   contract::REFERRAL,
+   msg::Whitelist,
+   state::config::{Config, DEFAULT_MAX_DEPTH},
+   ContractError,
+};
+
+/// Config layout of the v0.1 releases, before the referral tiers
+#[cw_serde]
+pub struct ConfigV0_1 {
+   pub owner: Addr,
+   pub whitelisted_denoms: Whitelist,
+   pub whitelisted_contracts: Whitelist,
+}
+
+pub static CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+
+/// Applies in order every state transform between the stored version and the current one
+pub fn migrate_state(storage: &mut dyn Storage, stored: &Version) -> Result<(), ContractError> {
+   if stored.major == 0 && stored.minor < 2 {
+      migrate_v0_1(storage)?;
+   }
+   Ok(())
+}
+
+/// Adds the direct-only referral tier, backfills the referrer to referees index and case-folds the
+/// codes generated before the validation. Codes clashing once folded are left as they are.
+fn migrate_v0_1(storage: &mut dyn Storage) -> Result<(), ContractError> {
+   let old = CONFIG_V0_1.load(storage)?;
+   let config = Config {
+      owner: old.owner,
+      whitelisted_denoms: old.whitelisted_denoms,
+      whitelisted_contracts: old.whitelisted_contracts,
+      tiers: vec![Decimal::one()],
+      max_depth: DEFAULT_MAX_DEPTH,
+   };
+   // same storage key, the stored owner was validated on instantiate
+   Item::new("config").save(storage, &config)?;
+
+   let referees = REFERRAL
+      .referee_to_user
+      .range(storage, None, None, Order::Ascending)
+      .collect::<StdResult<Vec<_>>>()?;
+   for (referee, user) in referees {
+      REFERRAL.index_referee(storage, &user, &referee)?;
+   }
+
+   let codes = REFERRAL
+      .code_to_user
+      .range(storage, None, None, Order::Ascending)
+      .collect::<StdResult<Vec<_>>>()?;
+   for (code, user) in codes {
+      let folded = code.to_ascii_lowercase();
+      if folded == code || REFERRAL.code_to_user.has(storage, &folded) {
+         continue;
+      }
+      REFERRAL.code_to_user.remove(storage, &code);
+      REFERRAL.code_to_user.save(storage, &folded, &user)?;
+      if REFERRAL.user_to_code.may_load(storage, &user)?.as_ref() == Some(&code) {
+         REFERRAL.user_to_code.save(storage, &user, &folded)?;
+      }
+   }
+   Ok(())
+}
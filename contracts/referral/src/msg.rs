@@ -8,21 +8,54 @@ pub struct InstantiateMsg {
    pub owner: Addr,
    pub whitelisted_denoms: Whitelist,
    pub whitelisted_contracts: Whitelist,
+   /// Defaults to the whole reward for the direct referrer
+   pub tiers: Option<Vec<Decimal>>,
+   pub max_depth: Option<u32>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
    UpdateConfig(ConfigUpdate),
 
-   GenCode { code: String },
-
-   AddReferee { referee: Addr, code: String },
+   /// Codes are case-folded, 3 to 32 characters of a-z, 0-9, - and _
+   GenCode {
+      code: String,
+   },
+
+   /// Replaces the code of the sender, the previous one is retired like a revoked code
+   ChangeCode {
+      code: String,
+   },
+
+   /// Stops a code of the sender from taking new referees, it cannot be taken again
+   RevokeCode {
+      code: String,
+   },
+
+   /// Moves `code` of the sender to `to`, who must not have a code yet. Existing referees keep
+   /// their referrer.
+   TransferCode {
+      code: String,
+      to: Addr,
+   },
+
+   AddReferee {
+      referee: Addr,
+      code: String,
+   },
 
    ClaimRewards {},
 
-   DistributeRewards { referers: Vec<(Addr, Decimal)> },
+   /// Splits the funds between `referers` by weight, then shares each part along their upline
+   /// following the configured tiers
+   DistributeRewards {
+      referers: Vec<(Addr, Decimal)>,
+   },
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -38,11 +71,29 @@ pub enum QueryMsg {
    #[returns(String)]
    GetReferrer { user: Addr },
 
+   /// Referees of `user` ordered by address
    #[returns(Vec<String>)]
-   ReferralStructure { user: Addr },
+   ReferralStructure { user: Addr, start_after: Option<String>, limit: Option<u32> },
+
+   #[returns(u64)]
+   ReferralCount { user: Addr },
 
    #[returns(PendingRewardsResponse)]
    PendingRewards { user: Addr },
+
+   /// Referrers of `user` from the direct one up to `max_depth` levels
+   #[returns(Vec<String>)]
+   Upline { user: Addr },
+
+   #[returns(Vec<TierEarnings>)]
+   TierEarnings { user: Addr },
+}
+
+#[cw_serde]
+pub struct TierEarnings {
+   /// Level in the upline of the referees, 1 for direct referrals
+   pub level: u32,
+   pub earned: Vec<Coin>,
 }
 
 #[cw_serde]
@@ -61,4 +112,6 @@ pub struct ConfigUpdate {
    pub owner: Option<Addr>,
    pub whitelisted_denoms: Option<Whitelist>,
    pub whitelisted_contracts: Option<Whitelist>,
+   pub tiers: Option<Vec<Decimal>>,
+   pub max_depth: Option<u32>,
 }
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Api, StdResult, Storage};
+use cosmwasm_std::{ensure, Addr, Api, Decimal, StdResult, Storage};
 use cw_storage_plus::Item;
 
 use crate::msg::{ConfigUpdate, InstantiateMsg, Whitelist};
@@ -11,8 +11,16 @@ pub struct Config {
    pub owner: Addr,
    pub whitelisted_denoms: Whitelist,
    pub whitelisted_contracts: Whitelist,
+   /// Share of the rewards for each level of the upline, starting from the direct referrer
+   pub tiers: Vec<Decimal>,
+   /// Highest number of referrers walked up the chain
+   pub max_depth: u32,
 }
 
+/// Hard cap on `max_depth` so distributions stay within the gas limit
+pub const MAX_DEPTH: u32 = 10;
+pub const DEFAULT_MAX_DEPTH: u32 = 3;
+
 impl Config {
    pub fn load(storage: &dyn Storage) -> StdResult<Self> {
       Item::new("config").load(storage)
@@ -25,6 +33,16 @@ impl Config {
 
    pub fn validate(&self, api: &dyn Api) -> Result<(), ContractError> {
       api.addr_validate(self.owner.as_str())?;
+      ensure!(
+         self.max_depth > 0 && self.max_depth <= MAX_DEPTH,
+         ContractError::Invalid("max_depth".to_string())
+      );
+      ensure!(
+         !self.tiers.is_empty()
+            && self.tiers.len() <= self.max_depth as usize
+            && self.tiers.iter().sum::<Decimal>() == Decimal::one(),
+         ContractError::Invalid("tiers".to_string())
+      );
 
       Ok(())
    }
@@ -42,6 +60,14 @@ impl Config {
          self.whitelisted_contracts = whitelisted_contracts;
       }
 
+      if let Some(tiers) = msg.tiers {
+         self.tiers = tiers;
+      }
+
+      if let Some(max_depth) = msg.max_depth {
+         self.max_depth = max_depth;
+      }
+
       Ok(())
    }
 }
@@ -52,6 +78,50 @@ impl From<InstantiateMsg> for Config {
          owner: msg.owner,
          whitelisted_denoms: msg.whitelisted_denoms,
          whitelisted_contracts: msg.whitelisted_contracts,
+         tiers: msg.tiers.unwrap_or_else(|| vec![Decimal::one()]),
+         max_depth: msg.max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
       }
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use cosmwasm_std::testing::MockApi;
+
+   use super::*;
+
+   #[test]
+   fn test_validate_tiers() {
+      let api = MockApi::default();
+      let mut config = Config::from(InstantiateMsg {
+         owner: api.addr_make("owner"),
+         whitelisted_denoms: Whitelist::All,
+         whitelisted_contracts: Whitelist::All,
+         tiers: None,
+         max_depth: None,
+      });
+      config.validate(&api).unwrap();
+      assert_eq!(config.tiers, vec![Decimal::one()]);
+      assert_eq!(config.max_depth, DEFAULT_MAX_DEPTH);
+
+      config.tiers = vec![Decimal::percent(70), Decimal::percent(20), Decimal::percent(10)];
+      config.validate(&api).unwrap();
+
+      // tiers must split the whole reward
+      config.tiers = vec![Decimal::percent(70), Decimal::percent(20)];
+      config.validate(&api).unwrap_err();
+      config.tiers = vec![];
+      config.validate(&api).unwrap_err();
+
+      // and fit in the walked depth
+      config.tiers = vec![Decimal::percent(25); 4];
+      config.validate(&api).unwrap_err();
+      config.max_depth = 4;
+      config.validate(&api).unwrap();
+
+      config.max_depth = 0;
+      config.validate(&api).unwrap_err();
+      config.max_depth = MAX_DEPTH + 1;
+      config.validate(&api).unwrap_err();
+   }
+}
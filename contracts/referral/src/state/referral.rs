@@ -1,10 +1,47 @@
-use cosmwasm_std::{Order, StdError, StdResult, Storage};
-use cw_storage_plus::Map;
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Coin, Decimal, Empty, Order, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::{Bound, Map};
+
+pub const MIN_CODE_LENGTH: usize = 3;
+pub const MAX_CODE_LENGTH: usize = 32;
+/// Codes nobody can take, compared after case-folding
+pub const RESERVED_CODES: [&str; 6] = ["admin", "owner", "referral", "wenruji", "null", "none"];
+
+/// Case-folds `code` and checks its length, charset and that it is not reserved
+pub fn normalize_code(code: &str) -> StdResult<String> {
+   let code = code.to_ascii_lowercase();
+   if code.len() < MIN_CODE_LENGTH || code.len() > MAX_CODE_LENGTH {
+      return Err(StdError::generic_err(format!(
+         "Code must be {MIN_CODE_LENGTH} to {MAX_CODE_LENGTH} characters"
+      )));
+   }
+   if !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+      return Err(StdError::generic_err("Code can only contain a-z, 0-9, - and _"));
+   }
+   if RESERVED_CODES.contains(&code.as_str()) {
+      return Err(StdError::generic_err("Code is reserved"));
+   }
+   Ok(code)
+}
 
 pub struct ReferralSM<'a> {
+   /// Current code of each user
    pub user_to_code: Map<&'a str, String>,
+   /// Owner of every live code, an account holds at most one
    pub code_to_user: Map<&'a str, String>,
+   /// Last owner of the revoked and replaced codes, they can never be taken again
+   pub revoked_codes: Map<&'a str, String>,
    pub referee_to_user: Map<&'a str, String>,
+   pub user_to_referees: Map<(&'a str, &'a str), Empty>,
+   pub referee_count: Map<&'a str, u64>,
+   pub tier_earnings: Map<(&'a str, u32, &'a str), Uint128>,
+}
+
+impl<'a> Default for ReferralSM<'a> {
+   fn default() -> Self {
+      Self::new()
+   }
 }
 
 impl<'a> ReferralSM<'a> {
@@ -12,36 +49,106 @@ impl<'a> ReferralSM<'a> {
       Self {
          user_to_code: Map::new("ref/utc"),
          code_to_user: Map::new("ref/ctu"),
+         revoked_codes: Map::new("ref/rvk"),
          referee_to_user: Map::new("ref/rft"),
+         user_to_referees: Map::new("ref/utr"),
+         referee_count: Map::new("ref/cnt"),
+         tier_earnings: Map::new("ref/te"),
+      }
+   }
+
+   pub fn gen_code(&self, storage: &mut dyn Storage, user: &str, code: &str) -> StdResult<()> {
+      // Check if the user already has a code
+      let existing_code = self.user_to_code.may_load(storage, user)?;
+      if existing_code.is_some() {
+         return Err(StdError::generic_err("User already has a code"));
+      }
+
+      self.claim_code(storage, user, code)
+   }
+
+   /// Replaces the current code of `user`. The previous one is retired like a revoked code, its
+   /// referees keep their referrer.
+   pub fn change_code(&self, storage: &mut dyn Storage, user: &str, code: &str) -> StdResult<()> {
+      let Some(previous) = self.user_to_code.may_load(storage, user)? else {
+         return Err(StdError::generic_err("User has no code"));
+      };
+      self.claim_code(storage, user, code)?;
+      self.code_to_user.remove(storage, &previous);
+      self.revoked_codes.save(storage, &previous, &user.to_string())
+   }
+
+   /// Stops `code` of `user` from taking new referees, existing referees keep their referrer.
+   /// Revoking the current code lets `user` generate a new one.
+   pub fn revoke_code(&self, storage: &mut dyn Storage, user: &str, code: &str) -> StdResult<()> {
+      let (key, owner) = self.find_code(storage, code)?;
+      if owner != user {
+         return Err(StdError::generic_err("Code not owned by user"));
+      }
+      self.code_to_user.remove(storage, &key);
+      self.revoked_codes.save(storage, &key, &owner)?;
+      if self.user_to_code.may_load(storage, user)?.as_ref() == Some(&key) {
+         self.user_to_code.remove(storage, user);
       }
+      Ok(())
    }
 
-   pub fn gen_code(
+   /// Moves `code` of `user` to `to`, who must not have a code yet. Existing referees keep their
+   /// referrer.
+   pub fn transfer_code(
       &self,
       storage: &mut dyn Storage,
-      user: &String,
-      code: &String,
+      user: &str,
+      code: &str,
+      to: &str,
    ) -> StdResult<()> {
-      // Check if the user already has a code
-      let existing_code = self.user_to_code.may_load(storage, user.as_str())?;
-      if existing_code.is_some() {
+      let (key, owner) = self.find_code(storage, code)?;
+      if owner != user {
+         return Err(StdError::generic_err("Code not owned by user"));
+      }
+      if self.user_to_code.has(storage, to) {
          return Err(StdError::generic_err("User already has a code"));
       }
+      if self.user_to_code.may_load(storage, &owner)?.as_ref() == Some(&key) {
+         self.user_to_code.remove(storage, &owner);
+      }
+      self.code_to_user.save(storage, &key, &to.to_string())?;
+      self.user_to_code.save(storage, to, &key)?;
+      Ok(())
+   }
+
+   /// Validates `code` and makes it the current code of `user`
+   fn claim_code(&self, storage: &mut dyn Storage, user: &str, code: &str) -> StdResult<()> {
+      let code = normalize_code(code)?;
+      if self.code_to_user.has(storage, &code) || self.revoked_codes.has(storage, &code) {
+         return Err(StdError::generic_err("Code already taken"));
+      }
 
-      // Save the new code
-      self.user_to_code.save(storage, user.as_str(), code)?;
-      self.code_to_user.save(storage, code.as_str(), user)?;
+      self.user_to_code.save(storage, user, &code)?;
+      self.code_to_user.save(storage, &code, &user.to_string())?;
       Ok(())
    }
 
-   pub fn get_code(&self, storage: &dyn Storage, user: &String) -> StdResult<String> {
-      Ok(self.user_to_code.may_load(storage, user.as_str())?.unwrap_or_default())
+   /// Stored key and owner of a live code. Codes generated before the validation can contain
+   /// upper case, they are matched as given before being case-folded.
+   fn find_code(&self, storage: &dyn Storage, code: &str) -> StdResult<(String, String)> {
+      if let Some(user) = self.code_to_user.may_load(storage, code)? {
+         return Ok((code.to_string(), user));
+      }
+      let folded = code.to_ascii_lowercase();
+      match self.code_to_user.may_load(storage, &folded)? {
+         Some(user) => Ok((folded, user)),
+         None => Err(StdError::not_found("Code")),
+      }
+   }
+
+   pub fn get_code(&self, storage: &dyn Storage, user: &str) -> StdResult<String> {
+      Ok(self.user_to_code.may_load(storage, user)?.unwrap_or_default())
    }
 
-   pub fn get_addr(&self, storage: &dyn Storage, code: &String) -> StdResult<String> {
-      // Retrieve the code for the given user
-      match self.code_to_user.load(storage, &code) {
-         Ok(addr) => Ok(addr),
+   pub fn get_addr(&self, storage: &dyn Storage, code: &str) -> StdResult<String> {
+      match self.find_code(storage, code) {
+         Ok((_, addr)) => Ok(addr),
          Err(_) => Err(StdError::not_found("Addr")),
       }
    }
@@ -49,53 +156,156 @@ impl<'a> ReferralSM<'a> {
    pub fn add_referee(
       &self,
       storage: &mut dyn Storage,
-      referee: &String,
-      code: &String,
+      referee: &str,
+      code: &str,
    ) -> StdResult<()> {
-      let existing_code: Option<String> = self.code_to_user.may_load(storage, code)?;
-      if existing_code.is_none() {
+      let Ok((_, user)) = self.find_code(storage, code) else {
          return Err(StdError::generic_err("Code Not Found"));
-      }
+      };
 
       // Check if the referee is already added
-      let existing_user = self.referee_to_user.may_load(storage, referee.as_str())?;
+      let existing_user = self.referee_to_user.may_load(storage, referee)?;
       if existing_user.is_some() {
          return Err(StdError::generic_err("Referee already added"));
       }
 
       // Save the referee
-      self.referee_to_user.save(storage, referee.as_str(), &existing_code.unwrap())?;
+      self.referee_to_user.save(storage, referee, &user)?;
+      self.index_referee(storage, &user, referee)
+   }
+
+   /// Adds `referee` to the referees of `user`
+   pub fn index_referee(
+      &self,
+      storage: &mut dyn Storage,
+      user: &str,
+      referee: &str,
+   ) -> StdResult<()> {
+      self.user_to_referees.save(storage, (user, referee), &Empty {})?;
+      self
+         .referee_count
+         .update(storage, user, |count| -> StdResult<_> { Ok(count.unwrap_or_default() + 1) })?;
       Ok(())
    }
 
-   pub fn get_referrer(&self, storage: &dyn Storage, referee: &String) -> StdResult<String> {
+   pub fn get_referrer(&self, storage: &dyn Storage, referee: &str) -> StdResult<String> {
       // Retrieve the user who referred the given referee
-      Ok(self.referee_to_user.may_load(storage, referee.as_str())?.unwrap_or_default())
+      Ok(self.referee_to_user.may_load(storage, referee)?.unwrap_or_default())
    }
 
-   pub fn get_referral_struct(&self, storage: &dyn Storage, user: &str) -> StdResult<Vec<String>> {
-      let mut referees = Vec::new();
+   /// Referees of `user` ordered by address, a `None` limit takes every referee
+   pub fn get_referral_struct(
+      &self,
+      storage: &dyn Storage,
+      user: &str,
+      start_after: Option<&str>,
+      limit: Option<usize>,
+   ) -> StdResult<Vec<String>> {
+      self
+         .user_to_referees
+         .prefix(user)
+         .keys(storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+         .take(limit.unwrap_or(usize::MAX))
+         .collect()
+   }
 
-      let entries = self
-         .referee_to_user
-         .range(storage, None, None, Order::Ascending)
-         .collect::<StdResult<Vec<_>>>()?;
+   /// Referrers above `user`, from the direct one up to `max_depth` levels. The walk stops at the
+   /// first address already met, referral chains can loop back on themselves.
+   pub fn get_upline(
+      &self,
+      storage: &dyn Storage,
+      user: &str,
+      max_depth: u32,
+   ) -> StdResult<Vec<String>> {
+      let mut upline: Vec<String> = Vec::with_capacity(max_depth as usize);
+      let mut current = user.to_string();
+      while upline.len() < max_depth as usize {
+         let Some(referrer) = self.referee_to_user.may_load(storage, &current)? else {
+            break;
+         };
+         if referrer == user || upline.contains(&referrer) {
+            break;
+         }
+         upline.push(referrer.clone());
+         current = referrer;
+      }
+      Ok(upline)
+   }
 
-      for entry in entries {
-         let (referee, stored_user) = entry;
-         if stored_user == user {
-            referees.push(referee.to_string());
+   /// Shares `rewards` of `referrer` with its upline following `tiers`. The shares of levels
+   /// missing from the chain and the rounding stay with `referrer`.
+   pub fn split_tiers(
+      &self,
+      storage: &dyn Storage,
+      referrer: &str,
+      rewards: &[Coin],
+      tiers: &[Decimal],
+   ) -> StdResult<Vec<(String, u32, Vec<Coin>)>> {
+      let upline = self.get_upline(storage, referrer, tiers.len() as u32 - 1)?;
+      let mut split: Vec<(String, u32, Vec<Coin>)> =
+         vec![(referrer.to_string(), 1, Vec::with_capacity(rewards.len()))];
+      for (level, user) in upline.into_iter().enumerate() {
+         split.push((user, level as u32 + 2, Vec::with_capacity(rewards.len())));
+      }
+
+      for coin in rewards {
+         let mut shared = Uint128::zero();
+         for ((_, _, coins), tier) in split.iter_mut().zip(tiers).skip(1) {
+            let amount = coin.amount.mul_floor(*tier);
+            if !amount.is_zero() {
+               shared += amount;
+               coins.push(Coin::new(amount, &coin.denom));
+            }
+         }
+         let rest = coin.amount - shared;
+         if !rest.is_zero() {
+            split[0].2.push(Coin::new(rest, &coin.denom));
          }
       }
 
-      Ok(referees)
+      split.retain(|(_, _, coins)| !coins.is_empty());
+      Ok(split)
+   }
+
+   pub fn add_tier_earnings(
+      &self,
+      storage: &mut dyn Storage,
+      user: &str,
+      level: u32,
+      earned: &[Coin],
+   ) -> StdResult<()> {
+      for coin in earned {
+         self.tier_earnings.update(
+            storage,
+            (user, level, &coin.denom),
+            |total| -> StdResult<_> { Ok(total.unwrap_or_default() + coin.amount) },
+         )?;
+      }
+      Ok(())
+   }
+
+   pub fn get_tier_earnings(
+      &self,
+      storage: &dyn Storage,
+      user: &str,
+   ) -> StdResult<Vec<(u32, Vec<Coin>)>> {
+      let mut earnings: BTreeMap<u32, Vec<Coin>> = BTreeMap::new();
+      for item in self.tier_earnings.sub_prefix(user).range(storage, None, None, Order::Ascending) {
+         let ((level, denom), amount) = item?;
+         earnings.entry(level).or_default().push(Coin::new(amount, denom));
+      }
+      Ok(earnings.into_iter().collect())
+   }
+
+   pub fn get_referral_count(&self, storage: &dyn Storage, user: &str) -> StdResult<u64> {
+      Ok(self.referee_count.may_load(storage, user)?.unwrap_or_default())
    }
 }
 
 #[cfg(test)]
 mod test {
-   use super::ReferralSM;
-   use cosmwasm_std::testing::mock_dependencies;
+   use super::{normalize_code, ReferralSM};
+   use cosmwasm_std::{coin, coins, testing::mock_dependencies, Decimal};
 
    #[test]
    fn test_gen_code() {
@@ -109,7 +319,7 @@ mod test {
       referral_system.gen_code(&mut deps.storage, &user, &code).unwrap();
 
       // Attempt to generate a code for the same user should fail
-      let result = referral_system.gen_code(&mut deps.storage, &user, &"new_code".to_string());
+      let result = referral_system.gen_code(&mut deps.storage, &user, "new_code");
       assert!(result.is_err());
 
       // Verify the code is correct
@@ -117,6 +327,66 @@ mod test {
       assert_eq!(stored_code, code);
    }
 
+   #[test]
+   fn test_normalize_code() {
+      assert_eq!(normalize_code("Alice_42").unwrap(), "alice_42");
+      assert_eq!(normalize_code("a-b").unwrap(), "a-b");
+      assert_eq!(normalize_code(&"x".repeat(32)).unwrap(), "x".repeat(32));
+
+      normalize_code("ab").unwrap_err();
+      normalize_code(&"x".repeat(33)).unwrap_err();
+      normalize_code("with space").unwrap_err();
+      normalize_code("émoji").unwrap_err();
+      normalize_code("ADMIN").unwrap_err();
+   }
+
+   #[test]
+   fn test_code_lifecycle() {
+      let mut deps = mock_dependencies();
+      let referral_system = ReferralSM::new();
+      let (alice, bob) = ("alice".to_string(), "bob".to_string());
+
+      referral_system.gen_code(&mut deps.storage, &alice, "Alice").unwrap();
+      // codes are unique whatever the case
+      referral_system.gen_code(&mut deps.storage, &bob, "ALICE").unwrap_err();
+      referral_system.change_code(&mut deps.storage, &bob, "bob").unwrap_err();
+
+      // the previous code is retired by a change, its referees keep their referrer
+      referral_system.add_referee(&mut deps.storage, "r1", "alice").unwrap();
+      referral_system.change_code(&mut deps.storage, &alice, "alice2").unwrap();
+      assert_eq!(referral_system.get_code(&deps.storage, &alice).unwrap(), "alice2");
+      referral_system.get_addr(&deps.storage, "alice").unwrap_err();
+      referral_system.add_referee(&mut deps.storage, "r2", "alice").unwrap_err();
+      assert_eq!(referral_system.get_referrer(&deps.storage, "r1").unwrap(), alice);
+      referral_system.gen_code(&mut deps.storage, &bob, "alice").unwrap_err();
+      referral_system.change_code(&mut deps.storage, &alice, "alice").unwrap_err();
+      referral_system.gen_code(&mut deps.storage, &bob, "bob").unwrap();
+
+      // a revoked code stops taking referees and is never handed out again
+      referral_system.add_referee(&mut deps.storage, "r2", "ALICE2").unwrap();
+      referral_system.revoke_code(&mut deps.storage, &bob, "alice2").unwrap_err();
+      referral_system.revoke_code(&mut deps.storage, &alice, "alice2").unwrap();
+      referral_system.get_addr(&deps.storage, "alice2").unwrap_err();
+      referral_system.add_referee(&mut deps.storage, "r3", "alice2").unwrap_err();
+      assert_eq!(referral_system.get_referrer(&deps.storage, "r2").unwrap(), alice);
+
+      // revoking the current code frees the user for a new one
+      assert_eq!(referral_system.get_code(&deps.storage, &alice).unwrap(), "");
+      referral_system.gen_code(&mut deps.storage, &alice, "alice2").unwrap_err();
+      referral_system.gen_code(&mut deps.storage, &alice, "alice3").unwrap();
+
+      // only the owner transfers a code, to a user without one
+      let carol = "carol".to_string();
+      referral_system.transfer_code(&mut deps.storage, &bob, "alice3", &carol).unwrap_err();
+      referral_system.transfer_code(&mut deps.storage, &alice, "alice3", &bob).unwrap_err();
+      referral_system.transfer_code(&mut deps.storage, &alice, "alice3", &carol).unwrap();
+      assert_eq!(referral_system.get_code(&deps.storage, &alice).unwrap(), "");
+      assert_eq!(referral_system.get_code(&deps.storage, &carol).unwrap(), "alice3");
+      referral_system.add_referee(&mut deps.storage, "r4", "alice3").unwrap();
+      assert_eq!(referral_system.get_referrer(&deps.storage, "r4").unwrap(), carol);
+      assert_eq!(referral_system.get_referrer(&deps.storage, "r1").unwrap(), alice);
+   }
+
    #[test]
    fn test_add_referee() {
       let mut deps = mock_dependencies();
@@ -141,6 +411,91 @@ mod test {
       assert_eq!(stored_user, user);
    }
 
+   #[test]
+   fn test_get_upline() {
+      let mut deps = mock_dependencies();
+      let referral_system = ReferralSM::new();
+
+      // d -> c -> b -> a
+      for (user, code) in [("a", "AAA"), ("b", "BBB"), ("c", "CCC")] {
+         referral_system.gen_code(&mut deps.storage, user, code).unwrap();
+      }
+      referral_system.add_referee(&mut deps.storage, "b", "AAA").unwrap();
+      referral_system.add_referee(&mut deps.storage, "c", "BBB").unwrap();
+      referral_system.add_referee(&mut deps.storage, "d", "CCC").unwrap();
+
+      assert_eq!(referral_system.get_upline(&deps.storage, "d", 10).unwrap(), vec!["c", "b", "a"]);
+      assert_eq!(referral_system.get_upline(&deps.storage, "d", 2).unwrap(), vec!["c", "b"]);
+      assert_eq!(referral_system.get_upline(&deps.storage, "d", 0).unwrap(), Vec::<String>::new());
+      assert_eq!(referral_system.get_upline(&deps.storage, "a", 10).unwrap(), Vec::<String>::new());
+
+      // a joins with the code of c, closing the loop
+      referral_system.add_referee(&mut deps.storage, "a", "CCC").unwrap();
+      assert_eq!(referral_system.get_upline(&deps.storage, "d", 10).unwrap(), vec!["c", "b", "a"]);
+      assert_eq!(referral_system.get_upline(&deps.storage, "a", 10).unwrap(), vec!["c", "b"]);
+
+      // self referral
+      referral_system.gen_code(&mut deps.storage, "e", "EEE").unwrap();
+      referral_system.add_referee(&mut deps.storage, "e", "EEE").unwrap();
+      assert_eq!(referral_system.get_upline(&deps.storage, "e", 10).unwrap(), Vec::<String>::new());
+   }
+
+   #[test]
+   fn test_split_tiers() {
+      let mut deps = mock_dependencies();
+      let referral_system = ReferralSM::new();
+      let tiers = vec![Decimal::percent(70), Decimal::percent(20), Decimal::percent(10)];
+
+      for (user, code) in [("a", "AAA"), ("b", "BBB")] {
+         referral_system.gen_code(&mut deps.storage, user, code).unwrap();
+      }
+      referral_system.add_referee(&mut deps.storage, "b", "AAA").unwrap();
+
+      // no upline, everything stays with the referrer
+      let split = referral_system.split_tiers(&deps.storage, "a", &coins(100, "utoken"), &tiers);
+      assert_eq!(split.unwrap(), vec![("a".to_string(), 1, coins(100, "utoken"))]);
+
+      // missing third level goes to the referrer
+      let split =
+         referral_system.split_tiers(&deps.storage, "b", &coins(101, "utoken"), &tiers).unwrap();
+      assert_eq!(
+         split,
+         vec![("b".to_string(), 1, coins(81, "utoken")), ("a".to_string(), 2, coins(20, "utoken"))]
+      );
+
+      // full chain
+      referral_system.add_referee(&mut deps.storage, "c", "BBB").unwrap();
+      let rewards = vec![coin(1000, "utoken"), coin(10, "uother")];
+      let split = referral_system.split_tiers(&deps.storage, "c", &rewards, &tiers).unwrap();
+      assert_eq!(
+         split,
+         vec![
+            ("c".to_string(), 1, vec![coin(700, "utoken"), coin(7, "uother")]),
+            ("b".to_string(), 2, vec![coin(200, "utoken"), coin(2, "uother")]),
+            ("a".to_string(), 3, vec![coin(100, "utoken"), coin(1, "uother")]),
+         ]
+      );
+   }
+
+   #[test]
+   fn test_tier_earnings() {
+      let mut deps = mock_dependencies();
+      let referral_system = ReferralSM::new();
+
+      referral_system.add_tier_earnings(&mut deps.storage, "a", 2, &coins(10, "utoken")).unwrap();
+      referral_system.add_tier_earnings(&mut deps.storage, "a", 1, &coins(5, "utoken")).unwrap();
+      referral_system
+         .add_tier_earnings(&mut deps.storage, "a", 2, &[coin(10, "utoken"), coin(1, "uother")])
+         .unwrap();
+      referral_system.add_tier_earnings(&mut deps.storage, "b", 1, &coins(7, "utoken")).unwrap();
+
+      assert_eq!(
+         referral_system.get_tier_earnings(&deps.storage, "a").unwrap(),
+         vec![(1, coins(5, "utoken")), (2, vec![coin(1, "uother"), coin(20, "utoken")])]
+      );
+      assert!(referral_system.get_tier_earnings(&deps.storage, "c").unwrap().is_empty());
+   }
+
    #[test]
    fn test_get_referral_struct() {
       let mut deps = mock_dependencies();
@@ -159,7 +514,28 @@ mod test {
       referral_system.add_referee(&mut deps.storage, &referee2, &code).unwrap();
 
       // Get all referees for the user
-      let referees = referral_system.get_referral_struct(&deps.storage, &user).unwrap();
-      assert_eq!(referees, vec![referee1, referee2]);
+      let referees = referral_system.get_referral_struct(&deps.storage, &user, None, None).unwrap();
+      assert_eq!(referees, vec![referee1.clone(), referee2.clone()]);
+
+      // Referees of other users are skipped
+      referral_system.gen_code(&mut deps.storage, "user2", "other").unwrap();
+      referral_system.add_referee(&mut deps.storage, "referee0", "other").unwrap();
+      referral_system.add_referee(&mut deps.storage, "referee3", "other").unwrap();
+
+      // Paginate the referees
+      let page = referral_system.get_referral_struct(&deps.storage, &user, None, Some(1)).unwrap();
+      assert_eq!(page, vec![referee1.clone()]);
+      let page = referral_system
+         .get_referral_struct(&deps.storage, &user, Some(&referee1), Some(1))
+         .unwrap();
+      assert_eq!(page, vec![referee2.clone()]);
+      let page = referral_system
+         .get_referral_struct(&deps.storage, &user, Some(&referee2), Some(1))
+         .unwrap();
+      assert!(page.is_empty());
+
+      assert_eq!(referral_system.get_referral_count(&deps.storage, &user).unwrap(), 2);
+      assert_eq!(referral_system.get_referral_count(&deps.storage, "user2").unwrap(), 2);
+      assert_eq!(referral_system.get_referral_count(&deps.storage, "nobody").unwrap(), 0);
    }
 }
@@ -0,0 +1,122 @@
+use cosmwasm_std::{coins, Decimal};
+use cw2::{get_contract_version, set_contract_version};
+
+use cw_storage_plus::Item;
+
+use crate::{
+   contract::REFERRAL,
+   migrations::{ConfigV0_1, CONFIG_V0_1},
+   msg::*,
+   state::config::Config,
+};
+
+use super::{test_helpers::TestEnv, test_macros::define_test};
+
+/// Drops the state added after v0.1.0-rc1, the layouts listed in RELEASES.md
+fn downgrade_to_v0_1(env: &mut TestEnv) {
+   let mut storage = env.app.contract_storage_mut(&env.referral_addr);
+   REFERRAL.user_to_referees.clear(storage.as_mut());
+   REFERRAL.referee_count.clear(storage.as_mut());
+   let config: Config = Item::new("config").load(storage.as_ref()).unwrap();
+   CONFIG_V0_1
+      .save(
+         storage.as_mut(),
+         &ConfigV0_1 {
+            owner: config.owner,
+            whitelisted_denoms: config.whitelisted_denoms,
+            whitelisted_contracts: config.whitelisted_contracts,
+         },
+      )
+      .unwrap();
+   set_contract_version(storage.as_mut(), "referral", "0.1.0-rc1").unwrap();
+}
+
+fn set_legacy_code(env: &mut TestEnv, account: &str, code: &str) {
+   let user = env.addr(account).to_string();
+   let mut storage = env.app.contract_storage_mut(&env.referral_addr);
+   if let Some(current) = REFERRAL.user_to_code.may_load(storage.as_ref(), &user).unwrap() {
+      REFERRAL.code_to_user.remove(storage.as_mut(), &current);
+   }
+   REFERRAL.user_to_code.save(storage.as_mut(), &user, &code.to_string()).unwrap();
+   REFERRAL.code_to_user.save(storage.as_mut(), code, &user).unwrap();
+}
+
+fn set_version(env: &mut TestEnv, name: &str, version: &str) {
+   let mut storage = env.app.contract_storage_mut(&env.referral_addr);
+   set_contract_version(storage.as_mut(), name, version).unwrap();
+}
+
+define_test! {
+    name: test_migrate_from_v0_1,
+    config: {
+        owner: "owner",
+        whitelisted_contracts: Whitelist::All,
+        whitelisted_denoms: Whitelist::All
+    },
+    accounts: {
+        owner: coins(1000, "utoken"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.gen_code("alice", "CODE".to_string()).unwrap();
+        env.add_referee("owner", "bob", "CODE".to_string()).unwrap();
+        env.add_referee("owner", "charlie", "CODE".to_string()).unwrap();
+        env.distribute_rewards("owner", coins(100, "utoken"), vec![(env.addr("alice"), Decimal::one())]).unwrap();
+
+        downgrade_to_v0_1(env);
+        // codes were stored as given before v0.2, two of them can clash once folded
+        set_legacy_code(env, "alice", "CODE");
+        set_legacy_code(env, "bob", "BOB");
+        set_legacy_code(env, "charlie", "bob");
+        let storage = env.app.contract_storage(&env.referral_addr);
+        assert_eq!(REFERRAL.get_referral_count(storage.as_ref(), env.addr("alice").as_str()).unwrap(), 0);
+        drop(storage);
+
+        // only the admin can migrate
+        env.migrate("alice").unwrap_err();
+        env.migrate("owner").unwrap();
+
+        let version = get_contract_version(env.app.contract_storage(&env.referral_addr).as_ref()).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        // codes, referees and accrued rewards survive the migration
+        env.assert_code("alice", "code".to_string());
+        env.assert_code("bob", "BOB".to_string());
+        assert_eq!(env.get_addr("BOB").unwrap(), env.addr("bob").to_string());
+        assert_eq!(env.get_addr("bob").unwrap(), env.addr("charlie").to_string());
+        env.assert_pending_rewards("alice", coins(100, "utoken"));
+        env.claim_rewards("alice").unwrap();
+        env.add_referee("owner", "bob", "CODE".to_string()).unwrap_err();
+
+        // the referees index is backfilled
+        let mut referees = vec![env.addr("bob").to_string(), env.addr("charlie").to_string()];
+        referees.sort();
+        assert_eq!(env.referral_structure("alice", None, None), referees);
+        assert_eq!(env.referral_count("alice"), 2);
+
+        // rewards keep going to the direct referrer only
+        let config: Config = env.app.wrap().query_wasm_smart(&env.referral_addr, &QueryMsg::Config {}).unwrap();
+        assert_eq!(config.tiers, vec![Decimal::one()]);
+
+        env.add_referee("owner", "dave", "CODE".to_string()).unwrap();
+        assert_eq!(env.referral_count("alice"), 3);
+    }
+}
+
+define_test! {
+    name: test_migrate_refuses_downgrade,
+    config: {
+        owner: "owner",
+        whitelisted_contracts: Whitelist::All,
+        whitelisted_denoms: Whitelist::All
+    },
+    accounts: {
+        alice: coins(1000, "utoken"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        set_version(env, "referral", "99.0.0");
+        env.migrate("owner").unwrap_err();
+
+        set_version(env, "hitnrug", "0.1.0-rc1");
+        env.migrate("owner").unwrap_err();
+    }
+}
@@ -1,3 +1,4 @@
+mod migrations;
 mod test_cases;
 mod test_helpers;
 mod test_macros;
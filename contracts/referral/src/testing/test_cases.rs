@@ -16,7 +16,63 @@ define_test! {
     },
     test_fn: |env: &mut TestEnv| {
         env.gen_code("alice", "CODE".to_string()).unwrap();
-        env.assert_code("alice", "CODE".to_string());
+        env.assert_code("alice", "code".to_string());
+    }
+}
+
+define_test! {
+    name: test_code_lifecycle,
+    config: {
+        owner: "owner",
+        whitelisted_contracts: Whitelist::All,
+        whitelisted_denoms: Whitelist::All
+    },
+    accounts: {
+        owner: coins(1000, "utoken"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // invalid or taken codes
+        env.gen_code("alice", "ab".to_string()).unwrap_err();
+        env.gen_code("alice", "not valid".to_string()).unwrap_err();
+        env.gen_code("alice", "Owner".to_string()).unwrap_err();
+        env.gen_code("alice", "Alice".to_string()).unwrap();
+        env.gen_code("bob", "ALICE".to_string()).unwrap_err();
+        env.change_code("bob", "bob").unwrap_err();
+        env.gen_code("bob", "bob".to_string()).unwrap();
+
+        // old code is retired by a change, its referees keep their referrer
+        env.add_referee("owner", "charlie", "alice".to_string()).unwrap();
+        env.change_code("alice", "alice-2").unwrap();
+        env.assert_code("alice", "alice-2".to_string());
+        env.get_addr("alice").unwrap_err();
+        env.add_referee("owner", "dave", "ALICE".to_string()).unwrap_err();
+        env.add_referee("owner", "dave", "ALICE-2".to_string()).unwrap();
+        // and nobody else can take it over
+        env.gen_code("dave", "alice".to_string()).unwrap_err();
+        env.change_code("bob", "alice").unwrap_err();
+
+        // revoked codes stop taking referees, existing ones keep their referrer
+        env.revoke_code("bob", "alice-2").unwrap_err();
+        env.revoke_code("alice", "alice-2").unwrap();
+        env.get_addr("alice-2").unwrap_err();
+        env.add_referee("owner", "erin", "alice-2".to_string()).unwrap_err();
+        env.gen_code("erin", "alice-2".to_string()).unwrap_err();
+        assert_eq!(env.referral_count("alice"), 2);
+
+        // only the code owner transfers it, to an account without a code
+        env.gen_code("alice", "alice-3".to_string()).unwrap();
+        env.transfer_code("owner", "alice-3", "frank").unwrap_err();
+        env.transfer_code("alice", "alice-3", "bob").unwrap_err();
+        env.transfer_code("alice", "alice-3", "frank").unwrap();
+        env.assert_code("alice", "".to_string());
+        env.assert_code("frank", "alice-3".to_string());
+        env.add_referee("owner", "erin", "alice-3".to_string()).unwrap();
+        assert_eq!(env.referral_count("frank"), 1);
+        assert_eq!(env.referral_count("alice"), 2);
+
+        // nor can its previous owner take it back
+        env.gen_code("alice", "alice".to_string()).unwrap_err();
+        env.gen_code("alice", "alice-4".to_string()).unwrap();
     }
 }
 
@@ -38,6 +94,40 @@ define_test! {
     }
 }
 
+define_test! {
+    name: test_referral_structure,
+    config: {
+        owner: "owner",
+        whitelisted_contracts: Whitelist::All,
+        whitelisted_denoms: Whitelist::All
+    },
+    accounts: {
+        owner: coins(1000, "utoken"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        env.gen_code("alice", "ALICE".to_string()).unwrap();
+        env.gen_code("bob", "BOB".to_string()).unwrap();
+        for referee in ["charlie", "dave", "erin"] {
+            env.add_referee("owner", referee, "ALICE".to_string()).unwrap();
+        }
+        env.add_referee("owner", "frank", "BOB".to_string()).unwrap();
+
+        let mut referees: Vec<String> = ["charlie", "dave", "erin"].iter().map(|r| env.addr(r).to_string()).collect();
+        referees.sort();
+        assert_eq!(env.referral_structure("alice", None, None), referees);
+        assert_eq!(env.referral_structure("bob", None, None), vec![env.addr("frank").to_string()]);
+        assert_eq!(env.referral_count("alice"), 3);
+        assert_eq!(env.referral_count("bob"), 1);
+        assert_eq!(env.referral_count("frank"), 0);
+
+        // pages of two
+        let first = env.referral_structure("alice", None, Some(2));
+        assert_eq!(first, referees[..2]);
+        let second = env.referral_structure("alice", first.last().cloned(), Some(2));
+        assert_eq!(second, referees[2..]);
+    }
+}
+
 define_test! {
     name: test_update_config,
     config: {
@@ -53,6 +143,8 @@ define_test! {
             owner: Some(env.addr("new_owner")),
             whitelisted_contracts: Some(Whitelist::Some(vec![])),
             whitelisted_denoms: Some(Whitelist::Some(vec![])),
+            tiers: None,
+            max_depth: None,
         };
         env.update_config("owner", new_config).unwrap();
         //env.assert_config("new_owner".to_string(), Whitelist::Some(vec![]), Whitelist::Some(vec![]));
@@ -132,7 +224,7 @@ define_test! {
         bob: coins(500, "utoken"),
     },
     test_fn: |env: &mut TestEnv| {
-        env.update_config("owner", ConfigUpdate {whitelisted_contracts:Some(Whitelist::Some(vec![env.addr("alice").to_string()])), owner: None, whitelisted_denoms: None }).unwrap();
+        env.update_config("owner", ConfigUpdate {whitelisted_contracts:Some(Whitelist::Some(vec![env.addr("alice").to_string()])), owner: None, whitelisted_denoms: None, tiers: None, max_depth: None }).unwrap();
         // Alice should be able to add referee and distribute rewards
         env.gen_code("alice", "CODE".to_string()).unwrap();
         env.add_referee("alice", "bob", "CODE".to_string()).unwrap();
@@ -185,6 +277,8 @@ define_test! {
             owner: Some(env.addr("new_owner")),
             whitelisted_contracts: Some(Whitelist::All),
             whitelisted_denoms: Some(Whitelist::All),
+            tiers: None,
+            max_depth: None,
         };
         // Owner can update config
         env.update_config("owner", new_config.clone()).unwrap();
@@ -224,9 +318,105 @@ fn test_instantiate_msg_json() {
       owner: Addr::unchecked("owner_address"),
       whitelisted_denoms: Whitelist::All,
       whitelisted_contracts: Whitelist::All,
+      tiers: None,
+      max_depth: None,
    };
 
    // Serialize the instance to JSON and print it
    let json_instance = to_json_string(&instance).unwrap();
    println!("{}", json_instance);
 }
+
+define_test! {
+    name: test_multi_level_rewards,
+    config: {
+        owner: "owner",
+        whitelisted_contracts: Whitelist::All,
+        whitelisted_denoms: Whitelist::All
+    },
+    accounts: {
+        owner: coins(2000, "utoken"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // dave -> charlie -> bob -> alice
+        env.gen_code("alice", "ALICE".to_string()).unwrap();
+        env.gen_code("bob", "BOB".to_string()).unwrap();
+        env.gen_code("charlie", "CHARLIE".to_string()).unwrap();
+        env.add_referee("owner", "bob", "ALICE".to_string()).unwrap();
+        env.add_referee("owner", "charlie", "BOB".to_string()).unwrap();
+        env.add_referee("owner", "dave", "CHARLIE".to_string()).unwrap();
+
+        let upline: Vec<String> = ["charlie", "bob", "alice"].iter().map(|u| env.addr(u).to_string()).collect();
+        assert_eq!(env.upline("dave"), upline);
+
+        // invalid tiers are refused
+        let update = |tiers: Vec<Decimal>, max_depth: Option<u32>| ConfigUpdate {
+            owner: None,
+            whitelisted_contracts: None,
+            whitelisted_denoms: None,
+            tiers: Some(tiers),
+            max_depth,
+        };
+        env.update_config("owner", update(vec![Decimal::percent(70), Decimal::percent(20)], None)).unwrap_err();
+        env.update_config("owner", update(vec![Decimal::percent(25); 4], None)).unwrap_err();
+
+        env.update_config("owner", update(vec![Decimal::percent(70), Decimal::percent(20), Decimal::percent(10)], None)).unwrap();
+
+        // charlie brought dave, rewards flow up to alice
+        env.distribute_rewards("owner", coins(1000, "utoken"), vec![(env.addr("charlie"), Decimal::one())]).unwrap();
+        env.assert_pending_rewards("charlie", coins(700, "utoken"));
+        env.assert_pending_rewards("bob", coins(200, "utoken"));
+        env.assert_pending_rewards("alice", coins(100, "utoken"));
+
+        // bob has no third level, its share stays with him
+        env.distribute_rewards("owner", coins(100, "utoken"), vec![(env.addr("bob"), Decimal::one())]).unwrap();
+        env.assert_pending_rewards("bob", coins(280, "utoken"));
+        env.assert_pending_rewards("alice", coins(120, "utoken"));
+
+        assert_eq!(env.tier_earnings("bob"), vec![
+            TierEarnings { level: 1, earned: coins(80, "utoken") },
+            TierEarnings { level: 2, earned: coins(200, "utoken") },
+        ]);
+        assert_eq!(env.tier_earnings("alice"), vec![
+            TierEarnings { level: 2, earned: coins(20, "utoken") },
+            TierEarnings { level: 3, earned: coins(100, "utoken") },
+        ]);
+
+        // the upline query stops at max_depth
+        env.update_config("owner", update(vec![Decimal::one()], Some(1))).unwrap();
+        assert_eq!(env.upline("dave"), upline[..1]);
+    }
+}
+
+define_test! {
+    name: test_referral_loop,
+    config: {
+        owner: "owner",
+        whitelisted_contracts: Whitelist::All,
+        whitelisted_denoms: Whitelist::All
+    },
+    accounts: {
+        owner: coins(2000, "utoken"),
+    },
+    test_fn: |env: &mut TestEnv| {
+        // alice and bob referred each other
+        env.gen_code("alice", "ALICE".to_string()).unwrap();
+        env.gen_code("bob", "BOB".to_string()).unwrap();
+        env.add_referee("owner", "bob", "ALICE".to_string()).unwrap();
+        env.add_referee("owner", "alice", "BOB".to_string()).unwrap();
+
+        env.update_config("owner", ConfigUpdate {
+            owner: None,
+            whitelisted_contracts: None,
+            whitelisted_denoms: None,
+            tiers: Some(vec![Decimal::percent(50), Decimal::percent(30), Decimal::percent(20)]),
+            max_depth: None,
+        }).unwrap();
+
+        assert_eq!(env.upline("bob"), vec![env.addr("alice").to_string()]);
+
+        env.distribute_rewards("owner", coins(100, "utoken"), vec![(env.addr("bob"), Decimal::one())]).unwrap();
+        env.assert_pending_rewards("bob", coins(70, "utoken"));
+        env.assert_pending_rewards("alice", coins(30, "utoken"));
+    }
+}
@@ -3,6 +3,7 @@ use cosmwasm_std::{testing::MockApi, Addr, Coin, Decimal};
 use cw_multi_test::{App, AppResponse, BasicAppBuilder, ContractWrapper, Executor};
 use cw_utils::NativeBalance;
 
+#[allow(dead_code)]
 pub struct TestEnv {
    pub app: App,
    pub owner: Addr,
@@ -20,15 +21,25 @@ pub fn setup_test_env(
       }
    });
    let owner = app.api().addr_make("owner");
-   let referral_code_id = app.store_code(Box::new(ContractWrapper::new(
-      crate::contract::execute,
-      crate::contract::instantiate,
-      crate::contract::query,
-   )));
+   let referral_code_id = app.store_code(Box::new(
+      ContractWrapper::new(
+         crate::contract::execute,
+         crate::contract::instantiate,
+         crate::contract::query,
+      )
+      .with_migrate(crate::contract::migrate),
+   ));
 
    // Instantiate contract
    let referral_addr = app
-      .instantiate_contract(referral_code_id, owner.clone(), &instantiate_msg, &[], "rewards", None)
+      .instantiate_contract(
+         referral_code_id,
+         owner.clone(),
+         &instantiate_msg,
+         &[],
+         "rewards",
+         Some(owner.to_string()),
+      )
       .unwrap();
 
    TestEnv { app, owner, referral_addr, referral_code_id }
@@ -43,6 +54,8 @@ pub fn create_config(
       owner: MockApi::default().addr_make(owner),
       whitelisted_denoms,
       whitelisted_contracts,
+      tiers: None,
+      max_depth: None,
    }
 }
 
@@ -60,6 +73,45 @@ impl TestEnv {
       )
    }
 
+   pub fn change_code(&mut self, account: &str, code: &str) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.referral_addr.clone(),
+         &ExecuteMsg::ChangeCode { code: code.to_string() },
+         &[],
+      )
+   }
+
+   pub fn revoke_code(&mut self, account: &str, code: &str) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.referral_addr.clone(),
+         &ExecuteMsg::RevokeCode { code: code.to_string() },
+         &[],
+      )
+   }
+
+   pub fn transfer_code(
+      &mut self,
+      account: &str,
+      code: &str,
+      to: &str,
+   ) -> anyhow::Result<AppResponse> {
+      self.app.execute_contract(
+         self.addr(account),
+         self.referral_addr.clone(),
+         &ExecuteMsg::TransferCode { code: code.to_string(), to: self.addr(to) },
+         &[],
+      )
+   }
+
+   pub fn get_addr(&self, code: &str) -> anyhow::Result<String> {
+      Ok(self
+         .app
+         .wrap()
+         .query_wasm_smart(&self.referral_addr, &QueryMsg::GetAddr { code: code.to_string() })?)
+   }
+
    pub fn assert_code(&mut self, account: &str, code: String) {
       let res: String = self
          .app
@@ -84,6 +136,52 @@ impl TestEnv {
       )
    }
 
+   pub fn referral_structure(
+      &self,
+      account: &str,
+      start_after: Option<String>,
+      limit: Option<u32>,
+   ) -> Vec<String> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            &self.referral_addr,
+            &QueryMsg::ReferralStructure { user: self.addr(account), start_after, limit },
+         )
+         .unwrap()
+   }
+
+   pub fn referral_count(&self, account: &str) -> u64 {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            &self.referral_addr,
+            &QueryMsg::ReferralCount { user: self.addr(account) },
+         )
+         .unwrap()
+   }
+
+   pub fn upline(&self, account: &str) -> Vec<String> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(&self.referral_addr, &QueryMsg::Upline { user: self.addr(account) })
+         .unwrap()
+   }
+
+   pub fn tier_earnings(&self, account: &str) -> Vec<TierEarnings> {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            &self.referral_addr,
+            &QueryMsg::TierEarnings { user: self.addr(account) },
+         )
+         .unwrap()
+   }
+
    pub fn claim_rewards(&mut self, account: &str) -> anyhow::Result<AppResponse> {
       self.app.execute_contract(
          self.addr(account),
@@ -131,6 +229,15 @@ impl TestEnv {
       );
    }
 
+   pub fn migrate(&mut self, account: &str) -> anyhow::Result<AppResponse> {
+      self.app.migrate_contract(
+         self.addr(account),
+         self.referral_addr.clone(),
+         &MigrateMsg {},
+         self.referral_code_id,
+      )
+   }
+
    pub fn update_config(
       &mut self,
       account: &str,
@@ -0,0 +1,60 @@
+//! Polls the `NextAction {}` query of the games and sends the actions that are due, so rounds
+//! get settled without anyone calling `EndGame`. The chain is reached through [`Chain`], a bot
+//! implements it over its RPC client and calls [`Keeper::tick`] on a timer.
+
+use cosmwasm_std::{Addr, Timestamp};
+use wenruji_rs::keeper::{KeeperMsg, NextAction};
+
+#[cfg(test)]
+mod testing;
+
+pub trait Chain {
+   fn block_time(&self) -> anyhow::Result<Timestamp>;
+   fn next_action(&self, game: &Addr) -> anyhow::Result<NextAction>;
+   fn execute(&mut self, game: &Addr, msg: &KeeperMsg) -> anyhow::Result<()>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tick {
+   /// `msg` was executed on `game`
+   Sent { game: Addr, msg: KeeperMsg },
+   /// Nothing to do on `game` before `until`
+   Waiting { game: Addr, until: Timestamp },
+}
+
+pub struct Keeper {
+   games: Vec<Addr>,
+}
+
+impl Keeper {
+   pub fn new(games: Vec<Addr>) -> Self {
+      Self { games }
+   }
+
+   /// Sends the due action of every game. A failed query or execution is returned right away,
+   /// the next tick retries it.
+   pub fn tick(&self, chain: &mut impl Chain) -> anyhow::Result<Vec<Tick>> {
+      let now = chain.block_time()?;
+      let mut ticks = vec![];
+      for game in &self.games {
+         let action = chain.next_action(game)?;
+         if action.is_due(now) {
+            chain.execute(game, &action.msg)?;
+            ticks.push(Tick::Sent { game: game.clone(), msg: action.msg });
+         } else {
+            ticks.push(Tick::Waiting { game: game.clone(), until: action.at });
+         }
+      }
+      Ok(ticks)
+   }
+
+   /// Earliest time an action is due on any game, `None` without games
+   pub fn next_wakeup(&self, chain: &impl Chain) -> anyhow::Result<Option<Timestamp>> {
+      let mut wakeup: Option<Timestamp> = None;
+      for game in &self.games {
+         let at = chain.next_action(game)?.at;
+         wakeup = Some(wakeup.map_or(at, |wakeup| wakeup.min(at)));
+      }
+      Ok(wakeup)
+   }
+}
@@ -0,0 +1,2 @@
+mod test_cases;
+mod test_helpers;
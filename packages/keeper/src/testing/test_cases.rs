@@ -0,0 +1,60 @@
+use cosmwasm_std::{Timestamp, Uint128};
+use wenruji_rs::keeper::KeeperMsg;
+
+use super::test_helpers::setup_test_env;
+use crate::{Keeper, Tick};
+
+#[test]
+fn test_keeper_settles_rounds() {
+   let mut env = setup_test_env(&["alice", "bob"]);
+   let keeper = Keeper::new(vec![env.hitnrug.clone(), env.vault.clone()]);
+
+   // hitnrug only takes joins before its round starts
+   env.set_block(Timestamp::from_seconds(999));
+   env.join_hitnrug("alice");
+   env.join_hitnrug("bob");
+   env.set_block(Timestamp::from_seconds(1000));
+   env.join_vault("alice");
+
+   // nothing is due while the rounds are live
+   assert_eq!(
+      keeper.tick(&mut env).unwrap(),
+      vec![
+         Tick::Waiting { game: env.hitnrug.clone(), until: Timestamp::from_seconds(1601) },
+         Tick::Waiting { game: env.vault.clone(), until: Timestamp::from_seconds(2001) },
+      ]
+   );
+   assert_eq!(keeper.next_wakeup(&env).unwrap(), Some(Timestamp::from_seconds(1601)));
+
+   // hitnrug is settled and restarted, the keeper takes 1% of the 200 pot
+   env.set_block(Timestamp::from_seconds(1601));
+   assert_eq!(
+      keeper.tick(&mut env).unwrap(),
+      vec![
+         Tick::Sent { game: env.hitnrug.clone(), msg: KeeperMsg::Settle {} },
+         Tick::Waiting { game: env.vault.clone(), until: Timestamp::from_seconds(2001) },
+      ]
+   );
+   assert_eq!(env.hitnrug_index(), 2);
+   assert_eq!(env.balance(&env.keeper), Uint128::new(2));
+   assert_eq!(keeper.next_wakeup(&env).unwrap(), Some(Timestamp::from_seconds(2001)));
+
+   // the vault is left without a winner, its round is settled all the same
+   env.exit_vault("alice");
+   env.set_block(Timestamp::from_seconds(2001));
+   let ticks = keeper.tick(&mut env).unwrap();
+   assert_eq!(ticks[1], Tick::Sent { game: env.vault.clone(), msg: KeeperMsg::Settle {} });
+   assert!(!env.vault_joined("alice"));
+
+   // both games are waiting on their next round
+   let ticks = keeper.tick(&mut env).unwrap();
+   assert!(ticks.iter().all(|tick| matches!(tick, Tick::Waiting { .. })));
+}
+
+#[test]
+fn test_keeper_without_games() {
+   let mut env = setup_test_env(&[]);
+   let keeper = Keeper::new(vec![]);
+   assert_eq!(keeper.tick(&mut env).unwrap(), vec![]);
+   assert_eq!(keeper.next_wakeup(&env).unwrap(), None);
+}
@@ -0,0 +1,230 @@
+use cosmwasm_std::{coins, Addr, Decimal, Timestamp, Uint128};
+use cw_multi_test::{App, BasicAppBuilder, ContractWrapper, Executor};
+use wenruji_rs::keeper::{KeeperMsg, NextAction};
+
+use crate::Chain;
+
+pub const DENOM: &str = "denom";
+
+pub struct TestEnv {
+   pub app: App,
+   pub keeper: Addr,
+   pub hitnrug: Addr,
+   pub vault: Addr,
+}
+
+/// hitnrug runs from 1000 to 1600 and the vault from 1000 to 2000, both with a 1% settlement fee
+pub fn setup_test_env(players: &[&str]) -> TestEnv {
+   let mut app = BasicAppBuilder::new().build(|router, api, storage| {
+      for player in players {
+         router.bank.init_balance(storage, &api.addr_make(player), coins(1000, DENOM)).unwrap();
+      }
+   });
+   let owner = app.api().addr_make("owner");
+
+   let ref_code_id = app.store_code(Box::new(ContractWrapper::new(
+      referral::contract::execute,
+      referral::contract::instantiate,
+      referral::contract::query,
+   )));
+   let referral = app
+      .instantiate_contract(
+         ref_code_id,
+         owner.clone(),
+         &referral::InstantiateMsg {
+            owner: owner.clone(),
+            whitelisted_denoms: referral::msg::Whitelist::All,
+            whitelisted_contracts: referral::msg::Whitelist::All,
+            tiers: None,
+            max_depth: None,
+         },
+         &[],
+         "referral",
+         None,
+      )
+      .unwrap();
+
+   let hitnrug_code_id = app.store_code(Box::new(ContractWrapper::new(
+      hitnrug::contract::execute,
+      hitnrug::contract::instantiate,
+      hitnrug::contract::query,
+   )));
+   let hitnrug = app
+      .instantiate_contract(
+         hitnrug_code_id,
+         owner.clone(),
+         &hitnrug::msg::InstantiateMsg {
+            owner: owner.clone(),
+            ticket_denom: DENOM.to_string(),
+            ticket_amount: Uint128::new(100),
+            starts_at: Timestamp::from_seconds(1000),
+            duration_seconds: 600,
+            game_delay_sec: 10,
+            delay_play_seconds: 60,
+            fees: hitnrug::msg::Fees {
+               fee_platform: hitnrug::msg::Fee { address: owner.clone(), bp: Decimal::percent(10) },
+               fee_ref: hitnrug::msg::Fee { address: referral.clone(), bp: Decimal::percent(10) },
+            },
+            points: hitnrug::msg::Points {
+               keep: 4,
+               hit: -5,
+               help: hitnrug::msg::Point { myself: 6, other: 4 },
+               shield_seconds: 0,
+               steal: 0,
+               rug_bonus: Decimal::zero(),
+            },
+            decay_curve: None,
+            help_gate: None,
+            prize_table: None,
+            settlement_fee: Some(Decimal::percent(1)),
+            round_retention: None,
+            max_ticket_amount: None,
+            stake_weighted_points: None,
+            consolation_share: None,
+            action_rules: None,
+            teams: None,
+         },
+         &[],
+         "hitnrug",
+         None,
+      )
+      .unwrap();
+
+   let vault_code_id = app.store_code(Box::new(ContractWrapper::new(
+      crack_the_vault::contract::execute,
+      crack_the_vault::contract::instantiate,
+      crack_the_vault::contract::query,
+   )));
+   let vault = app
+      .instantiate_contract(
+         vault_code_id,
+         owner.clone(),
+         &crack_the_vault::msg::InstantiateMsg {
+            owner: owner.clone(),
+            ticket_denom: DENOM.to_string(),
+            ticket_amount: Uint128::new(100),
+            starts_at: Timestamp::from_seconds(1000),
+            duration_seconds: 1000,
+            game_delay: 0,
+            contracts: crack_the_vault::msg::Contracts {
+               swap: app.api().addr_make("fin"),
+               referral: referral.clone(),
+            },
+            donation_addrs: vec![],
+            admins: None,
+            fees: crack_the_vault::msg::Fees {
+               fee_platform: crack_the_vault::msg::Fee {
+                  address: owner.clone(),
+                  fee: Decimal::percent(10),
+               },
+               fee_nami: crack_the_vault::msg::Fee {
+                  address: owner.clone(),
+                  fee: Decimal::percent(10),
+               },
+               fee_ref: crack_the_vault::msg::Fee {
+                  address: referral.clone(),
+                  fee: Decimal::percent(10),
+               },
+            },
+            decay_curve: None,
+            winner_rule: None,
+            settlement_fee: Some(Decimal::percent(1)),
+            entry_denoms: None,
+            max_ticket_amount: None,
+         },
+         &[],
+         "vault",
+         None,
+      )
+      .unwrap();
+
+   let keeper = app.api().addr_make("keeper");
+   TestEnv { app, keeper, hitnrug, vault }
+}
+
+impl TestEnv {
+   pub fn addr(&self, account: &str) -> Addr {
+      self.app.api().addr_make(account)
+   }
+
+   pub fn set_block(&mut self, time: Timestamp) {
+      let mut new_block = self.app.block_info();
+      new_block.time = time;
+      self.app.set_block(new_block);
+   }
+
+   pub fn join_hitnrug(&mut self, account: &str) {
+      self
+         .app
+         .execute_contract(
+            self.addr(account),
+            self.hitnrug.clone(),
+            &hitnrug::msg::ExecuteMsg::Join { ref_code: None },
+            &coins(100, DENOM),
+         )
+         .unwrap();
+   }
+
+   pub fn join_vault(&mut self, account: &str) {
+      self
+         .app
+         .execute_contract(
+            self.addr(account),
+            self.vault.clone(),
+            &crack_the_vault::msg::ExecuteMsg::Join { ref_code: None, commitment: None },
+            &coins(100, DENOM),
+         )
+         .unwrap();
+   }
+
+   pub fn exit_vault(&mut self, account: &str) {
+      self
+         .app
+         .execute_contract(
+            self.addr(account),
+            self.vault.clone(),
+            &crack_the_vault::msg::ExecuteMsg::Exit {},
+            &[],
+         )
+         .unwrap();
+   }
+
+   pub fn hitnrug_index(&self) -> u64 {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(self.hitnrug.clone(), &hitnrug::msg::QueryMsg::GameIndex {})
+         .unwrap()
+   }
+
+   pub fn vault_joined(&self, account: &str) -> bool {
+      self
+         .app
+         .wrap()
+         .query_wasm_smart(
+            self.vault.clone(),
+            &crack_the_vault::msg::QueryMsg::HasJoined { player: self.addr(account) },
+         )
+         .unwrap()
+   }
+
+   pub fn balance(&self, account: &Addr) -> Uint128 {
+      self.app.wrap().query_balance(account, DENOM).unwrap().amount
+   }
+}
+
+impl Chain for TestEnv {
+   fn block_time(&self) -> anyhow::Result<Timestamp> {
+      Ok(self.app.block_info().time)
+   }
+
+   fn next_action(&self, game: &Addr) -> anyhow::Result<NextAction> {
+      // both games answer the same query
+      Ok(self.app.wrap().query_wasm_smart(game, &hitnrug::msg::QueryMsg::NextAction {})?)
+   }
+
+   fn execute(&mut self, game: &Addr, msg: &KeeperMsg) -> anyhow::Result<()> {
+      self.app.execute_contract(self.keeper.clone(), game.clone(), msg, &[])?;
+      Ok(())
+   }
+}
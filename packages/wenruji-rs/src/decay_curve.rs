@@ -0,0 +1,291 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Timestamp};
+
+use crate::DecayGameError;
+
+/// Highest exponent accepted by the sigmoid curve, above it the midpoint underflows to zero
+const MAX_SIGMOID_STEEPNESS: u32 = 32;
+
+/// Shape of the decay between `decay_starts_at` (factor 1) and `decay_ends_at` (factor 0).
+/// Every curve is monotonic non-increasing over time.
+#[cw_serde]
+#[derive(Default)]
+pub enum DecayCurve {
+   /// Constant decay from 1 to 0
+   #[default]
+   Linear,
+   /// Halves every `half_life_seconds`, interpolated linearly inside each half-life
+   Exponential { half_life_seconds: u64 },
+   /// Drops by `1 / steps` at the end of each of the `steps` equal tiers
+   Step { steps: u32 },
+   /// Linear between `(elapsed, factor)` breakpoints, with elapsed expressed as a fraction of
+   /// the decay duration. The curve always starts at `(0, 1)` and ends at `(1, 0)`.
+   PiecewiseLinear { breakpoints: Vec<(Decimal, Decimal)> },
+   /// Slow start and slow end, `(1 - t)^k / (t^k + (1 - t)^k)` with `k = steepness`
+   Sigmoid { steepness: u32 },
+}
+
+impl DecayCurve {
+   pub fn validate(&self) -> Result<(), DecayGameError> {
+      let valid = match self {
+         DecayCurve::Linear => true,
+         DecayCurve::Exponential { half_life_seconds } => *half_life_seconds > 0,
+         DecayCurve::Step { steps } => *steps > 0,
+         DecayCurve::PiecewiseLinear { breakpoints } => {
+            let mut prev = (Decimal::zero(), Decimal::one());
+            breakpoints.iter().all(|point| {
+               let valid = point.0 > prev.0 && point.0 < Decimal::one() && point.1 <= prev.1;
+               prev = *point;
+               valid
+            })
+         }
+         DecayCurve::Sigmoid { steepness } => *steepness > 0 && *steepness <= MAX_SIGMOID_STEEPNESS,
+      };
+
+      if !valid {
+         return Err(DecayGameError::Invalid("decay_curve".to_string()));
+      }
+      Ok(())
+   }
+
+   /// The amount of decay remaining at `now`
+   pub fn factor(
+      &self,
+      decay_starts_at: &Timestamp,
+      decay_ends_at: &Timestamp,
+      now: &Timestamp,
+   ) -> Decimal {
+      if now.le(decay_starts_at) {
+         return Decimal::one();
+      }
+      if now.gt(decay_ends_at) {
+         return Decimal::zero();
+      }
+      let elapsed = now.seconds() - decay_starts_at.seconds();
+      let duration = decay_ends_at.seconds() - decay_starts_at.seconds();
+
+      match self {
+         DecayCurve::Linear => Decimal::from_ratio(duration - elapsed, duration),
+         DecayCurve::Exponential { half_life_seconds } => {
+            let halvings = elapsed / half_life_seconds;
+            if halvings >= 64 {
+               return Decimal::zero();
+            }
+            let remainder = elapsed % half_life_seconds;
+            Decimal::percent(50).pow(halvings as u32)
+               * (Decimal::one() - Decimal::from_ratio(remainder, 2 * half_life_seconds))
+         }
+         DecayCurve::Step { steps } => {
+            let steps = *steps as u128;
+            let passed = elapsed as u128 * steps / duration as u128;
+            Decimal::one() - Decimal::from_ratio(passed, steps)
+         }
+         DecayCurve::PiecewiseLinear { breakpoints } => {
+            let t = Decimal::from_ratio(elapsed, duration);
+            let mut prev = (Decimal::zero(), Decimal::one());
+            for next in breakpoints.iter().chain([(Decimal::one(), Decimal::zero())].iter()) {
+               if t <= next.0 {
+                  let progress = (t - prev.0) / (next.0 - prev.0);
+                  return prev.1 - (prev.1 - next.1) * progress;
+               }
+               prev = *next;
+            }
+            Decimal::zero()
+         }
+         DecayCurve::Sigmoid { steepness } => {
+            let t = Decimal::from_ratio(elapsed, duration);
+            let remaining = (Decimal::one() - t).pow(*steepness);
+            let passed = t.pow(*steepness);
+            remaining / (remaining + passed)
+         }
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   const START: Timestamp = Timestamp::from_seconds(100);
+   const END: Timestamp = Timestamp::from_seconds(1100);
+
+   fn factor(curve: &DecayCurve, seconds: u64) -> Decimal {
+      curve.factor(&START, &END, &Timestamp::from_seconds(seconds))
+   }
+
+   fn curves() -> Vec<DecayCurve> {
+      vec![
+         DecayCurve::Linear,
+         DecayCurve::Exponential { half_life_seconds: 250 },
+         DecayCurve::Exponential { half_life_seconds: 1 },
+         DecayCurve::Step { steps: 4 },
+         DecayCurve::Step { steps: 1 },
+         DecayCurve::PiecewiseLinear { breakpoints: vec![] },
+         DecayCurve::PiecewiseLinear {
+            breakpoints: vec![
+               (Decimal::percent(10), Decimal::percent(90)),
+               (Decimal::percent(50), Decimal::percent(90)),
+               (Decimal::percent(60), Decimal::percent(20)),
+            ],
+         },
+         DecayCurve::Sigmoid { steepness: 1 },
+         DecayCurve::Sigmoid { steepness: 3 },
+         DecayCurve::Sigmoid { steepness: MAX_SIGMOID_STEEPNESS },
+      ]
+   }
+
+   #[test]
+   fn test_validate() {
+      for curve in curves() {
+         curve.validate().unwrap();
+      }
+
+      DecayCurve::Exponential { half_life_seconds: 0 }.validate().unwrap_err();
+      DecayCurve::Step { steps: 0 }.validate().unwrap_err();
+      DecayCurve::Sigmoid { steepness: 0 }.validate().unwrap_err();
+      DecayCurve::Sigmoid { steepness: MAX_SIGMOID_STEEPNESS + 1 }.validate().unwrap_err();
+
+      // elapsed must be strictly increasing inside (0, 1)
+      DecayCurve::PiecewiseLinear { breakpoints: vec![(Decimal::zero(), Decimal::one())] }
+         .validate()
+         .unwrap_err();
+      DecayCurve::PiecewiseLinear { breakpoints: vec![(Decimal::one(), Decimal::zero())] }
+         .validate()
+         .unwrap_err();
+      DecayCurve::PiecewiseLinear {
+         breakpoints: vec![
+            (Decimal::percent(50), Decimal::percent(50)),
+            (Decimal::percent(50), Decimal::percent(40)),
+         ],
+      }
+      .validate()
+      .unwrap_err();
+
+      // factor cannot increase or exceed one
+      DecayCurve::PiecewiseLinear {
+         breakpoints: vec![
+            (Decimal::percent(20), Decimal::percent(50)),
+            (Decimal::percent(40), Decimal::percent(60)),
+         ],
+      }
+      .validate()
+      .unwrap_err();
+      DecayCurve::PiecewiseLinear {
+         breakpoints: vec![(Decimal::percent(20), Decimal::percent(101))],
+      }
+      .validate()
+      .unwrap_err();
+   }
+
+   #[test]
+   fn test_start_and_end() {
+      for curve in curves() {
+         // before and at decay_starts_at => no decay
+         assert_eq!(factor(&curve, 0), Decimal::one(), "{curve:?}");
+         assert_eq!(factor(&curve, 100), Decimal::one(), "{curve:?}");
+         // after decay_ends_at => full decay
+         assert_eq!(factor(&curve, 1101), Decimal::zero(), "{curve:?}");
+         assert_eq!(factor(&curve, u64::MAX / 1_000_000_000), Decimal::zero(), "{curve:?}");
+         // first second of decay never exceeds one
+         assert!(factor(&curve, 101) <= Decimal::one(), "{curve:?}");
+      }
+   }
+
+   #[test]
+   fn test_monotonic() {
+      for curve in curves() {
+         let mut prev = Decimal::one();
+         for seconds in 0..1200 {
+            let current = factor(&curve, seconds);
+            assert!(current <= prev, "{curve:?} increases at {seconds}: {prev} -> {current}");
+            prev = current;
+         }
+      }
+   }
+
+   #[test]
+   fn test_linear() {
+      let curve = DecayCurve::Linear;
+      assert_eq!(factor(&curve, 350), Decimal::percent(75));
+      assert_eq!(factor(&curve, 600), Decimal::percent(50));
+      assert_eq!(factor(&curve, 1099), Decimal::permille(1));
+      assert_eq!(factor(&curve, 1100), Decimal::zero());
+   }
+
+   #[test]
+   fn test_exponential() {
+      let curve = DecayCurve::Exponential { half_life_seconds: 250 };
+      assert_eq!(factor(&curve, 350), Decimal::percent(50));
+      assert_eq!(factor(&curve, 600), Decimal::percent(25));
+      assert_eq!(factor(&curve, 850), Decimal::from_ratio(1u128, 8u128));
+      // end of the decay keeps the last halving
+      assert_eq!(factor(&curve, 1100), Decimal::from_ratio(1u128, 16u128));
+      // halfway through the first half-life
+      assert_eq!(factor(&curve, 225), Decimal::percent(75));
+
+      // very short half-life bottoms out at zero before the end
+      let curve = DecayCurve::Exponential { half_life_seconds: 1 };
+      assert_eq!(factor(&curve, 101), Decimal::percent(50));
+      assert_eq!(factor(&curve, 164), Decimal::zero());
+   }
+
+   #[test]
+   fn test_step() {
+      let curve = DecayCurve::Step { steps: 4 };
+      assert_eq!(factor(&curve, 349), Decimal::one());
+      assert_eq!(factor(&curve, 350), Decimal::percent(75));
+      assert_eq!(factor(&curve, 599), Decimal::percent(75));
+      assert_eq!(factor(&curve, 600), Decimal::percent(50));
+      assert_eq!(factor(&curve, 1099), Decimal::percent(25));
+      assert_eq!(factor(&curve, 1100), Decimal::zero());
+
+      let curve = DecayCurve::Step { steps: 1 };
+      assert_eq!(factor(&curve, 1099), Decimal::one());
+      assert_eq!(factor(&curve, 1100), Decimal::zero());
+   }
+
+   #[test]
+   fn test_piecewise_linear() {
+      let curve = DecayCurve::PiecewiseLinear {
+         breakpoints: vec![
+            (Decimal::percent(10), Decimal::percent(90)),
+            (Decimal::percent(50), Decimal::percent(90)),
+            (Decimal::percent(60), Decimal::percent(20)),
+         ],
+      };
+      assert_eq!(factor(&curve, 150), Decimal::percent(95));
+      assert_eq!(factor(&curve, 200), Decimal::percent(90));
+      assert_eq!(factor(&curve, 400), Decimal::percent(90));
+      assert_eq!(factor(&curve, 600), Decimal::percent(90));
+      assert_eq!(factor(&curve, 650), Decimal::percent(55));
+      assert_eq!(factor(&curve, 700), Decimal::percent(20));
+      assert_eq!(factor(&curve, 900), Decimal::percent(10));
+      assert_eq!(factor(&curve, 1100), Decimal::zero());
+
+      // no breakpoints is the linear curve
+      let curve = DecayCurve::PiecewiseLinear { breakpoints: vec![] };
+      assert_eq!(factor(&curve, 350), Decimal::percent(75));
+   }
+
+   #[test]
+   fn test_sigmoid() {
+      let curve = DecayCurve::Sigmoid { steepness: 1 };
+      assert_eq!(factor(&curve, 350), Decimal::percent(75));
+
+      let curve = DecayCurve::Sigmoid { steepness: 2 };
+      assert_eq!(factor(&curve, 600), Decimal::percent(50));
+      // 0.75^2 / (0.25^2 + 0.75^2)
+      assert_eq!(factor(&curve, 350), Decimal::percent(90));
+      assert_eq!(factor(&curve, 850), Decimal::percent(10));
+      assert_eq!(factor(&curve, 1100), Decimal::zero());
+   }
+
+   #[test]
+   fn test_zero_duration() {
+      let start = Timestamp::from_seconds(100);
+      for curve in curves() {
+         assert_eq!(curve.factor(&start, &start, &start), Decimal::one());
+         assert_eq!(curve.factor(&start, &start, &start.plus_seconds(1)), Decimal::zero());
+      }
+   }
+}
@@ -1,8 +1,9 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Decimal, Timestamp, Uint128};
-use std::ops::Sub;
 use thiserror::Error;
 
+use crate::DecayCurve;
+
 // Core Decay Game
 #[cw_serde]
 #[derive(Default)]
@@ -12,6 +13,8 @@ pub struct DecayGame {
    pub total: Uint128,
    pub exited: Uint128,
    pub rewards: Uint128,
+   #[serde(default)]
+   pub curve: DecayCurve,
 }
 
 impl DecayGame {
@@ -22,9 +25,15 @@ impl DecayGame {
          total: Uint128::zero(),
          exited: Uint128::zero(),
          rewards: Uint128::zero(),
+         curve: DecayCurve::Linear,
       }
    }
 
+   pub fn with_curve(mut self, curve: DecayCurve) -> Self {
+      self.curve = curve;
+      self
+   }
+
    pub fn validate(&self, now: &Timestamp) -> Result<(), DecayGameError> {
       if self.decay_starts_at.lt(now) {
          return Err(DecayGameError::Invalid("decay_starts_at".to_string()));
@@ -32,6 +41,7 @@ impl DecayGame {
       if self.decay_ends_at.lt(&self.decay_starts_at) {
          return Err(DecayGameError::Invalid("decay_ends_at".to_string()));
       }
+      self.curve.validate()?;
 
       Ok(())
    }
@@ -46,18 +56,89 @@ impl DecayGame {
       }
       self.total += amount;
 
-      Ok(DecayGameAccount { amount, decay_snapshot: Decimal::zero(), pending: Uint128::zero() })
+      Ok(DecayGameAccount { amount, ..Default::default() })
+   }
+
+   /// Adds `amount` to the stake of `account`, only before the decay starts
+   pub fn top_up(
+      &mut self,
+      amount: Uint128,
+      now: &Timestamp,
+      account: &mut DecayGameAccount,
+   ) -> Result<(), DecayGameError> {
+      if now.ge(&self.decay_starts_at) {
+         return Err(DecayGameError::Invalid("decay_started".to_string()));
+      }
+      account.ensure_active()?;
+      self.total += amount;
+      account.amount += amount;
+      Ok(())
+   }
+
+   pub fn exit(
+      &mut self,
+      now: &Timestamp,
+      account: &mut DecayGameAccount,
+   ) -> Result<(), DecayGameError> {
+      self.exit_with_bonus(now, account, Decimal::zero())
    }
 
-   pub fn exit(&mut self, now: &Timestamp, account: &mut DecayGameAccount) -> () {
-      let factor = decay_factor(&self, now);
+   /// Exits at the current decay factor raised by `bonus`, capped at 1
+   pub fn exit_with_bonus(
+      &mut self,
+      now: &Timestamp,
+      account: &mut DecayGameAccount,
+      bonus: Decimal,
+   ) -> Result<(), DecayGameError> {
+      account.ensure_active()?;
+      let factor = (self.decay_factor(now) + bonus).min(Decimal::one());
       let pending = account.amount.mul_floor(factor);
 
       self.exited += pending;
 
-      account.pending = pending;
+      account.pending += pending;
 
       account.decay_snapshot = factor;
+      account.status = AccountStatus::Exited { at: *now };
+      Ok(())
+   }
+
+   /// Leaves the whole stake of `account` to the pool
+   pub fn forfeit(
+      &mut self,
+      now: &Timestamp,
+      account: &mut DecayGameAccount,
+   ) -> Result<(), DecayGameError> {
+      account.ensure_active()?;
+      account.status = AccountStatus::Forfeited { at: *now };
+      Ok(())
+   }
+
+   /// Takes `amount` of the stake out at the current decay factor and keeps the rest in play
+   pub fn exit_partial(
+      &mut self,
+      now: &Timestamp,
+      account: &mut DecayGameAccount,
+      amount: Uint128,
+   ) -> Result<(), DecayGameError> {
+      account.ensure_active()?;
+      if amount.is_zero() || amount >= account.amount {
+         return Err(DecayGameError::Invalid("exit_amount".to_string()));
+      }
+      let factor = self.decay_factor(now);
+      let paid = amount.mul_floor(factor);
+
+      self.exited += paid;
+
+      account.amount -= amount;
+      account.pending += paid;
+      account.partial_exits.push(PartialExit { amount, decay_snapshot: factor, paid, at: *now });
+      Ok(())
+   }
+
+   /// The amount of decay remaining following the game curve
+   pub fn decay_factor(&self, now: &Timestamp) -> Decimal {
+      self.curve.factor(&self.decay_starts_at, &self.decay_ends_at, now)
    }
 
    pub fn pending_claims(&self, account: &DecayGameAccount) -> Uint128 {
@@ -71,6 +152,9 @@ impl DecayGame {
    pub fn claim(&mut self, account: &mut DecayGameAccount) -> Uint128 {
       let amount = self.pending_claims(account);
       account.pending = Uint128::zero();
+      if let AccountStatus::Exited { at } = account.status {
+         account.status = AccountStatus::Claimed { at };
+      }
       amount
    }
 
@@ -79,13 +163,11 @@ impl DecayGame {
          return Err(DecayGameError::DecayNotEnded {});
       }
       match self.total - self.exited - self.rewards == Uint128::zero() {
-         true => {
-            return Err(DecayGameError::NoRewards {});
-         }
+         true => Err(DecayGameError::NoRewards {}),
 
          false => {
             self.rewards = self.total - self.exited;
-            return Ok(self.rewards);
+            Ok(self.rewards)
          }
       }
    }
@@ -94,9 +176,70 @@ impl DecayGame {
 #[cw_serde]
 #[derive(Default)]
 pub struct DecayGameAccount {
+   /// Stake still in play
    pub amount: Uint128,
+   /// Decay factor of the full exit, zero while the account is in play
    pub decay_snapshot: Decimal,
    pub pending: Uint128,
+   /// Parts of the stake taken out before the full exit
+   #[serde(default)]
+   pub partial_exits: Vec<PartialExit>,
+   #[serde(default)]
+   pub status: AccountStatus,
+}
+
+/// Where an account stands in the game, `at` being the time it left
+#[cw_serde]
+#[derive(Default)]
+pub enum AccountStatus {
+   /// In play
+   #[default]
+   Joined,
+   /// Exited with its claim still pending
+   Exited { at: Timestamp },
+   /// Exited and paid
+   Claimed { at: Timestamp },
+   /// Left its stake to the pool
+   Forfeited { at: Timestamp },
+}
+
+impl DecayGameAccount {
+   /// Whether the account is still in play, whatever its exit factor
+   pub fn is_active(&self) -> bool {
+      self.status == AccountStatus::Joined
+   }
+
+   /// Time the account left the game
+   pub fn exited_at(&self) -> Option<Timestamp> {
+      match self.status {
+         AccountStatus::Joined => None,
+         AccountStatus::Exited { at }
+         | AccountStatus::Claimed { at }
+         | AccountStatus::Forfeited { at } => Some(at),
+      }
+   }
+
+   fn ensure_active(&self) -> Result<(), DecayGameError> {
+      if !self.is_active() {
+         return Err(DecayGameError::Invalid("already_exited".to_string()));
+      }
+      Ok(())
+   }
+
+   /// Stake joined with, including the parts already taken out
+   pub fn principal(&self) -> Uint128 {
+      self.amount + self.partial_exits.iter().map(|exit| exit.amount).sum::<Uint128>()
+   }
+}
+
+#[cw_serde]
+pub struct PartialExit {
+   /// Stake taken out
+   pub amount: Uint128,
+   pub decay_snapshot: Decimal,
+   /// `amount` at `decay_snapshot`
+   pub paid: Uint128,
+   pub at: Timestamp,
 }
 
 #[derive(Error, Debug)]
@@ -111,21 +254,6 @@ pub enum DecayGameError {
    Invalid(String),
 }
 
-/// The amount of decay remaining in a linear model
-fn decay_factor(equilibria_pool: &DecayGame, now: &Timestamp) -> Decimal {
-   if now.le(&equilibria_pool.decay_starts_at) {
-      return Decimal::one();
-   }
-   if now.gt(&equilibria_pool.decay_ends_at) {
-      return Decimal::zero();
-   }
-   let remaning = equilibria_pool.decay_ends_at.seconds().sub(now.seconds());
-   let duration =
-      equilibria_pool.decay_ends_at.seconds().sub(equilibria_pool.decay_starts_at.seconds());
-
-   Decimal::from_ratio(remaning, duration)
-}
-
 #[cfg(test)]
 mod tests {
 
@@ -150,6 +278,11 @@ mod tests {
       decay_ends_at = Timestamp::from_seconds(1002);
       let pool = DecayGame::new(decay_starts_at, decay_ends_at);
       pool.validate(&now).unwrap();
+
+      // invalid curve => Error
+      let pool =
+         DecayGame::new(decay_starts_at, decay_ends_at).with_curve(DecayCurve::Step { steps: 0 });
+      pool.validate(&now).unwrap_err();
    }
 
    #[test]
@@ -168,7 +301,7 @@ mod tests {
       assert_eq!(account.decay_snapshot, Decimal::zero());
 
       //No decay now is still before the start date
-      pool.exit(&now, &mut account);
+      pool.exit(&now, &mut account).unwrap();
       assert_eq!(pool.exited, Uint128::from(100u128));
       assert_eq!(pool.pending_rewards(), Uint128::zero());
       assert_eq!(account.amount, Uint128::from(100u128));
@@ -185,7 +318,7 @@ mod tests {
       assert_eq!(pool.total, Uint128::from(200u128));
 
       now = Timestamp::from_seconds(550);
-      pool.exit(&now, &mut account);
+      pool.exit(&now, &mut account).unwrap();
       assert_eq!(account.amount, Uint128::from(100u128));
       assert_eq!(account.pending, Uint128::from(50u128));
       assert_eq!(account.decay_snapshot, Decimal::from_ratio(Uint128::one(), Uint128::from(2u128)));
@@ -205,7 +338,7 @@ mod tests {
       now = Timestamp::from_seconds(2000);
 
       // exit after end => decay 0
-      pool.exit(&now, &mut account);
+      pool.exit(&now, &mut account).unwrap();
       assert_eq!(account.amount, Uint128::from(100u128));
       assert_eq!(account.pending, Uint128::zero());
       assert_eq!(account.decay_snapshot, Decimal::zero());
@@ -219,4 +352,147 @@ mod tests {
       //join after end => err
       pool.join(Uint128::from(100u128), &now).unwrap_err();
    }
+
+   #[test]
+   fn exit_with_curve() {
+      let decay_starts_at = Timestamp::from_seconds(100);
+      let decay_ends_at = Timestamp::from_seconds(1100);
+
+      let mut pool = DecayGame::new(decay_starts_at, decay_ends_at)
+         .with_curve(DecayCurve::Exponential { half_life_seconds: 250 });
+      pool.validate(&Timestamp::from_seconds(1)).unwrap();
+
+      let mut account = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+      let mut other = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+
+      // two half-lives
+      pool.exit(&Timestamp::from_seconds(600), &mut account).unwrap();
+      assert_eq!(account.decay_snapshot, Decimal::percent(25));
+      assert_eq!(account.pending, Uint128::from(25u128));
+
+      // exponential keeps a remainder at the end, nothing after it
+      pool.exit(&Timestamp::from_seconds(1101), &mut other).unwrap();
+      assert_eq!(other.pending, Uint128::zero());
+      assert_eq!(pool.pending_rewards(), Uint128::from(175u128));
+   }
+
+   #[test]
+   fn top_up() {
+      let mut pool = DecayGame::new(Timestamp::from_seconds(100), Timestamp::from_seconds(1100));
+      let mut account = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+      let mut other = pool.join(Uint128::from(50u128), &Timestamp::from_seconds(1)).unwrap();
+
+      pool.top_up(Uint128::from(100u128), &Timestamp::from_seconds(99), &mut account).unwrap();
+      assert_eq!(account.amount, Uint128::from(200u128));
+      assert_eq!(pool.total, Uint128::from(250u128));
+
+      // no top-up once the decay started
+      pool.top_up(Uint128::from(100u128), &Timestamp::from_seconds(100), &mut other).unwrap_err();
+
+      // exits are pro-rata to the stake
+      pool.exit(&Timestamp::from_seconds(600), &mut account).unwrap();
+      pool.exit(&Timestamp::from_seconds(600), &mut other).unwrap();
+      assert_eq!(account.pending, Uint128::from(100u128));
+      assert_eq!(other.pending, Uint128::from(25u128));
+      assert_eq!(pool.pending_rewards(), Uint128::from(125u128));
+
+      // exited accounts cannot top up
+      let mut pool = DecayGame::new(Timestamp::from_seconds(100), Timestamp::from_seconds(1100));
+      let mut account = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+      pool.exit(&Timestamp::from_seconds(1), &mut account).unwrap();
+      pool.top_up(Uint128::from(100u128), &Timestamp::from_seconds(2), &mut account).unwrap_err();
+   }
+
+   #[test]
+   fn exit_partial() {
+      let mut pool = DecayGame::new(Timestamp::from_seconds(100), Timestamp::from_seconds(1100));
+      let mut account = pool.join(Uint128::from(200u128), &Timestamp::from_seconds(1)).unwrap();
+      let mut other = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+
+      // the whole stake or nothing is not a partial exit
+      let now = Timestamp::from_seconds(350);
+      pool.exit_partial(&now, &mut account, Uint128::zero()).unwrap_err();
+      pool.exit_partial(&now, &mut account, Uint128::from(200u128)).unwrap_err();
+
+      pool.exit_partial(&now, &mut account, Uint128::from(100u128)).unwrap();
+      assert_eq!(account.amount, Uint128::from(100u128));
+      assert_eq!(account.decay_snapshot, Decimal::zero());
+      assert_eq!(pool.claim(&mut account), Uint128::from(75u128));
+
+      pool
+         .exit_partial(&Timestamp::from_seconds(600), &mut account, Uint128::from(40u128))
+         .unwrap();
+      assert_eq!(account.amount, Uint128::from(60u128));
+      assert_eq!(account.principal(), Uint128::from(200u128));
+      assert_eq!(
+         account.partial_exits[1],
+         PartialExit {
+            amount: Uint128::from(40u128),
+            decay_snapshot: Decimal::percent(50),
+            paid: Uint128::from(20u128),
+            at: Timestamp::from_seconds(600),
+         }
+      );
+
+      // the rest of the stake exits at the later factor
+      pool.exit(&Timestamp::from_seconds(850), &mut account).unwrap();
+      assert_eq!(pool.claim(&mut account), Uint128::from(35u128));
+      pool.exit_partial(&Timestamp::from_seconds(850), &mut account, Uint128::one()).unwrap_err();
+
+      // the pool keeps everything that was not paid out
+      pool.exit(&Timestamp::from_seconds(1000), &mut other).unwrap();
+      assert_eq!(pool.exited, Uint128::from(75u128 + 20 + 15 + 10));
+      assert_eq!(pool.pending_rewards(), Uint128::from(300u128 - 120));
+   }
+
+   #[test]
+   fn exit_status() {
+      let mut pool = DecayGame::new(Timestamp::from_seconds(100), Timestamp::from_seconds(1100));
+      let mut account = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+      let mut other = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+      assert_eq!(account.status, AccountStatus::Joined);
+      assert!(account.is_active());
+
+      // an exit at a zero factor leaves a zero snapshot but still counts
+      pool.exit(&Timestamp::from_seconds(1100), &mut account).unwrap();
+      assert!(account.decay_snapshot.is_zero());
+      assert_eq!(account.status, AccountStatus::Exited { at: Timestamp::from_seconds(1100) });
+      assert_eq!(account.exited_at(), Some(Timestamp::from_seconds(1100)));
+      pool.exit(&Timestamp::from_seconds(1100), &mut account).unwrap_err();
+      pool.exit_partial(&Timestamp::from_seconds(1100), &mut account, Uint128::one()).unwrap_err();
+
+      assert_eq!(pool.claim(&mut account), Uint128::zero());
+      assert_eq!(account.status, AccountStatus::Claimed { at: Timestamp::from_seconds(1100) });
+
+      // partial exits keep the account in play
+      pool.exit_partial(&Timestamp::from_seconds(600), &mut other, Uint128::from(50u128)).unwrap();
+      assert_eq!(other.partial_exits[0].at, Timestamp::from_seconds(600));
+      assert_eq!(pool.claim(&mut other), Uint128::from(25u128));
+      assert!(other.is_active());
+
+      pool.forfeit(&Timestamp::from_seconds(700), &mut other).unwrap();
+      assert_eq!(other.status, AccountStatus::Forfeited { at: Timestamp::from_seconds(700) });
+      pool.exit(&Timestamp::from_seconds(800), &mut other).unwrap_err();
+      pool.forfeit(&Timestamp::from_seconds(800), &mut other).unwrap_err();
+   }
+
+   #[test]
+   fn exit_with_bonus() {
+      let mut pool = DecayGame::new(Timestamp::from_seconds(100), Timestamp::from_seconds(1100));
+      let mut account = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+      let mut other = pool.join(Uint128::from(100u128), &Timestamp::from_seconds(1)).unwrap();
+
+      pool
+         .exit_with_bonus(&Timestamp::from_seconds(600), &mut account, Decimal::percent(20))
+         .unwrap();
+      assert_eq!(account.decay_snapshot, Decimal::percent(70));
+      assert_eq!(account.pending, Uint128::from(70u128));
+
+      // the factor never goes above 1
+      pool
+         .exit_with_bonus(&Timestamp::from_seconds(600), &mut other, Decimal::percent(80))
+         .unwrap();
+      assert_eq!(other.decay_snapshot, Decimal::one());
+      assert_eq!(pool.pending_rewards(), Uint128::from(30u128));
+   }
 }
@@ -0,0 +1,38 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Timestamp;
+
+/// Messages a keeper sends to a game, serialized like the variants of the game `ExecuteMsg`
+#[cw_serde]
+pub enum KeeperMsg {
+   /// Settles the finished round and starts the next one
+   Settle {},
+}
+
+/// Returned by the `NextAction {}` query of the games
+#[cw_serde]
+pub struct NextAction {
+   /// First block time `msg` can be executed at
+   pub at: Timestamp,
+   pub msg: KeeperMsg,
+}
+
+impl NextAction {
+   pub fn is_due(&self, now: Timestamp) -> bool {
+      now >= self.at
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use cosmwasm_std::to_json_string;
+
+   use super::*;
+
+   #[test]
+   fn test_next_action() {
+      let action = NextAction { at: Timestamp::from_seconds(100), msg: KeeperMsg::Settle {} };
+      assert!(!action.is_due(Timestamp::from_seconds(99)));
+      assert!(action.is_due(Timestamp::from_seconds(100)));
+      assert_eq!(to_json_string(&action.msg).unwrap(), r#"{"settle":{}}"#);
+   }
+}
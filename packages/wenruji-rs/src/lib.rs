@@ -1,9 +1,15 @@
+mod decay_curve;
 mod decay_game;
+pub mod keeper;
+mod player_stats;
+pub mod randomness;
 mod rewards;
 mod rewards_util;
 mod utils;
 
-pub use decay_game::{DecayGame, DecayGameAccount, DecayGameError};
+pub use decay_curve::DecayCurve;
+pub use decay_game::{AccountStatus, DecayGame, DecayGameAccount, DecayGameError, PartialExit};
+pub use player_stats::{PlayerStats, PlayerStatsSM};
 pub use rewards::{RewardInfo, RewardsSM};
 pub use rewards_util::*;
 pub use utils::*;
@@ -0,0 +1,177 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Decimal, StdResult, Storage, Uint128};
+use cw_storage_plus::Map;
+
+use crate::normalize;
+
+/// Lifetime statistics of an account, kept across rounds
+#[cw_serde]
+#[derive(Default)]
+pub struct PlayerStats {
+   pub rounds_joined: u64,
+   pub rounds_exited: u64,
+   /// Sum of the decay snapshots of every exit
+   pub exit_decay_sum: Decimal,
+   /// Average share of the ticket taken back on exit
+   pub avg_exit_decay: Decimal,
+   /// Tickets paid, in the ticket denom
+   pub paid_in: Uint128,
+   /// Claimed on exit, in the ticket denom
+   pub paid_out: Uint128,
+   /// Prizes won at settlement
+   pub prizes: Vec<Coin>,
+   /// Rounds finished in first place
+   pub wins: u64,
+   pub hits_given: u64,
+   pub hits_received: u64,
+}
+
+pub struct PlayerStatsSM<'a> {
+   pub stats: Map<&'a Addr, PlayerStats>,
+}
+
+impl<'a> PlayerStatsSM<'a> {
+   pub const fn new() -> Self {
+      Self { stats: Map::new("pst") }
+   }
+
+   /// Stats of `user`, zeroed for unknown accounts
+   pub fn load(&self, storage: &dyn Storage, user: &Addr) -> StdResult<PlayerStats> {
+      Ok(self.stats.may_load(storage, user)?.unwrap_or_default())
+   }
+
+   fn update(
+      &self,
+      storage: &mut dyn Storage,
+      user: &Addr,
+      action: impl FnOnce(&mut PlayerStats),
+   ) -> StdResult<()> {
+      let mut stats = self.load(storage, user)?;
+      action(&mut stats);
+      self.stats.save(storage, user, &stats)
+   }
+
+   pub fn record_join(
+      &self,
+      storage: &mut dyn Storage,
+      user: &Addr,
+      amount: Uint128,
+   ) -> StdResult<()> {
+      self.update(storage, user, |stats| {
+         stats.rounds_joined += 1;
+         stats.paid_in += amount;
+      })
+   }
+
+   /// Stake added to the position of the current round
+   pub fn record_top_up(
+      &self,
+      storage: &mut dyn Storage,
+      user: &Addr,
+      amount: Uint128,
+   ) -> StdResult<()> {
+      self.update(storage, user, |stats| stats.paid_in += amount)
+   }
+
+   pub fn record_exit(
+      &self,
+      storage: &mut dyn Storage,
+      user: &Addr,
+      amount: Uint128,
+      decay_snapshot: Decimal,
+   ) -> StdResult<()> {
+      self.update(storage, user, |stats| {
+         stats.rounds_exited += 1;
+         stats.paid_out += amount;
+         stats.exit_decay_sum += decay_snapshot;
+         stats.avg_exit_decay =
+            stats.exit_decay_sum / Decimal::from_ratio(stats.rounds_exited, 1u64);
+      })
+   }
+
+   /// Part of the stake taken out, the round is not counted as exited
+   pub fn record_partial_exit(
+      &self,
+      storage: &mut dyn Storage,
+      user: &Addr,
+      amount: Uint128,
+   ) -> StdResult<()> {
+      self.update(storage, user, |stats| stats.paid_out += amount)
+   }
+
+   pub fn record_win(&self, storage: &mut dyn Storage, user: &Addr) -> StdResult<()> {
+      self.update(storage, user, |stats| stats.wins += 1)
+   }
+
+   pub fn record_prize(
+      &self,
+      storage: &mut dyn Storage,
+      user: &Addr,
+      prize: Vec<Coin>,
+   ) -> StdResult<()> {
+      self.update(storage, user, |stats| {
+         stats.prizes = normalize(stats.prizes.drain(..).chain(prize).collect());
+      })
+   }
+
+   pub fn record_hit(
+      &self,
+      storage: &mut dyn Storage,
+      hitter: &Addr,
+      target: &Addr,
+   ) -> StdResult<()> {
+      self.update(storage, hitter, |stats| stats.hits_given += 1)?;
+      self.update(storage, target, |stats| stats.hits_received += 1)
+   }
+}
+
+impl<'a> Default for PlayerStatsSM<'a> {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use cosmwasm_std::{coin, coins, testing::mock_dependencies};
+
+   use super::*;
+
+   #[test]
+   fn test_player_stats() {
+      let mut deps = mock_dependencies();
+      let state = PlayerStatsSM::new();
+      let alice = Addr::unchecked("alice");
+      let bob = Addr::unchecked("bob");
+
+      assert_eq!(state.load(&deps.storage, &alice).unwrap(), PlayerStats::default());
+
+      state.record_join(&mut deps.storage, &alice, Uint128::new(100)).unwrap();
+      state.record_exit(&mut deps.storage, &alice, Uint128::new(50), Decimal::percent(50)).unwrap();
+      state.record_join(&mut deps.storage, &alice, Uint128::new(100)).unwrap();
+      state.record_partial_exit(&mut deps.storage, &alice, Uint128::new(10)).unwrap();
+      state.record_exit(&mut deps.storage, &alice, Uint128::new(15), Decimal::percent(25)).unwrap();
+      state.record_join(&mut deps.storage, &alice, Uint128::new(100)).unwrap();
+      state.record_top_up(&mut deps.storage, &alice, Uint128::new(50)).unwrap();
+      state.record_win(&mut deps.storage, &alice).unwrap();
+      state.record_prize(&mut deps.storage, &alice, coins(80, "ukuji")).unwrap();
+      state
+         .record_prize(&mut deps.storage, &alice, vec![coin(20, "ukuji"), coin(5, "uusk")])
+         .unwrap();
+      state.record_hit(&mut deps.storage, &bob, &alice).unwrap();
+
+      let stats = state.load(&deps.storage, &alice).unwrap();
+      assert_eq!(stats.rounds_joined, 3);
+      assert_eq!(stats.rounds_exited, 2);
+      assert_eq!(stats.avg_exit_decay, Decimal::permille(375));
+      assert_eq!(stats.paid_in, Uint128::new(350));
+      assert_eq!(stats.paid_out, Uint128::new(75));
+      assert_eq!(stats.prizes, vec![coin(100, "ukuji"), coin(5, "uusk")]);
+      assert_eq!(stats.wins, 1);
+      assert_eq!((stats.hits_given, stats.hits_received), (0, 1));
+
+      let stats = state.load(&deps.storage, &bob).unwrap();
+      assert_eq!((stats.hits_given, stats.hits_received), (1, 0));
+      assert_eq!(stats.rounds_joined, 0);
+   }
+}
@@ -0,0 +1,202 @@
+//! Commit-reveal source of randomness.
+//!
+//! Players commit `sha256(player || secret)` while the game is open and reveal the secret once it
+//! is closed. Every secret is hashed with its commitment and XOR-combined into the seed, so the
+//! outcome is unknown as long as a single player keeps their secret private until the reveal phase
+//! and players sharing a secret do not cancel each other out. Withholding a reveal is the only way
+//! to influence the seed, games must penalise it.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Uint128};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+pub const SEED_LENGTH: usize = 32;
+
+/// Commitment of `player` to `secret`, binding the player so commitments cannot be copied
+pub fn commitment(player: &Addr, secret: &[u8]) -> Binary {
+   let mut hasher = Sha256::new();
+   hasher.update(player.as_bytes());
+   hasher.update(secret);
+   Binary::from(hasher.finalize().to_vec())
+}
+
+pub fn validate_commitment(commitment: &Binary) -> Result<(), RandomnessError> {
+   if commitment.len() != SEED_LENGTH {
+      return Err(RandomnessError::Invalid("commitment".to_string()));
+   }
+   Ok(())
+}
+
+#[cw_serde]
+pub struct CommitReveal {
+   pub seed: Binary,
+   pub reveals: u32,
+}
+
+impl Default for CommitReveal {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
+impl CommitReveal {
+   pub fn new() -> Self {
+      CommitReveal { seed: Binary::from(vec![0u8; SEED_LENGTH]), reveals: 0 }
+   }
+
+   /// Checks `secret` against the commitment of `player` and mixes it into the seed
+   pub fn reveal(
+      &mut self,
+      player: &Addr,
+      commitment: &Binary,
+      secret: &Binary,
+   ) -> Result<(), RandomnessError> {
+      if self::commitment(player, secret) != *commitment {
+         return Err(RandomnessError::InvalidReveal {});
+      }
+
+      let mut hasher = Sha256::new();
+      hasher.update(commitment.as_slice());
+      hasher.update(secret.as_slice());
+      let hash = hasher.finalize();
+      let seed: Vec<u8> = self.seed.iter().zip(hash.iter()).map(|(a, b)| a ^ b).collect();
+      self.seed = Binary::from(seed);
+      self.reveals += 1;
+      Ok(())
+   }
+
+   /// Draws one of `candidates` with a probability proportional to its weight.
+   /// Candidates must be listed in a deterministic order.
+   pub fn pick_weighted<'a, T>(&self, candidates: &'a [(T, Uint128)]) -> Option<&'a T> {
+      if self.reveals == 0 {
+         return None;
+      }
+      let total: u128 = candidates.iter().map(|(_, weight)| weight.u128()).sum();
+      if total == 0 {
+         return None;
+      }
+
+      let mut bytes = [0u8; 16];
+      bytes.copy_from_slice(&self.seed[..16]);
+      let mut target = u128::from_be_bytes(bytes) % total;
+
+      candidates.iter().find_map(|(candidate, weight)| {
+         if target < weight.u128() {
+            return Some(candidate);
+         }
+         target -= weight.u128();
+         None
+      })
+   }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RandomnessError {
+   #[error("InvalidReveal")]
+   InvalidReveal {},
+
+   #[error("Invalid: {0}")]
+   Invalid(String),
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_commitment() {
+      let alice = Addr::unchecked("alice");
+      let bob = Addr::unchecked("bob");
+
+      let commit = commitment(&alice, b"secret");
+      validate_commitment(&commit).unwrap();
+      assert_eq!(commit, commitment(&alice, b"secret"));
+      assert_ne!(commit, commitment(&alice, b"other"));
+      // the same secret commits differently for each player
+      assert_ne!(commit, commitment(&bob, b"secret"));
+
+      validate_commitment(&Binary::from(b"short".to_vec())).unwrap_err();
+   }
+
+   #[test]
+   fn test_reveal() {
+      let alice = Addr::unchecked("alice");
+      let bob = Addr::unchecked("bob");
+      let alice_secret = Binary::from(b"alice secret".to_vec());
+      let bob_secret = Binary::from(b"bob secret".to_vec());
+      let alice_commit = commitment(&alice, &alice_secret);
+      let bob_commit = commitment(&bob, &bob_secret);
+
+      let mut randomness = CommitReveal::new();
+
+      // wrong secret or copied commitment
+      assert_eq!(
+         randomness.reveal(&alice, &alice_commit, &bob_secret),
+         Err(RandomnessError::InvalidReveal {})
+      );
+      assert_eq!(
+         randomness.reveal(&bob, &alice_commit, &alice_secret),
+         Err(RandomnessError::InvalidReveal {})
+      );
+      assert_eq!(randomness, CommitReveal::new());
+
+      randomness.reveal(&alice, &alice_commit, &alice_secret).unwrap();
+      let alice_only = randomness.seed.clone();
+      assert_ne!(alice_only, CommitReveal::new().seed);
+      randomness.reveal(&bob, &bob_commit, &bob_secret).unwrap();
+      assert_eq!(randomness.reveals, 2);
+      assert_ne!(randomness.seed, alice_only);
+
+      // the same secret revealed by two players still moves the seed
+      let mut shared = CommitReveal::new();
+      shared.reveal(&alice, &alice_commit, &alice_secret).unwrap();
+      shared.reveal(&bob, &commitment(&bob, &alice_secret), &alice_secret).unwrap();
+      assert_ne!(shared.seed, CommitReveal::new().seed);
+
+      // XOR is order independent
+      let mut reversed = CommitReveal::new();
+      reversed.reveal(&bob, &bob_commit, &bob_secret).unwrap();
+      reversed.reveal(&alice, &alice_commit, &alice_secret).unwrap();
+      assert_eq!(reversed, randomness);
+   }
+
+   #[test]
+   fn test_pick_weighted() {
+      let candidates =
+         vec![("alice", Uint128::new(1)), ("bob", Uint128::zero()), ("charlie", Uint128::new(3))];
+
+      // nothing revealed => no draw
+      assert_eq!(CommitReveal::new().pick_weighted(&candidates), None);
+
+      let seed = |first: u128| {
+         let mut seed = first.to_be_bytes().to_vec();
+         seed.extend([0xffu8; 16]);
+         CommitReveal { seed: Binary::from(seed), reveals: 1 }
+      };
+
+      // target = first 16 bytes % total weight
+      assert_eq!(seed(0).pick_weighted(&candidates), Some(&"alice"));
+      assert_eq!(seed(1).pick_weighted(&candidates), Some(&"charlie"));
+      assert_eq!(seed(3).pick_weighted(&candidates), Some(&"charlie"));
+      assert_eq!(seed(4).pick_weighted(&candidates), Some(&"alice"));
+      assert_eq!(seed(u128::MAX).pick_weighted(&candidates), Some(&"charlie"));
+
+      // no weight => no draw
+      assert_eq!(seed(0).pick_weighted::<&str>(&[]), None);
+      assert_eq!(seed(0).pick_weighted(&[("bob", Uint128::zero())]), None);
+
+      // draws follow the weights
+      let mut wins = [0u32; 3];
+      for i in 0..400u32 {
+         let mut randomness = CommitReveal::new();
+         let player = Addr::unchecked("player");
+         let secret = i.to_be_bytes();
+         randomness.reveal(&player, &commitment(&player, &secret), &Binary::from(secret)).unwrap();
+         let winner = randomness.pick_weighted(&candidates).unwrap();
+         wins[candidates.iter().position(|(c, _)| c == winner).unwrap()] += 1;
+      }
+      assert_eq!(wins[1], 0);
+      assert!(wins[2] > wins[0] * 2, "{wins:?}");
+   }
+}
@@ -1,6 +1,14 @@
 use cosmwasm_std::{Addr, Api, Coin, StdError};
 use cw_utils::NativeBalance;
 
+pub const DEFAULT_LIMIT: u32 = 30;
+pub const MAX_LIMIT: u32 = 100;
+
+/// Page size of paginated queries, capped to `MAX_LIMIT`
+pub fn page_limit(limit: Option<u32>) -> usize {
+   limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
 pub fn to_addr(addr: String, api: &dyn Api) -> Result<Addr, StdError> {
    let canonical_addr = api.addr_canonicalize(&addr)?;
    api.addr_humanize(&canonical_addr)
@@ -27,4 +35,12 @@ mod tests {
 
       assert_eq!(addr, verify)
    }
+
+   #[test]
+   fn test_page_limit() {
+      assert_eq!(page_limit(None), DEFAULT_LIMIT as usize);
+      assert_eq!(page_limit(Some(0)), 0);
+      assert_eq!(page_limit(Some(5)), 5);
+      assert_eq!(page_limit(Some(MAX_LIMIT + 1)), MAX_LIMIT as usize);
+   }
 }